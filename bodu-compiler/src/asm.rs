@@ -0,0 +1,630 @@
+use bodu_vm::{Instruction, Label, Operator, VarIndex};
+
+use crate::disasm::operator;
+
+// turns the text written by disasm::disassemble back into instructions. comments start with ; and go until the end of the line.
+pub fn assemble(src: &str) -> Result<Vec<Instruction>, String> {
+    let mut p = Parser {
+        chars: src.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let instrs = p.vec_instr(false)?;
+    Ok(instrs)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn err<T>(&self, msg: &str) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, msg))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        if let Some(ch) = ch {
+            self.pos += 1;
+            if ch == '\n' {
+                self.line += 1;
+            }
+        }
+        ch
+    }
+
+    // skips spaces and comments, but not newlines
+    fn skip_space(&mut self) {
+        while let Some(ch) = self.peek() {
+            if ch == ';' {
+                while let Some(ch) = self.peek() {
+                    if ch == '\n' {
+                        break;
+                    }
+                    self.next();
+                }
+            } else if ch.is_whitespace() && ch != '\n' {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    // skips everything that isn't code, including newlines
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_space();
+            if self.peek() == Some('\n') {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), String> {
+        self.skip_space();
+        if self.peek() == Some(ch) {
+            self.next();
+            Ok(())
+        } else {
+            self.err(&format!("expected '{}'", ch))
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_space();
+        match self.peek() {
+            Some('\n') => {
+                self.next();
+                Ok(())
+            },
+            None => Ok(()),
+            _ => self.err("expected the end of the line"),
+        }
+    }
+
+    // reads instructions until the end of the input, or until a } if inside is true
+    fn vec_instr(&mut self, inside: bool) -> Result<Vec<Instruction>, String> {
+        let mut v = Vec::new();
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => {
+                    if inside {
+                        return self.err("expected '}'");
+                    }
+                    return Ok(v);
+                },
+                Some('}') => {
+                    if !inside {
+                        return self.err("unexpected '}'");
+                    }
+                    self.next();
+                    self.end_of_line()?;
+                    return Ok(v);
+                },
+                Some('@') | Some('#') => {
+                    let l = self.label()?;
+                    self.expect(':')?;
+                    self.end_of_line()?;
+                    v.push(Instruction::Label(l));
+                },
+                _ => {
+                    let instr = self.instr()?;
+                    v.push(instr);
+                },
+            }
+        }
+    }
+
+    fn body(&mut self) -> Result<Vec<Instruction>, String> {
+        self.expect('{')?;
+        self.end_of_line()?;
+        self.vec_instr(true)
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        self.skip_space();
+        let mut s = String::new();
+        while let Some(ch) = self.peek() {
+            if ch.is_alphanumeric() || ch == '_' {
+                s.push(ch);
+                self.next();
+            } else {
+                break;
+            }
+        }
+        if s.is_empty() {
+            return self.err("expected a word");
+        }
+        Ok(s)
+    }
+
+    // a number or anything else that ends at a separator, like -12 or 1.5e-7
+    fn literal(&mut self) -> Result<String, String> {
+        self.skip_space();
+        let mut s = String::new();
+        while let Some(ch) = self.peek() {
+            if ch.is_whitespace() || ",;(){}".contains(ch) {
+                break;
+            }
+            s.push(ch);
+            self.next();
+        }
+        if s.is_empty() {
+            return self.err("expected a literal");
+        }
+        Ok(s)
+    }
+
+    fn index(&mut self) -> Result<u64, String> {
+        let mut s = String::new();
+        while let Some(ch) = self.peek() {
+            if !ch.is_ascii_digit() {
+                break;
+            }
+            s.push(ch);
+            self.next();
+        }
+        match s.parse::<u64>() {
+            Ok(i) => Ok(i),
+            Err(_) => self.err("expected an index"),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        if self.peek() == Some('\"') {
+            self.string()
+        } else {
+            self.word()
+        }
+    }
+
+    fn comma(&mut self) -> Result<(), String> {
+        self.expect(',')
+    }
+
+    fn varindex(&mut self) -> Result<VarIndex, String> {
+        self.skip_space();
+        match self.next() {
+            Some('%') => Ok(VarIndex::Temp(self.index()?)),
+            Some('^') => Ok(VarIndex::Arg(self.index()? as usize)),
+            Some('$') => Ok(VarIndex::Ident(self.name()?)),
+            _ => self.err("expected a variable (%temp, ^arg or $ident)"),
+        }
+    }
+
+    fn vec_varindex(&mut self) -> Result<Vec<VarIndex>, String> {
+        self.expect('(')?;
+        let mut v = Vec::new();
+        self.skip_space();
+        if self.peek() == Some(')') {
+            self.next();
+            return Ok(v);
+        }
+        loop {
+            v.push(self.varindex()?);
+            self.skip_space();
+            match self.next() {
+                Some(',') => {},
+                Some(')') => return Ok(v),
+                _ => return self.err("expected ',' or ')'"),
+            }
+        }
+    }
+
+    fn label(&mut self) -> Result<Label, String> {
+        self.skip_space();
+        match self.next() {
+            Some('@') => Ok(Label::Unnamed(self.index()?)),
+            Some('#') => Ok(Label::Named(self.name()?)),
+            _ => self.err("expected a label (@n or #name)"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.skip_space();
+        if self.next() != Some('\"') {
+            return self.err("expected a string");
+        }
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('\"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('\\') => s.push('\\'),
+                    Some('\"') => s.push('\"'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('0') => s.push('\0'),
+                    Some('u') => {
+                        if self.next() != Some('{') {
+                            return self.err("expected '{' after \\u");
+                        }
+                        let mut hex = String::new();
+                        loop {
+                            match self.next() {
+                                Some('}') => break,
+                                Some(ch) => hex.push(ch),
+                                None => return self.err("unfinished string"),
+                            }
+                        }
+                        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            Some(ch) => s.push(ch),
+                            None => return self.err(&format!("invalid character code '{}'", hex)),
+                        }
+                    },
+                    _ => return self.err("invalid escape sequence"),
+                },
+                Some('\n') | None => return self.err("unfinished string"),
+                Some(ch) => s.push(ch),
+            }
+        }
+    }
+
+    fn operator(&mut self) -> Result<Operator, String> {
+        self.expect('[')?;
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some(']') => break,
+                Some('\n') | None => return self.err("expected ']'"),
+                Some(ch) => s.push(ch),
+            }
+        }
+        let ops = [
+            Operator::Plus, Operator::Minus, Operator::Times, Operator::Divide, Operator::Modulus, Operator::OrThat, Operator::Ternary,
+            Operator::EqualTo, Operator::Not, Operator::NotEqualTo, Operator::Less, Operator::LessOrEqual, Operator::Greater,
+            Operator::GreaterOrEqual, Operator::And, Operator::Or, Operator::Xor, Operator::Property, Operator::Tuple, Operator::Pipe,
+            Operator::IsntNull,
+        ];
+        match ops.into_iter().find(|op| operator(*op) == s) {
+            Some(op) => Ok(op),
+            None => self.err(&format!("unknown operator '[{}]'", s)),
+        }
+    }
+
+    fn three(&mut self) -> Result<(VarIndex, VarIndex, VarIndex), String> {
+        let a = self.varindex()?;
+        self.comma()?;
+        let b = self.varindex()?;
+        self.comma()?;
+        let c = self.varindex()?;
+        Ok((a, b, c))
+    }
+
+    fn two(&mut self) -> Result<(VarIndex, VarIndex), String> {
+        let a = self.varindex()?;
+        self.comma()?;
+        let b = self.varindex()?;
+        Ok((a, b))
+    }
+
+    fn instr(&mut self) -> Result<Instruction, String> {
+        let name = self.word()?;
+        let instr = match name.as_str() {
+            "add" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Add(a, b, c)
+            },
+            "return" => Instruction::Return(self.varindex()?),
+            "throw" => Instruction::Throw(self.varindex()?),
+            "call" => {
+                let (a, b) = self.two()?;
+                self.comma()?;
+                Instruction::Call(a, b, self.vec_varindex()?)
+            },
+            "get" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Get(a, b, c)
+            },
+            "multiply" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Multiply(a, b, c)
+            },
+            "negate" => {
+                let (a, b) = self.two()?;
+                Instruction::Negate(a, b)
+            },
+            "subtract" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Subtract(a, b, c)
+            },
+            "has" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Has(a, b, c)
+            },
+            "set" => {
+                let (a, b, c) = self.three()?;
+                self.comma()?;
+                Instruction::Set(a, b, c, self.varindex()?)
+            },
+            "decl" => Instruction::Decl(self.varindex()?),
+            "goto" => Instruction::Goto(self.label()?),
+            "eql" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Eql(a, b, c)
+            },
+            "neql" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Neql(a, b, c)
+            },
+            "gotoif" => {
+                let l = self.label()?;
+                self.comma()?;
+                Instruction::GotoIf(l, self.varindex()?)
+            },
+            "block" => return Ok(Instruction::Block(self.body()?)),
+            "maketuple" => {
+                let a = self.varindex()?;
+                self.comma()?;
+                Instruction::MakeTuple(a, self.vec_varindex()?)
+            },
+            "detuple" => {
+                let a = self.vec_varindex()?;
+                self.comma()?;
+                Instruction::DeTuple(a, self.varindex()?)
+            },
+            "divide" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Divide(a, b, c)
+            },
+            "remainder" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Remainder(a, b, c)
+            },
+            "makebind" => {
+                let (a, b) = self.two()?;
+                Instruction::MakeBind(a, b)
+            },
+            "catch" => {
+                let (a, b) = self.two()?;
+                return Ok(Instruction::Catch(a, b, self.body()?));
+            },
+            "assign" => {
+                let (a, b) = self.two()?;
+                Instruction::Assign(a, b)
+            },
+            "defer" => return Ok(Instruction::Defer(self.body()?)),
+            "boolean" => {
+                let a = self.varindex()?;
+                self.comma()?;
+                match self.word()?.as_str() {
+                    "true" => Instruction::Boolean(a, true),
+                    "false" => Instruction::Boolean(a, false),
+                    _ => return self.err("expected true or false"),
+                }
+            },
+            "number" => {
+                let a = self.varindex()?;
+                self.comma()?;
+                let s = self.literal()?;
+                match s.parse::<i64>() {
+                    Ok(n) => Instruction::Number(a, n),
+                    Err(_) => return self.err(&format!("invalid number '{}'", s)),
+                }
+            },
+            "float" => {
+                let a = self.varindex()?;
+                self.comma()?;
+                let s = self.literal()?;
+                match s.parse::<f64>() {
+                    Ok(n) => Instruction::Float(a, n),
+                    Err(_) => return self.err(&format!("invalid float '{}'", s)),
+                }
+            },
+            "string" => {
+                let a = self.varindex()?;
+                self.comma()?;
+                Instruction::String(a, self.string()?)
+            },
+            "makefunction" => {
+                let a = self.varindex()?;
+                return Ok(Instruction::MakeFunction(a, self.body()?));
+            },
+            "not" => {
+                let (a, b) = self.two()?;
+                Instruction::Not(a, b)
+            },
+            "gt" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Gt(a, b, c)
+            },
+            "ge" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Ge(a, b, c)
+            },
+            "lt" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Lt(a, b, c)
+            },
+            "le" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Le(a, b, c)
+            },
+            "and" => {
+                let (a, b, c) = self.three()?;
+                Instruction::And(a, b, c)
+            },
+            "or" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Or(a, b, c)
+            },
+            "xor" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Xor(a, b, c)
+            },
+            "getpipeshorthand" => Instruction::GetPipeShorthand(self.varindex()?),
+            "setpipeshorthand" => Instruction::SetPipeShorthand(self.varindex()?),
+            "orthat" => {
+                let (a, b, c) = self.three()?;
+                Instruction::OrThat(a, b, c)
+            },
+            "operatorfn" => {
+                let a = self.varindex()?;
+                self.comma()?;
+                Instruction::OperatorFn(a, self.operator()?)
+            },
+            "debug" => Instruction::Debug(self.varindex()?),
+            "release" => Instruction::Release(self.varindex()?),
+            "maybe" => Instruction::Maybe(self.varindex()?),
+            "tonumber" => {
+                let (a, b) = self.two()?;
+                Instruction::ToNumber(a, b)
+            },
+            "iterate" => {
+                let (a, b, c) = self.three()?;
+                Instruction::Iterate(a, b, c)
+            },
+            "probably" => Instruction::Probably(self.varindex()?),
+            "possibly" => Instruction::Possibly(self.varindex()?),
+            "isntnull" => {
+                let (a, b) = self.two()?;
+                Instruction::IsntNull(a, b)
+            },
+            "finally" => {
+                let a = self.body()?;
+                self.skip_blank();
+                return Ok(Instruction::Finally(a, self.body()?));
+            },
+            _ => return self.err(&format!("unknown instruction '{}'", name)),
+        };
+        self.end_of_line()?;
+        Ok(instr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bodu_vm::{Instruction, Label, Operator, VarIndex};
+
+    use crate::{compile_instrs, decompile, disasm::disassemble};
+
+    use super::assemble;
+
+    fn t(i: u64) -> VarIndex {
+        VarIndex::Temp(i)
+    }
+
+    fn id(s: &str) -> VarIndex {
+        VarIndex::Ident(s.to_string())
+    }
+
+    // every instruction, every operator and the names, strings and numbers that need quoting or are at the edges
+    fn every_instruction() -> Vec<Instruction> {
+        let operators = [
+            Operator::Plus, Operator::Minus, Operator::Times, Operator::Divide, Operator::Modulus, Operator::OrThat, Operator::Ternary,
+            Operator::EqualTo, Operator::Not, Operator::NotEqualTo, Operator::Less, Operator::LessOrEqual, Operator::Greater,
+            Operator::GreaterOrEqual, Operator::And, Operator::Or, Operator::Xor, Operator::Property, Operator::Tuple, Operator::Pipe,
+            Operator::IsntNull,
+        ];
+        let mut v = vec![
+            Instruction::Add(t(0), t(1), t(2)),
+            Instruction::Return(t(0)),
+            Instruction::Throw(id("e")),
+            Instruction::Call(t(3), id("print"), vec![t(1), VarIndex::Arg(0), id("a b")]),
+            Instruction::Call(t(3), id("f"), vec![]),
+            Instruction::Get(t(4), id("obj"), t(5)),
+            Instruction::Multiply(t(0), t(1), t(2)),
+            Instruction::Negate(t(0), t(1)),
+            Instruction::Subtract(t(0), t(1), t(2)),
+            Instruction::Has(t(0), t(1), t(2)),
+            Instruction::Set(t(0), t(1), t(2), t(3)),
+            Instruction::Decl(id("x")),
+            Instruction::Decl(id("\"quoted\"\n\tname\\")),
+            Instruction::Label(Label::Named("start".to_string())),
+            Instruction::Label(Label::Named("with space".to_string())),
+            Instruction::Label(Label::Unnamed(7)),
+            Instruction::Goto(Label::Named("start".to_string())),
+            Instruction::Goto(Label::Unnamed(u64::MAX)),
+            Instruction::Eql(t(0), t(1), t(2)),
+            Instruction::Neql(t(0), t(1), t(2)),
+            Instruction::GotoIf(Label::Unnamed(7), t(0)),
+            Instruction::Block(vec![
+                Instruction::Block(vec![]),
+                Instruction::Assign(id("x"), t(1)),
+            ]),
+            Instruction::MakeTuple(t(0), vec![t(1), t(2)]),
+            Instruction::MakeTuple(t(0), vec![]),
+            Instruction::DeTuple(vec![t(1), t(2)], t(0)),
+            Instruction::Divide(t(0), t(1), t(2)),
+            Instruction::Remainder(t(0), t(1), t(2)),
+            Instruction::MakeBind(t(0), t(1)),
+            Instruction::Catch(t(0), t(1), vec![Instruction::Throw(t(2))]),
+            Instruction::Assign(t(0), VarIndex::Arg(usize::MAX)),
+            Instruction::Defer(vec![Instruction::SetPipeShorthand(t(0))]),
+            Instruction::Boolean(t(0), true),
+            Instruction::Boolean(t(0), false),
+            Instruction::Number(t(0), 0),
+            Instruction::Number(t(0), -1),
+            Instruction::Number(t(0), i64::MIN),
+            Instruction::Number(t(0), i64::MAX),
+            Instruction::Float(t(0), 0.1),
+            Instruction::Float(t(0), -0.0),
+            Instruction::Float(t(0), 1e300),
+            Instruction::Float(t(0), f64::INFINITY),
+            Instruction::Float(t(0), f64::NEG_INFINITY),
+            Instruction::Float(t(0), f64::MIN_POSITIVE),
+            Instruction::Float(t(0), f64::NAN),
+            Instruction::String(t(0), String::new()),
+            Instruction::String(t(0), "\"hi\"\\ \n\r\t\0 \u{1} ü 😀".to_string()),
+            Instruction::MakeFunction(t(0), vec![
+                Instruction::Decl(id("a")),
+                Instruction::Assign(id("a"), VarIndex::Arg(0)),
+                Instruction::MakeFunction(t(1), vec![Instruction::Return(t(0))]),
+                Instruction::Return(t(1)),
+            ]),
+            Instruction::Not(t(0), t(1)),
+            Instruction::Gt(t(0), t(1), t(2)),
+            Instruction::Ge(t(0), t(1), t(2)),
+            Instruction::Lt(t(0), t(1), t(2)),
+            Instruction::Le(t(0), t(1), t(2)),
+            Instruction::And(t(0), t(1), t(2)),
+            Instruction::Or(t(0), t(1), t(2)),
+            Instruction::Xor(t(0), t(1), t(2)),
+            Instruction::GetPipeShorthand(t(0)),
+            Instruction::SetPipeShorthand(t(0)),
+            Instruction::OrThat(t(0), t(1), t(2)),
+            Instruction::Debug(t(0)),
+            Instruction::Release(t(0)),
+            Instruction::Maybe(t(0)),
+            Instruction::ToNumber(t(0), t(1)),
+            Instruction::Iterate(t(0), t(1), t(2)),
+            Instruction::Probably(t(0)),
+            Instruction::Possibly(t(0)),
+            Instruction::IsntNull(t(0), t(1)),
+            Instruction::Finally(vec![Instruction::Return(t(0))], vec![Instruction::Label(Label::Unnamed(1))]),
+            Instruction::Finally(vec![], vec![]),
+        ];
+        v.extend(operators.into_iter().map(|op| Instruction::OperatorFn(t(0), op)));
+        v
+    }
+
+    // bytes -> listing -> bytes gives the same bytes, and the listing of the assembled code is the same listing
+    #[test]
+    fn round_trip() {
+        let bytes = compile_instrs(every_instruction(), false, None);
+        let listing = disassemble(&decompile(&bytes).unwrap().instrs);
+        let instrs = assemble(&listing).unwrap();
+        assert_eq!(disassemble(&instrs), listing);
+        assert_eq!(compile_instrs(instrs, false, None), bytes);
+    }
+
+    // comments, blank lines and other spacing don't change what's assembled
+    #[test]
+    fn round_trip_with_comments() {
+        let listing = disassemble(&every_instruction());
+        let commented = listing.lines().map(|line| format!("  {}   ; a comment\n\n", line)).collect::<String>();
+        let instrs = assemble(&format!("; header\n{}", commented)).unwrap();
+        assert_eq!(disassemble(&instrs), listing);
+    }
+}
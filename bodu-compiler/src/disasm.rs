@@ -0,0 +1,177 @@
+use bodu_vm::{Instruction, Label, Operator, VarIndex};
+
+// operands are written as: %0 for temps, ^0 for arguments, $name for identifiers, @0 for unnamed labels and #name for named labels.
+// identifiers and label names that aren't plain words are quoted, like $"a b".
+pub fn disassemble(instrs: &[Instruction]) -> String {
+    let mut s = String::new();
+    write_vec_instr(instrs, 0, &mut s);
+    s
+}
+
+fn write_vec_instr(instrs: &[Instruction], indent: usize, s: &mut String) {
+    for i in instrs {
+        write_instr(i, indent, s);
+    }
+}
+
+fn write_block(name: &str, operands: &[String], body: &[Instruction], indent: usize, s: &mut String) {
+    write_line(name, operands, indent, s);
+    s.pop(); // the newline
+    s.push_str(" {\n");
+    write_vec_instr(body, indent + 1, s);
+    s.push_str(&"    ".repeat(indent));
+    s.push_str("}\n");
+}
+
+fn write_line(name: &str, operands: &[String], indent: usize, s: &mut String) {
+    s.push_str(&"    ".repeat(indent));
+    s.push_str(name);
+    if !operands.is_empty() {
+        s.push(' ');
+        s.push_str(&operands.join(", "));
+    }
+    s.push('\n');
+}
+
+fn write_instr(instr: &Instruction, indent: usize, s: &mut String) {
+    let v = varindex;
+    match instr {
+        Instruction::Add(result, op1, op2) => write_line("add", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Return(op) => write_line("return", &[v(op)], indent, s),
+        Instruction::Throw(op) => write_line("throw", &[v(op)], indent, s),
+        Instruction::Call(result, f, args) => write_line("call", &[v(result), v(f), vec_varindex(args)], indent, s),
+        Instruction::Get(result, obj, prop) => write_line("get", &[v(result), v(obj), v(prop)], indent, s),
+        Instruction::Multiply(result, op1, op2) => write_line("multiply", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Negate(result, op) => write_line("negate", &[v(result), v(op)], indent, s),
+        Instruction::Subtract(result, op1, op2) => write_line("subtract", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Has(result, obj, prop) => write_line("has", &[v(result), v(obj), v(prop)], indent, s),
+        Instruction::Set(result, obj, prop, value) => write_line("set", &[v(result), v(obj), v(prop), v(value)], indent, s),
+        Instruction::Decl(op) => write_line("decl", &[v(op)], indent, s),
+        Instruction::Label(l) => write_line(&format!("{}:", label(l)), &[], indent, s),
+        Instruction::Goto(l) => write_line("goto", &[label(l)], indent, s),
+        Instruction::Eql(result, op1, op2) => write_line("eql", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Neql(result, op1, op2) => write_line("neql", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::GotoIf(l, op) => write_line("gotoif", &[label(l), v(op)], indent, s),
+        Instruction::Block(body) => write_block("block", &[], body, indent, s),
+        Instruction::MakeTuple(result, ops) => write_line("maketuple", &[v(result), vec_varindex(ops)], indent, s),
+        Instruction::DeTuple(results, op) => write_line("detuple", &[vec_varindex(results), v(op)], indent, s),
+        Instruction::Divide(result, op1, op2) => write_line("divide", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Remainder(result, op1, op2) => write_line("remainder", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::MakeBind(result, f) => write_line("makebind", &[v(result), v(f)], indent, s),
+        Instruction::Catch(iserr, err, body) => write_block("catch", &[v(iserr), v(err)], body, indent, s),
+        Instruction::Assign(result, op) => write_line("assign", &[v(result), v(op)], indent, s),
+        Instruction::Defer(body) => write_block("defer", &[], body, indent, s),
+        Instruction::Boolean(result, op) => write_line("boolean", &[v(result), op.to_string()], indent, s),
+        Instruction::Number(result, op) => write_line("number", &[v(result), op.to_string()], indent, s),
+        Instruction::Float(result, op) => write_line("float", &[v(result), format!("{:?}", op)], indent, s),
+        Instruction::String(result, op) => write_line("string", &[v(result), quote(op)], indent, s),
+        Instruction::MakeFunction(result, body) => write_block("makefunction", &[v(result)], body, indent, s),
+        Instruction::Not(result, op) => write_line("not", &[v(result), v(op)], indent, s),
+        Instruction::Gt(result, op1, op2) => write_line("gt", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Ge(result, op1, op2) => write_line("ge", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Lt(result, op1, op2) => write_line("lt", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Le(result, op1, op2) => write_line("le", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::And(result, op1, op2) => write_line("and", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Or(result, op1, op2) => write_line("or", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::Xor(result, op1, op2) => write_line("xor", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::GetPipeShorthand(result) => write_line("getpipeshorthand", &[v(result)], indent, s),
+        Instruction::SetPipeShorthand(op) => write_line("setpipeshorthand", &[v(op)], indent, s),
+        Instruction::OrThat(result, op1, op2) => write_line("orthat", &[v(result), v(op1), v(op2)], indent, s),
+        Instruction::OperatorFn(result, opfn) => write_line("operatorfn", &[v(result), format!("[{}]", operator(*opfn))], indent, s),
+        Instruction::Debug(result) => write_line("debug", &[v(result)], indent, s),
+        Instruction::Release(result) => write_line("release", &[v(result)], indent, s),
+        Instruction::Maybe(result) => write_line("maybe", &[v(result)], indent, s),
+        Instruction::ToNumber(result, op) => write_line("tonumber", &[v(result), v(op)], indent, s),
+        Instruction::Iterate(r1, r2, it) => write_line("iterate", &[v(r1), v(r2), v(it)], indent, s),
+        Instruction::Probably(result) => write_line("probably", &[v(result)], indent, s),
+        Instruction::Possibly(result) => write_line("possibly", &[v(result)], indent, s),
+        Instruction::IsntNull(result, op) => write_line("isntnull", &[v(result), v(op)], indent, s),
+        Instruction::Finally(body, finally) => {
+            // the finally block goes right after the block, on its own braces
+            write_block("finally", &[], body, indent, s);
+            s.push_str(&"    ".repeat(indent));
+            s.push_str("{\n");
+            write_vec_instr(finally, indent + 1, s);
+            s.push_str(&"    ".repeat(indent));
+            s.push_str("}\n");
+        },
+    }
+}
+
+fn varindex(v: &VarIndex) -> String {
+    match v {
+        VarIndex::Arg(i) => format!("^{}", i),
+        VarIndex::Ident(s) => format!("${}", name(s)),
+        VarIndex::Temp(i) => format!("%{}", i),
+    }
+}
+
+fn vec_varindex(v: &[VarIndex]) -> String {
+    format!("({})", v.iter().map(varindex).collect::<Vec<_>>().join(", "))
+}
+
+fn label(l: &Label) -> String {
+    match l {
+        Label::Named(s) => format!("#{}", name(s)),
+        Label::Unnamed(i) => format!("@{}", i),
+    }
+}
+
+fn name(s: &str) -> String {
+    if is_word(s) {
+        s.to_string()
+    } else {
+        quote(s)
+    }
+}
+
+pub(crate) fn is_word(s: &str) -> bool {
+    match s.chars().next() {
+        Some(ch) if ch.is_alphabetic() || ch == '_' => s.chars().all(|ch| ch.is_alphanumeric() || ch == '_'),
+        _ => false,
+    }
+}
+
+pub(crate) fn quote(s: &str) -> String {
+    let mut r = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '\\' => r.push_str("\\\\"),
+            '\"' => r.push_str("\\\""),
+            '\n' => r.push_str("\\n"),
+            '\r' => r.push_str("\\r"),
+            '\t' => r.push_str("\\t"),
+            '\0' => r.push_str("\\0"),
+            ch if ch.is_control() => r.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+            ch => r.push(ch),
+        }
+    }
+    r.push('\"');
+    r
+}
+
+pub(crate) fn operator(op: Operator) -> &'static str {
+    match op {
+        Operator::Plus => "+",
+        Operator::Minus => "-",
+        Operator::Times => "*",
+        Operator::Divide => "/",
+        Operator::Modulus => "%",
+        Operator::OrThat => "??",
+        Operator::Ternary => "?:",
+        Operator::EqualTo => "==",
+        Operator::Not => "!",
+        Operator::NotEqualTo => "!=",
+        Operator::Less => "<",
+        Operator::LessOrEqual => "<=",
+        Operator::Greater => ">",
+        Operator::GreaterOrEqual => ">=",
+        Operator::And => "&",
+        Operator::Or => "|",
+        Operator::Xor => "^",
+        Operator::Property => ".",
+        Operator::Tuple => ",",
+        Operator::Pipe => "|>",
+        Operator::IsntNull => "?",
+    }
+}
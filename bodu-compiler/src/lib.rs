@@ -1,154 +1,220 @@
-use bodu_vm::{Instruction, Label, Operator, VarIndex};
+use std::{collections::HashMap, sync::Arc};
 
-pub fn compile_instrs(instrs: Vec<Instruction>) -> Vec<u8> {
+use bodu_vm::{DebugInfo, Instruction, Label, Operator, Source, Span, VarIndex};
+
+pub mod asm; // the inverse of disasm
+pub mod disasm; // human readable listings of instructions
+
+pub const MAGIC: [u8; 4] = [0x0, b'B', b'D', b'U']; // the first bytes of every bytecode file, a source file can't start with a null byte
+pub const FORMAT_VERSION: u16 = 4; // bump this whenever the encoding of instructions changes
+
+// a decoded bytecode file
+#[derive(Clone, Debug)]
+pub struct Bytecode {
+    pub version: u16, // format version
+    pub bodu_version: String, // version of bodu that compiled the file
+    pub debug: bool, // whether it was compiled in debug mode
+    pub instrs: Vec<Instruction>,
+    pub dbg: Option<DebugInfo>, // the source and the spans of the instructions, if they were compiled with them
+}
+
+const FLAG_DEBUG: u8 = 1;
+const FLAG_DEBUG_INFO: u8 = 2;
+
+// file layout: magic, format version (u16), flags (u8), bodu version, payload length (u64), payload hash (u64), payload
+// payload layout: string table, instructions, debug info if the flag for it is set. strings in instructions and the debug
+// info are indices into the table
+pub fn compile_instrs(instrs: Vec<Instruction>, debug: bool, dbg: Option<&DebugInfo>) -> Vec<u8> {
+    let mut st = StringTable::default();
+    let mut body = Vec::new();
+    write_vec_instr(instrs, &mut body, &mut st);
+    if let Some(dbg) = dbg {
+        write_string(dbg.source.name.clone(), &mut body, &mut st);
+        write_string(dbg.source.contents.clone(), &mut body, &mut st);
+        write_debug_info(dbg, &mut body, &mut st);
+    }
+    let mut payload = Vec::new();
+    write_varint(st.strings.len() as u64, &mut payload);
+    for s in st.strings {
+        write_raw_string(s, &mut payload);
+    }
+    payload.extend_from_slice(&body);
     let mut v = Vec::new();
-    write_vec_instr(instrs, &mut v);
+    v.extend_from_slice(&MAGIC);
+    v.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    let mut flags = 0;
+    if debug {
+        flags |= FLAG_DEBUG;
+    }
+    if dbg.is_some() {
+        flags |= FLAG_DEBUG_INFO;
+    }
+    v.push(flags);
+    write_raw_string(env!("CARGO_PKG_VERSION").to_string(), &mut v);
+    write_u64(payload.len() as u64, &mut v);
+    write_u64(hash(&payload), &mut v);
+    v.extend_from_slice(&payload);
     v
 }
 
-fn write_vec_instr(instrs: Vec<Instruction>, vec_: &mut Vec<u8>) {
+// checks if the bytes look like a bytecode file (they may still be invalid)
+pub fn is_bytecode(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+// 64-bit FNV-1a
+fn hash(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+fn write_vec_instr(instrs: Vec<Instruction>, vec_: &mut Vec<u8>, st: &mut StringTable) {
     for i in instrs {
-        write_instr(i, vec_);
+        write_instr(i, vec_, st);
     }
     vec_.push(0x0);
 }
 
-fn write_instr(instr: Instruction, vec_: &mut Vec<u8>) {
+fn write_instr(instr: Instruction, vec_: &mut Vec<u8>, st: &mut StringTable) {
     match instr {
         Instruction::Add(result, op1, op2) => {
             vec_.push(0x1);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Return(op) => {
             vec_.push(0x2);
-            write_varindex(op, vec_);
+            write_varindex(op, vec_, st);
         },
         Instruction::Throw(op) => {
             vec_.push(0x3);
-            write_varindex(op, vec_);
+            write_varindex(op, vec_, st);
         },
         Instruction::Call(result, f, args) => {
             vec_.push(0x4);
-            write_varindex(result, vec_);
-            write_varindex(f, vec_);
-            write_vec_varindex(args, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(f, vec_, st);
+            write_vec_varindex(args, vec_, st);
         },
         Instruction::Get(result, obj, prop) => {
             vec_.push(0x5);
-            write_varindex(result, vec_);
-            write_varindex(obj, vec_);
-            write_varindex(prop, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(obj, vec_, st);
+            write_varindex(prop, vec_, st);
         },
         Instruction::Multiply(result, op1, op2) => {
             vec_.push(0x6);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Negate(result, op) => {
             vec_.push(0x7);
-            write_varindex(result, vec_);
-            write_varindex(op, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op, vec_, st);
         },
         Instruction::Subtract(result, op1, op2) => {
             vec_.push(0x8);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Has(result, obj, prop) => {
             vec_.push(0x9);
-            write_varindex(result, vec_);
-            write_varindex(obj, vec_);
-            write_varindex(prop, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(obj, vec_, st);
+            write_varindex(prop, vec_, st);
         },
         Instruction::Set(result, obj, prop, value) => {
             vec_.push(0xA);
-            write_varindex(result, vec_);
-            write_varindex(obj, vec_);
-            write_varindex(prop, vec_);
-            write_varindex(value, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(obj, vec_, st);
+            write_varindex(prop, vec_, st);
+            write_varindex(value, vec_, st);
         },
         Instruction::Decl(op) => {
             vec_.push(0xB);
-            write_varindex(op, vec_);
+            write_varindex(op, vec_, st);
         },
         Instruction::Label(l) => {
             vec_.push(0xC);
-            write_label(l, vec_);
+            write_label(l, vec_, st);
         },
         Instruction::Goto(l) => {
             vec_.push(0xD);
-            write_label(l, vec_);
+            write_label(l, vec_, st);
         },
         Instruction::Eql(result, op1, op2) => {
             vec_.push(0xE);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Neql(result, op1, op2) => {
             vec_.push(0xF);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::GotoIf(l, op) => {
             vec_.push(0x10);
-            write_label(l, vec_);
-            write_varindex(op, vec_);
+            write_label(l, vec_, st);
+            write_varindex(op, vec_, st);
         },
         Instruction::Block(instrs) => {
             vec_.push(0x11);
-            write_vec_instr(instrs, vec_);
+            write_vec_instr(instrs, vec_, st);
         },
         Instruction::MakeTuple(result, ops) => {
             vec_.push(0x12);
-            write_varindex(result, vec_);
-            write_vec_varindex(ops, vec_);
+            write_varindex(result, vec_, st);
+            write_vec_varindex(ops, vec_, st);
         },
         Instruction::DeTuple(results, op) => {
             vec_.push(0x13);
-            write_vec_varindex(results, vec_);
-            write_varindex(op, vec_);
+            write_vec_varindex(results, vec_, st);
+            write_varindex(op, vec_, st);
         },
         Instruction::Divide(result, op1, op2) => {
             vec_.push(0x14);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Remainder(result, op1, op2) => {
             vec_.push(0x15);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::MakeBind(result, f) => {
             vec_.push(0x16);
-            write_varindex(result, vec_);
-            write_varindex(f, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(f, vec_, st);
         },
         Instruction::Catch(iserr, err, block) => {
             vec_.push(0x17);
-            write_varindex(iserr, vec_);
-            write_varindex(err, vec_);
-            write_vec_instr(block, vec_);
+            write_varindex(iserr, vec_, st);
+            write_varindex(err, vec_, st);
+            write_vec_instr(block, vec_, st);
         },
         Instruction::Assign(result, op) => {
             vec_.push(0x18);
-            write_varindex(result, vec_);
-            write_varindex(op, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op, vec_, st);
         },
         Instruction::Defer(block) => {
             vec_.push(0x19);
-            write_vec_instr(block, vec_);
+            write_vec_instr(block, vec_, st);
         },
         Instruction::Boolean(result, op) => {
             vec_.push(0x1A);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
             vec_.push(if op {
                 1
             } else {
@@ -157,135 +223,170 @@ fn write_instr(instr: Instruction, vec_: &mut Vec<u8>) {
         },
         Instruction::Number(result, op) => {
             vec_.push(0x1B);
-            write_varindex(result, vec_);
-            let op = op.to_le_bytes();
-            vec_.extend_from_slice(&op);
+            write_varindex(result, vec_, st);
+            write_varint(((op << 1) ^ (op >> 63)) as u64, vec_); // zigzag, so small negative numbers stay small
         },
         Instruction::Float(result, op) => {
             vec_.push(0x1C);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
             let op = op.to_le_bytes();
             vec_.extend_from_slice(&op);
         },
         Instruction::String(result, op) => {
             vec_.push(0x1D);
-            write_varindex(result, vec_);
-            write_string(op, vec_);
+            write_varindex(result, vec_, st);
+            write_string(op, vec_, st);
         },
         Instruction::MakeFunction(result, body) => {
             vec_.push(0x1E);
-            write_varindex(result, vec_);
-            write_vec_instr(body, vec_);
+            write_varindex(result, vec_, st);
+            write_vec_instr(body, vec_, st);
         },
         Instruction::Not(result, op) => {
             vec_.push(0x1F);
-            write_varindex(result, vec_);
-            write_varindex(op, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op, vec_, st);
         },
         Instruction::Gt(result, op1, op2) => {
             vec_.push(0x20);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Ge(result, op1, op2) => {
             vec_.push(0x21);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Lt(result, op1, op2) => {
             vec_.push(0x22);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Le(result, op1, op2) => {
             vec_.push(0x23);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::And(result, op1, op2) => {
             vec_.push(0x24);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Or(result, op1, op2) => {
             vec_.push(0x25);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::Xor(result, op1, op2) => {
             vec_.push(0x26);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::GetPipeShorthand(result) => {
             vec_.push(0x27);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
         },
         Instruction::SetPipeShorthand(op) => {
             vec_.push(0x28);
-            write_varindex(op, vec_);
+            write_varindex(op, vec_, st);
         },
         Instruction::OrThat(result, op1, op2) => {
             vec_.push(0x29);
-            write_varindex(result, vec_);
-            write_varindex(op1, vec_);
-            write_varindex(op2, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op1, vec_, st);
+            write_varindex(op2, vec_, st);
         },
         Instruction::OperatorFn(result, opfn) => {
             vec_.push(0x2A);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
             write_operator(opfn, vec_);
         },
         Instruction::Debug(result) => {
             vec_.push(0x2B);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
         },
         Instruction::Release(result) => {
             vec_.push(0x2C);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
         },
         Instruction::Maybe(result) => {
             vec_.push(0x2D);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
         },
         Instruction::ToNumber(result, op) => {
             vec_.push(0x2E);
-            write_varindex(result, vec_);
-            write_varindex(op, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op, vec_, st);
         },
         Instruction::Iterate(r1, r2, it) => {
             vec_.push(0x2F);
-            write_varindex(r1, vec_);
-            write_varindex(r2, vec_);
-            write_varindex(it, vec_);
+            write_varindex(r1, vec_, st);
+            write_varindex(r2, vec_, st);
+            write_varindex(it, vec_, st);
         },
         Instruction::Probably(result) => {
             vec_.push(0x30);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
         },
         Instruction::Possibly(result) => {
             vec_.push(0x31);
-            write_varindex(result, vec_);
+            write_varindex(result, vec_, st);
         },
         Instruction::IsntNull(result, op) => {
             vec_.push(0x32);
-            write_varindex(result, vec_);
-            write_varindex(op, vec_);
+            write_varindex(result, vec_, st);
+            write_varindex(op, vec_, st);
+        },
+        Instruction::Finally(block, finally) => {
+            vec_.push(0x33);
+            write_vec_instr(block, vec_, st);
+            write_vec_instr(finally, vec_, st);
         },
     }
 }
 
-fn write_vec_varindex(v: Vec<VarIndex>, vec_: &mut Vec<u8>) {
-    write_u64(v.len() as u64, vec_);
+// the source is written once before the tree, every block has the same one
+fn write_debug_info(dbg: &DebugInfo, vec_: &mut Vec<u8>, st: &mut StringTable) {
+    match &dbg.name {
+        None => vec_.push(0x0),
+        Some(name) => {
+            vec_.push(0x1);
+            write_string(name.clone(), vec_, st);
+        },
+    }
+    write_varint(dbg.spans.len() as u64, vec_);
+    for span in dbg.spans.iter() {
+        match span {
+            None => vec_.push(0x0),
+            Some(span) => {
+                vec_.push(0x1);
+                write_varint(span.start as u64, vec_);
+                write_varint(span.end as u64, vec_);
+                write_varint(span.line as u64, vec_);
+                write_varint(span.col as u64, vec_);
+            },
+        }
+    }
+    let mut children = dbg.children.iter().collect::<Vec<_>>();
+    children.sort_by_key(|(i, _)| **i); // so the same code always gives the same bytes
+    write_varint(children.len() as u64, vec_);
+    for (i, child) in children {
+        write_varint(*i as u64, vec_);
+        write_debug_info(child, vec_, st);
+    }
+}
+
+fn write_vec_varindex(v: Vec<VarIndex>, vec_: &mut Vec<u8>, st: &mut StringTable) {
+    write_varint(v.len() as u64, vec_);
     for i in v {
-        write_varindex(i, vec_);
+        write_varindex(i, vec_, st);
     }
 }
 
@@ -294,42 +395,75 @@ fn write_u64(i: u64, vec_: &mut Vec<u8>) {
     vec_.extend_from_slice(&i);
 }
 
-fn write_string(s: String, vec_: &mut Vec<u8>) {
+// LEB128
+fn write_varint(mut i: u64, vec_: &mut Vec<u8>) {
+    while i >= 0x80 {
+        vec_.push((i as u8) | 0x80);
+        i >>= 7;
+    }
+    vec_.push(i as u8);
+}
+
+fn write_raw_string(s: String, vec_: &mut Vec<u8>) {
     let s = s.as_bytes();
-    write_u64(s.len() as u64, vec_);
+    write_varint(s.len() as u64, vec_);
     vec_.extend_from_slice(s);
 }
 
-fn write_varindex(v: VarIndex, vec_: &mut Vec<u8>) {
+fn write_string(s: String, vec_: &mut Vec<u8>, st: &mut StringTable) {
+    let i = st.index(s);
+    write_varint(i, vec_);
+}
+
+fn write_varindex(v: VarIndex, vec_: &mut Vec<u8>, st: &mut StringTable) {
     match v {
         VarIndex::Arg(v) => {
             vec_.push(0x0);
-            write_u64(v as u64, vec_);
+            write_varint(v as u64, vec_);
         },
         VarIndex::Ident(s) => {
             vec_.push(0x1);
-            write_string(s, vec_);
+            write_string(s, vec_, st);
         },
         VarIndex::Temp(v) => {
             vec_.push(0x2);
-            write_u64(v, vec_);
+            write_varint(v, vec_);
         },
     }
 }
 
-fn write_label(l: Label, vec_: &mut Vec<u8>) {
+fn write_label(l: Label, vec_: &mut Vec<u8>, st: &mut StringTable) {
     match l {
         Label::Named(s) => {
             vec_.push(0x0);
-            write_string(s, vec_);
+            write_string(s, vec_, st);
         },
         Label::Unnamed(i) => {
             vec_.push(0x1);
-            write_u64(i, vec_);
+            write_varint(i, vec_);
         },
     }
 }
 
+// deduplicated strings of a bytecode file
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, u64>,
+}
+
+impl StringTable {
+    fn index(&mut self, s: String) -> u64 {
+        if let Some(i) = self.indices.get(&s) {
+            return *i;
+        }
+        let i = self.strings.len() as u64;
+        self.strings.push(s.clone());
+        self.indices.insert(s, i);
+        i
+    }
+}
+
 fn write_operator(op: Operator, vec_: &mut Vec<u8>) {
     vec_.push(match op {
         Operator::Plus => 0x0,
@@ -354,4 +488,341 @@ fn write_operator(op: Operator, vec_: &mut Vec<u8>) {
         Operator::Pipe => 0x13,
         Operator::IsntNull => 0x14,
     });
-}
\ No newline at end of file
+}
+
+// reads bytes produced by compile_instrs back, checking the header and the hash first
+pub fn decompile(bytes: &[u8]) -> Result<Bytecode, String> {
+    if !is_bytecode(bytes) {
+        return Err("not a bodu bytecode file".to_string());
+    }
+    let mut i = MAGIC.len();
+    let version = u16::from_le_bytes(read_array(bytes, &mut i)?);
+    if version != FORMAT_VERSION {
+        return Err(format!("incompatible bytecode format version {} (this bodu reads version {})", version, FORMAT_VERSION));
+    }
+    let flags = read_u8(bytes, &mut i)?;
+    let bodu_version = read_raw_string(bytes, &mut i)?;
+    let len = read_u64(bytes, &mut i)?;
+    let h = read_u64(bytes, &mut i)?;
+    let payload = &bytes[i..];
+    if (payload.len() as u64) < len {
+        return Err(format!("truncated bytecode file (expected {} bytes of instructions, found {})", len, payload.len()));
+    }
+    if (payload.len() as u64) > len {
+        return Err(format!("unexpected data after the instructions at byte {}", i as u64 + len));
+    }
+    if hash(payload) != h {
+        return Err("bytecode file is corrupted (hash mismatch)".to_string());
+    }
+    let mut j = 0;
+    let count = read_varint(payload, &mut j)?;
+    let mut st = Vec::new();
+    for _ in 0..count {
+        st.push(read_raw_string(payload, &mut j)?);
+    }
+    let instrs = read_vec_instr(payload, &mut j, &st)?;
+    let dbg = if flags & FLAG_DEBUG_INFO != 0 {
+        let source = Source::new(read_string(payload, &mut j, &st)?, read_string(payload, &mut j, &st)?);
+        Some(read_debug_info(payload, &mut j, &st, &source)?)
+    } else {
+        None
+    };
+    if j != payload.len() {
+        return Err(format!("unexpected data after the instructions at byte {}", i + j));
+    }
+    Ok(Bytecode {
+        version,
+        bodu_version,
+        debug: flags & FLAG_DEBUG != 0,
+        instrs,
+        dbg,
+    })
+}
+
+fn read_debug_info(bytes: &[u8], i: &mut usize, st: &[String], source: &Arc<Source>) -> Result<DebugInfo, String> {
+    let name = match read_u8(bytes, i)? {
+        0x0 => None,
+        0x1 => Some(read_string(bytes, i, st)?),
+        t => return Err(format!("invalid function name type 0x{:X} at byte {}", t, *i - 1)),
+    };
+    let mut dbg = DebugInfo::new(source.clone(), name);
+    let len = read_varint(bytes, i)?;
+    for _ in 0..len {
+        dbg.spans.push(match read_u8(bytes, i)? {
+            0x0 => None,
+            0x1 => Some(Span {
+                start: read_varint(bytes, i)? as usize,
+                end: read_varint(bytes, i)? as usize,
+                line: read_varint(bytes, i)? as usize,
+                col: read_varint(bytes, i)? as usize,
+            }),
+            t => return Err(format!("invalid span type 0x{:X} at byte {}", t, *i - 1)),
+        });
+    }
+    let len = read_varint(bytes, i)?;
+    for _ in 0..len {
+        let j = read_varint(bytes, i)? as usize;
+        dbg.children.insert(j, Arc::new(read_debug_info(bytes, i, st, source)?));
+    }
+    Ok(dbg)
+}
+
+fn read_vec_instr(bytes: &[u8], i: &mut usize, st: &[String]) -> Result<Vec<Instruction>, String> {
+    let mut v = Vec::new();
+    loop {
+        let op = read_u8(bytes, i)?;
+        if op == 0x0 {
+            break;
+        }
+        v.push(read_instr(op, bytes, i, st)?);
+    }
+    Ok(v)
+}
+
+fn read_instr(op: u8, bytes: &[u8], i: &mut usize, st: &[String]) -> Result<Instruction, String> {
+    Ok(match op {
+        0x1 => Instruction::Add(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x2 => Instruction::Return(read_varindex(bytes, i, st)?),
+        0x3 => Instruction::Throw(read_varindex(bytes, i, st)?),
+        0x4 => Instruction::Call(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_vec_varindex(bytes, i, st)?),
+        0x5 => Instruction::Get(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x6 => Instruction::Multiply(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x7 => Instruction::Negate(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x8 => Instruction::Subtract(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x9 => Instruction::Has(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0xA => Instruction::Set(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0xB => Instruction::Decl(read_varindex(bytes, i, st)?),
+        0xC => Instruction::Label(read_label(bytes, i, st)?),
+        0xD => Instruction::Goto(read_label(bytes, i, st)?),
+        0xE => Instruction::Eql(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0xF => Instruction::Neql(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x10 => Instruction::GotoIf(read_label(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x11 => Instruction::Block(read_vec_instr(bytes, i, st)?),
+        0x12 => Instruction::MakeTuple(read_varindex(bytes, i, st)?, read_vec_varindex(bytes, i, st)?),
+        0x13 => Instruction::DeTuple(read_vec_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x14 => Instruction::Divide(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x15 => Instruction::Remainder(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x16 => Instruction::MakeBind(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x17 => Instruction::Catch(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_vec_instr(bytes, i, st)?),
+        0x18 => Instruction::Assign(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x19 => Instruction::Defer(read_vec_instr(bytes, i, st)?),
+        0x1A => Instruction::Boolean(read_varindex(bytes, i, st)?, read_u8(bytes, i)? != 0),
+        0x1B => Instruction::Number(read_varindex(bytes, i, st)?, {
+            let n = read_varint(bytes, i)?;
+            ((n >> 1) as i64) ^ -((n & 1) as i64)
+        }),
+        0x1C => Instruction::Float(read_varindex(bytes, i, st)?, f64::from_le_bytes(read_array(bytes, i)?)),
+        0x1D => Instruction::String(read_varindex(bytes, i, st)?, read_string(bytes, i, st)?),
+        0x1E => Instruction::MakeFunction(read_varindex(bytes, i, st)?, read_vec_instr(bytes, i, st)?),
+        0x1F => Instruction::Not(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x20 => Instruction::Gt(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x21 => Instruction::Ge(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x22 => Instruction::Lt(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x23 => Instruction::Le(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x24 => Instruction::And(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x25 => Instruction::Or(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x26 => Instruction::Xor(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x27 => Instruction::GetPipeShorthand(read_varindex(bytes, i, st)?),
+        0x28 => Instruction::SetPipeShorthand(read_varindex(bytes, i, st)?),
+        0x29 => Instruction::OrThat(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x2A => Instruction::OperatorFn(read_varindex(bytes, i, st)?, read_operator(bytes, i)?),
+        0x2B => Instruction::Debug(read_varindex(bytes, i, st)?),
+        0x2C => Instruction::Release(read_varindex(bytes, i, st)?),
+        0x2D => Instruction::Maybe(read_varindex(bytes, i, st)?),
+        0x2E => Instruction::ToNumber(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x2F => Instruction::Iterate(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x30 => Instruction::Probably(read_varindex(bytes, i, st)?),
+        0x31 => Instruction::Possibly(read_varindex(bytes, i, st)?),
+        0x32 => Instruction::IsntNull(read_varindex(bytes, i, st)?, read_varindex(bytes, i, st)?),
+        0x33 => Instruction::Finally(read_vec_instr(bytes, i, st)?, read_vec_instr(bytes, i, st)?),
+        _ => return Err(format!("invalid opcode 0x{:X} at byte {}", op, *i - 1)),
+    })
+}
+
+fn read_u8(bytes: &[u8], i: &mut usize) -> Result<u8, String> {
+    match bytes.get(*i) {
+        Some(b) => {
+            *i += 1;
+            Ok(*b)
+        },
+        None => Err("unexpected end of bytecode".to_string()),
+    }
+}
+
+fn read_array<const N: usize>(bytes: &[u8], i: &mut usize) -> Result<[u8; N], String> {
+    match bytes.get(*i..*i + N) {
+        Some(b) => {
+            *i += N;
+            Ok(b.try_into().unwrap())
+        },
+        None => Err("unexpected end of bytecode".to_string()),
+    }
+}
+
+fn read_vec_varindex(bytes: &[u8], i: &mut usize, st: &[String]) -> Result<Vec<VarIndex>, String> {
+    let len = read_varint(bytes, i)?;
+    let mut v = Vec::new();
+    for _ in 0..len {
+        v.push(read_varindex(bytes, i, st)?);
+    }
+    Ok(v)
+}
+
+fn read_u64(bytes: &[u8], i: &mut usize) -> Result<u64, String> {
+    Ok(u64::from_le_bytes(read_array(bytes, i)?))
+}
+
+fn read_varint(bytes: &[u8], i: &mut usize) -> Result<u64, String> {
+    let start = *i;
+    let mut v: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let b = read_u8(bytes, i)?;
+        if shift >= 64 || (shift == 63 && b > 1) {
+            return Err(format!("invalid varint at byte {}", start));
+        }
+        v |= ((b & 0x7F) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(v);
+        }
+        shift += 7;
+    }
+}
+
+fn read_raw_string(bytes: &[u8], i: &mut usize) -> Result<String, String> {
+    let len = read_varint(bytes, i)? as usize;
+    let s = match bytes.get(*i..(*i).saturating_add(len)) {
+        Some(s) => s,
+        None => return Err("unexpected end of bytecode".to_string()),
+    };
+    *i += len;
+    match String::from_utf8(s.to_vec()) {
+        Ok(s) => Ok(s),
+        Err(_) => Err(format!("invalid utf-8 string at byte {}", *i - len)),
+    }
+}
+
+fn read_string(bytes: &[u8], i: &mut usize, st: &[String]) -> Result<String, String> {
+    let start = *i;
+    let idx = read_varint(bytes, i)?;
+    match st.get(idx as usize) {
+        Some(s) => Ok(s.clone()),
+        None => Err(format!("invalid string index {} at byte {}", idx, start)),
+    }
+}
+
+fn read_varindex(bytes: &[u8], i: &mut usize, st: &[String]) -> Result<VarIndex, String> {
+    match read_u8(bytes, i)? {
+        0x0 => Ok(VarIndex::Arg(read_varint(bytes, i)? as usize)),
+        0x1 => Ok(VarIndex::Ident(read_string(bytes, i, st)?)),
+        0x2 => Ok(VarIndex::Temp(read_varint(bytes, i)?)),
+        t => Err(format!("invalid variable index type 0x{:X} at byte {}", t, *i - 1)),
+    }
+}
+
+fn read_label(bytes: &[u8], i: &mut usize, st: &[String]) -> Result<Label, String> {
+    match read_u8(bytes, i)? {
+        0x0 => Ok(Label::Named(read_string(bytes, i, st)?)),
+        0x1 => Ok(Label::Unnamed(read_varint(bytes, i)?)),
+        t => Err(format!("invalid label type 0x{:X} at byte {}", t, *i - 1)),
+    }
+}
+
+fn read_operator(bytes: &[u8], i: &mut usize) -> Result<Operator, String> {
+    Ok(match read_u8(bytes, i)? {
+        0x0 => Operator::Plus,
+        0x1 => Operator::Minus,
+        0x2 => Operator::Times,
+        0x3 => Operator::Divide,
+        0x4 => Operator::Modulus,
+        0x5 => Operator::OrThat,
+        0x6 => Operator::Ternary,
+        0x7 => Operator::EqualTo,
+        0x8 => Operator::Not,
+        0x9 => Operator::NotEqualTo,
+        0xA => Operator::Less,
+        0xB => Operator::LessOrEqual,
+        0xC => Operator::Greater,
+        0xD => Operator::GreaterOrEqual,
+        0xE => Operator::And,
+        0xF => Operator::Or,
+        0x10 => Operator::Xor,
+        0x11 => Operator::Property,
+        0x12 => Operator::Tuple,
+        0x13 => Operator::Pipe,
+        0x14 => Operator::IsntNull,
+        t => return Err(format!("invalid operator 0x{:X} at byte {}", t, *i - 1)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bodu_vm::{Instruction, VarIndex};
+
+    use super::{compile_instrs, decompile, FORMAT_VERSION, MAGIC};
+
+    fn bytecode() -> Vec<u8> {
+        compile_instrs(vec![
+            Instruction::String(VarIndex::Temp(1), "hi".to_string()),
+            Instruction::Return(VarIndex::Temp(1)),
+        ], true, None)
+    }
+
+    fn error(bytes: &[u8]) -> String {
+        match decompile(bytes) {
+            Ok(_) => panic!("decompile accepted bad bytecode"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn reads_the_header() {
+        let b = decompile(&bytecode()).unwrap();
+        assert_eq!(b.version, FORMAT_VERSION);
+        assert_eq!(b.bodu_version, env!("CARGO_PKG_VERSION"));
+        assert!(b.debug);
+        assert_eq!(b.instrs.len(), 2);
+        assert!(!decompile(&compile_instrs(Vec::new(), false, None)).unwrap().debug);
+    }
+
+    #[test]
+    fn rejects_source_code() {
+        assert_eq!(error(b"print(\"hi\");"), "not a bodu bytecode file");
+        assert_eq!(error(b""), "not a bodu bytecode file");
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        for version in [0, FORMAT_VERSION - 1, FORMAT_VERSION + 1, u16::MAX] {
+            let mut bytes = bytecode();
+            bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&version.to_le_bytes());
+            assert_eq!(error(&bytes), format!("incompatible bytecode format version {} (this bodu reads version {})", version, FORMAT_VERSION));
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = bytecode();
+        assert!(error(&bytes[..bytes.len() - 1]).starts_with("truncated bytecode file"));
+        assert_eq!(error(&bytes[..MAGIC.len() + 1]), "unexpected end of bytecode");
+        assert_eq!(error(&MAGIC), "unexpected end of bytecode");
+    }
+
+    #[test]
+    fn rejects_data_after_the_payload() {
+        let mut bytes = bytecode();
+        bytes.push(0);
+        assert!(error(&bytes).starts_with("unexpected data after the instructions"));
+    }
+
+    #[test]
+    fn rejects_changed_payloads() {
+        let bytes = bytecode();
+        // the payload is at the end, every byte of it is covered by the hash
+        for i in bytes.len() - 8..bytes.len() {
+            let mut bytes = bytes.clone();
+            bytes[i] ^= 0x40;
+            assert_eq!(error(&bytes), "bytecode file is corrupted (hash mismatch)");
+        }
+    }
+}
@@ -0,0 +1,640 @@
+use crate::{s1::{s1, s1_trivia, S1T, INVALID_IDENT_CHARS, KEYWORDS}, s2::{s2, S2T}, s3::{s3, ConditionType, LoopType, Operator, S3T}, s4::s4, vm::{Source, Span}, ParseError};
+
+// how tightly expressions bind, from the loosest. an expression is put in brackets when it's looser than where it goes
+const ARROW: u8 = 0; // |args| expr
+const PIPE: u8 = 1;
+const TERNARY: u8 = 2;
+const OR_THAT: u8 = 3;
+const OR: u8 = 4;
+const XOR: u8 = 5;
+const AND: u8 = 6;
+const COMPARISON: u8 = 7;
+const PLUS_MINUS: u8 = 8;
+const TIMES_DIVIDE: u8 = 9;
+const UNARY: u8 = 10;
+const FIELD: u8 = 11; // expr.prop, expr[prop], expr(args)
+const PRIMARY: u8 = 12;
+
+const INDENT: &str = "    ";
+
+// the source printed back from the S3 tree: 4 spaces of indentation, one statement per line, spaces around binary operators
+// and the alternative spellings (elif, func, &&, ...) replaced by the normal ones. comments are kept, blank lines between statements are kept but not more than one in a row.
+// comments between statements get their own line, comments inside a statement stay next to the same tokens
+pub fn format(contents: &str) -> Result<String, ParseError> {
+    let (tokens, comments, errors) = s1_trivia(contents.to_string());
+    if let Some(e) = errors.into_iter().next() {
+        return Err(e);
+    }
+    let source_tokens = tokens.clone();
+    let tokens = s2(tokens)?;
+    // s3 lets the last token be left over, it would be lost
+    match tokens.last() {
+        Some((S2T::Semicolon | S2T::CloseBrace | S2T::Label(_), _)) | None => {},
+        Some((_, span)) => return Err(ParseError::new("couldn't parse, unexpected end of file", Some(*span))),
+    }
+    let closes = tokens.iter().filter(|(t, _)| *t == S2T::CloseBrace).map(|(_, span)| span.start).collect();
+    // numbers are written like they were, 0x10 stays 0x10
+    let numbers = tokens.iter().filter_map(|(t, span)| {
+        let v = match t {
+            S2T::Int(n) => S3T::Number(*n),
+            S2T::Float(f) => S3T::Float(*f),
+            _ => return None,
+        };
+        Some((span.start, v, contents[span.start..span.end].to_string()))
+    }).collect();
+    let ast = s3(tokens)?;
+    let mut p = Printer {
+        source: contents,
+        lines: contents.match_indices('\n').map(|(i, _)| i).collect(),
+        comments,
+        next_comment: 0,
+        starts: source_tokens.iter().map(|(_, span)| span.start).collect(),
+        inline: Vec::new(),
+        closes,
+        next_close: 0,
+        numbers,
+        next_number: 0,
+        out: String::new(),
+        indent: 0,
+        last_end: 0,
+        block_start: true,
+    };
+    p.stat_list(&ast);
+    p.comments_before(usize::MAX);
+    let res = inline_comments(contents, &source_tokens, p.out, &p.inline);
+    // the formatted code must do exactly the same thing, the instructions are compared
+    let before = compile(contents)?;
+    match compile(&res) {
+        Ok(after) if after == before => Ok(res),
+        _ => Err(ParseError::new("the formatter changed what the code does, this is a bug in bodu fmt", None)),
+    }
+}
+
+fn compile(contents: &str) -> Result<String, ParseError> {
+    let source = Source::new("<fmt>".to_string(), contents.to_string());
+    let tokens = s2(s1(contents.to_string())?)?;
+    let (instrs, _) = s4(s3(tokens)?, source)?;
+    Ok(format!("{:?}", instrs))
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    lines: Vec<usize>, // where the \ns are in the source
+    comments: Vec<(String, crate::vm::Span)>,
+    next_comment: usize, // the first comment that wasn't printed yet
+    starts: Vec<usize>, // where the tokens start in the source
+    inline: Vec<(String, Span)>, // the comments inside statements, inline_comments puts them back in
+    closes: Vec<usize>, // where the }s are in the source
+    next_close: usize, // the } of the next block that ends
+    numbers: Vec<(usize, S3T, String)>, // where the number literals are, their values and how they're written
+    next_number: usize,
+    out: String,
+    indent: usize,
+    last_end: usize, // where the last statement or comment printed ends in the source
+    block_start: bool, // nothing was printed in the block yet
+}
+
+impl Printer<'_> {
+    // a line at the current indentation, text can have more lines if they're indented already
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+        self.block_start = false;
+    }
+
+    // one blank line if there was at least one in the source
+    fn blank_line(&mut self, start: usize) {
+        if self.block_start {
+            return;
+        }
+        if let Some(between) = self.source.get(self.last_end..start) && between.matches('\n').count() >= 2 {
+            self.out.push('\n');
+        }
+    }
+
+    // the comments that start before pos, each in its own line. the ones with tokens between them and pos are inside a statement
+    fn comments_before(&mut self, pos: usize) {
+        while let Some((text, span)) = self.comments.get(self.next_comment).cloned() {
+            if span.start >= pos {
+                break;
+            }
+            self.next_comment += 1;
+            let next = self.starts.partition_point(|start| *start < span.end);
+            if self.starts.get(next).is_some_and(|start| *start < pos) {
+                self.inline.push((text, span));
+                continue;
+            }
+            self.blank_line(span.start);
+            self.line(&text);
+            self.last_end = span.end;
+        }
+    }
+
+    // a comment after the end of a statement, in the same line and with no tokens between them
+    fn trailing_comment(&mut self, end: usize) {
+        let line = self.lines.partition_point(|nl| *nl < end) + 1;
+        if let Some((text, span)) = self.comments.get(self.next_comment).cloned() && span.line == line && self.starts.get(self.starts.partition_point(|start| *start < end)).is_none_or(|start| *start >= span.start) {
+            self.next_comment += 1;
+            self.out.pop(); // the \n
+            self.out.push(' ');
+            self.out.push_str(&text);
+            self.out.push('\n');
+            self.last_end = span.end;
+        }
+    }
+
+    fn stat_list(&mut self, stats: &[S3T]) {
+        for stat in stats {
+            match stat {
+                S3T::Spanned(span, stat) => {
+                    self.comments_before(span.start);
+                    // a block that was printed differently (an empty loop part isn't printed) can't make the other blocks use the wrong }
+                    while self.closes.get(self.next_close).is_some_and(|close| *close < span.start) {
+                        self.next_close += 1;
+                    }
+                    while self.numbers.get(self.next_number).is_some_and(|(pos, _, _)| *pos < span.start) {
+                        self.next_number += 1;
+                    }
+                    self.blank_line(span.start);
+                    let text = self.stat(stat);
+                    self.line(&text);
+                    self.comments_before(span.end);
+                    self.last_end = span.end;
+                    self.trailing_comment(span.end);
+                },
+                stat => {
+                    let text = self.stat(stat);
+                    self.line(&text);
+                },
+            }
+        }
+    }
+
+    // { stats }, indented one level more than the current line
+    fn block(&mut self, stats: &[S3T]) -> String {
+        let out = std::mem::take(&mut self.out);
+        self.indent += 1;
+        self.block_start = true;
+        self.stat_list(stats);
+        let close = self.closes.get(self.next_close).copied().unwrap_or(0);
+        self.next_close += 1;
+        self.comments_before(close);
+        self.indent -= 1;
+        self.block_start = false;
+        let inner = std::mem::replace(&mut self.out, out);
+        if inner.is_empty() {
+            return "{}".to_string();
+        }
+        format!("{{\n{}{}}}", inner, INDENT.repeat(self.indent))
+    }
+
+    fn stat(&mut self, stat: &S3T) -> String {
+        match stat {
+            S3T::Let(name, None) => format!("let {};", name),
+            S3T::Let(name, Some(v)) => format!("let {} = {};", name, self.expr_or_stat(v)),
+            S3T::LetDetuple(names, v) => format!("let ({}) = {};", names.join(", "), self.expr_or_stat(v)),
+            S3T::MultiLet(names) => format!("let ({});", names.join(", ")),
+            S3T::Out(v) => format!("out {};", self.expr_or_stat(v)),
+            S3T::Return(v) => format!("return {};", self.expr_or_stat(v)),
+            S3T::Throw(v) => format!("throw {};", self.expr_or_stat(v)),
+            S3T::Label(name) => format!("#{}", name),
+            S3T::Goto(name) => format!("goto #{};", name),
+            S3T::Defer(stats) => match stats.as_slice() {
+                // defer expr;
+                [v] if !matches!(v, S3T::Spanned(_, _)) => format!("defer {};", self.expr_or_stat(v)),
+                _ => format!("defer {}", self.block(stats)),
+            },
+            S3T::Bind(name, v) => format!("bind {} = {};", name, self.expr_or_stat(v)),
+            S3T::Decorator(d, f) => format!("@{} {}", self.expr(d, PIPE), self.stat_expr(f)),
+            S3T::Detuple(vars, v) => {
+                let vars = vars.iter().map(|v| self.expr_or_stat(v)).collect::<Vec<_>>();
+                format!("({}) = {};", vars.join(", "), self.expr(v, ARROW))
+            },
+            S3T::Assign(left, right) => {
+                // x = x + y is written as x += y, which is also what x += y and x++ become
+                let op = match &**right {
+                    S3T::Plus(l, r) if l == left => Some(("+=", r)),
+                    S3T::Minus(l, r) if l == left => Some(("-=", r)),
+                    S3T::Times(l, r) if l == left => Some(("*=", r)),
+                    S3T::Divide(l, r) if l == left => Some(("/=", r)),
+                    S3T::Modulus(l, r) if l == left => Some(("%=", r)),
+                    _ => None,
+                };
+                let left = self.expr(left, ARROW);
+                match op {
+                    Some((op, r)) => format!("{} {} {};", left, op, self.expr(r, ARROW)),
+                    None => format!("{} = {};", left, self.expr(right, ARROW)),
+                }
+            },
+            S3T::Continue(None) => "continue;".to_string(),
+            S3T::Continue(Some(v)) => format!("continue {};", self.expr_or_stat(v)),
+            S3T::Break(None) => "break;".to_string(),
+            S3T::Break(Some(v)) => format!("break {};", self.expr_or_stat(v)),
+            S3T::Spanned(_, stat) => self.stat(stat),
+            v if is_stat_expr(v) => self.stat_expr(v),
+            v => format!("{};", self.expr(v, ARROW)),
+        }
+    }
+
+    // where the parser takes both expressions and statement-expressions
+    fn expr_or_stat(&mut self, v: &S3T) -> String {
+        if is_stat_expr(v) {
+            return self.stat_expr(v);
+        }
+        self.expr(v, ARROW)
+    }
+
+    fn stat_expr(&mut self, v: &S3T) -> String {
+        match v {
+            S3T::If(ifs, else_) => {
+                let mut s = String::new();
+                for (n, (cond, v, stats)) in ifs.iter().enumerate() {
+                    if n > 0 {
+                        s.push_str(" else ");
+                    }
+                    s.push_str(match cond {
+                        ConditionType::If => "if ",
+                        ConditionType::Unless => "unless ",
+                    });
+                    s.push_str(&self.expr(v, PIPE));
+                    s.push(' ');
+                    s.push_str(&self.block(stats));
+                }
+                if let Some(stats) = else_ {
+                    s.push_str(" else ");
+                    s.push_str(&self.block(stats));
+                }
+                s
+            },
+            S3T::TryCatchFinally(try_, catch, finally) => {
+                let mut s = format!("try {}", self.block(try_));
+                if let Some((name, stats)) = catch {
+                    s.push_str(&format!(" catch {} {}", name, self.block(stats)));
+                }
+                if let Some(stats) = finally {
+                    s.push_str(&format!(" finally {}", self.block(stats)));
+                }
+                s
+            },
+            S3T::Loop(before, type_, body, again, after, else_) => {
+                let mut s = String::new();
+                if !before.is_empty() {
+                    s.push_str(&format!("before {} ", self.block(before)));
+                }
+                s.push_str(&match type_ {
+                    LoopType::Loop => "loop".to_string(),
+                    LoopType::LoopN(n) => format!("loop {}", self.expr(n, PIPE)),
+                    LoopType::While(cond) => format!("while {}", self.expr(cond, PIPE)),
+                    LoopType::Until(cond) => format!("until {}", self.expr(cond, PIPE)),
+                    LoopType::For(name, it) => format!("for {} in {}", name, self.expr(it, PIPE)),
+                    LoopType::ForWhile(name, it, cond) => format!("for {} in {} while {}", name, self.expr(it, PIPE), self.expr(cond, PIPE)),
+                    LoopType::ForUntil(name, it, cond) => format!("for {} in {} until {}", name, self.expr(it, PIPE), self.expr(cond, PIPE)),
+                });
+                s.push(' ');
+                s.push_str(&self.block(body));
+                for (name, stats) in [("again", again), ("after", after), ("else", else_)] {
+                    if !stats.is_empty() {
+                        s.push_str(&format!(" {} {}", name, self.block(stats)));
+                    }
+                }
+                s
+            },
+            S3T::Function(name, args, body) => match name {
+                Some(name) => format!("fn {}({}) {}", name, args.join(", "), self.block(body)),
+                None => format!("fn({}) {}", args.join(", "), self.block(body)),
+            },
+            S3T::Block(stats) => self.block(stats),
+            v => self.expr(v, ARROW),
+        }
+    }
+
+    // v in brackets if it binds looser than prec
+    fn expr(&mut self, v: &S3T, prec: u8) -> String {
+        if is_stat_expr(v) {
+            return format!("({})", self.stat_expr(v));
+        }
+        let s = match v {
+            S3T::Identifier(name) => name.clone(),
+            S3T::Number(_) | S3T::Float(_) => self.number(v),
+            S3T::String(s) => string(s),
+            S3T::Boolean(true) => "true".to_string(),
+            S3T::Boolean(false) => "false".to_string(),
+            S3T::Null => "null".to_string(),
+            S3T::PipeShorthand => "$".to_string(),
+            S3T::Debug => "debug".to_string(),
+            S3T::Release => "release".to_string(),
+            S3T::Maybe => "maybe".to_string(),
+            S3T::Probably => "probably".to_string(),
+            S3T::Possibly => "possibly".to_string(),
+            S3T::OperatorFn(op) => operator_fn(*op).to_string(),
+            S3T::Tuple(vs) => {
+                let vs = vs.iter().map(|v| self.expr_or_stat(v)).collect::<Vec<_>>();
+                format!("({})", vs.join(", "))
+            },
+            S3T::Function(None, args, body) => {
+                // |args| expr, the body is a return without a span
+                let v = match body.as_slice() {
+                    [S3T::Return(v)] => v,
+                    _ => return format!("({})", self.stat_expr(v)), // should never happen
+                };
+                // a nested |args| would start with ||, which is read as a single |
+                format!("|{}| {}", args.join(", "), self.expr(v, PIPE))
+            },
+            S3T::Property(v, prop) => {
+                let v = match &**v {
+                    S3T::Number(_) | S3T::Float(_) => format!("({})", self.expr(v, ARROW)), // 1.x would be read as the float 1.
+                    v => self.expr(v, FIELD),
+                };
+                match &**prop {
+                    S3T::String(name) if is_ident(name) => format!("{}.{}", v, name),
+                    prop => format!("{}[{}]", v, self.expr(prop, ARROW)),
+                }
+            },
+            S3T::FnCall(f, args) => {
+                let f = match &**f {
+                    S3T::Number(_) | S3T::Float(_) => format!("({})", self.expr(f, ARROW)),
+                    f => self.expr(f, FIELD),
+                };
+                let args = args.iter().map(|v| self.expr_or_stat(v)).collect::<Vec<_>>();
+                format!("{}({})", f, args.join(", "))
+            },
+            // - - and ? ? would be read as -- and ??
+            S3T::Negate(v) if matches!(**v, S3T::Negate(_)) => format!("-({})", self.expr(v, UNARY)),
+            S3T::Negate(v) => format!("-{}", self.expr(v, UNARY)),
+            S3T::Not(v) => format!("!{}", self.expr(v, UNARY)),
+            S3T::IsntNull(v) if matches!(**v, S3T::IsntNull(_)) => format!("?({})", self.expr(v, UNARY)),
+            S3T::IsntNull(v) => format!("?{}", self.expr(v, UNARY)),
+            S3T::Times(a, b) => self.binary(a, "*", b, TIMES_DIVIDE),
+            S3T::Divide(a, b) => self.binary(a, "/", b, TIMES_DIVIDE),
+            S3T::Modulus(a, b) => self.binary(a, "%", b, TIMES_DIVIDE),
+            S3T::Plus(a, b) => self.binary(a, "+", b, PLUS_MINUS),
+            S3T::Minus(a, b) => {
+                let a = self.expr(a, PLUS_MINUS);
+                match &**b {
+                    S3T::Negate(_) => format!("{} - ({})", a, self.expr(b, ARROW)),
+                    b => format!("{} - {}", a, self.expr(b, PLUS_MINUS + 1)),
+                }
+            },
+            S3T::EqualTo(a, b) => self.binary(a, "==", b, COMPARISON),
+            S3T::NotEqualTo(a, b) => self.binary(a, "!=", b, COMPARISON),
+            S3T::Less(a, b) => self.binary(a, "<", b, COMPARISON),
+            S3T::LessOrEqual(a, b) => self.binary(a, "<=", b, COMPARISON),
+            S3T::Greater(a, b) => self.binary(a, ">", b, COMPARISON),
+            S3T::GreaterOrEqual(a, b) => self.binary(a, ">=", b, COMPARISON),
+            S3T::And(a, b) => self.binary(a, "&", b, AND),
+            S3T::Xor(a, b) => self.binary(a, "^", b, XOR),
+            S3T::Or(a, b) => self.binary(a, "|", b, OR),
+            S3T::OrThat(a, b) => self.binary(a, "??", b, OR_THAT),
+            S3T::Ternary(cond, a, b) => {
+                let cond = self.expr(cond, OR_THAT);
+                // cond ? ?a : b would be read as cond ?? a
+                let a = match &**a {
+                    S3T::IsntNull(_) => format!("({})", self.expr(a, ARROW)),
+                    a => self.expr(a, OR_THAT),
+                };
+                format!("{} ? {} : {}", cond, a, self.expr(b, OR_THAT))
+            },
+            S3T::Pipe(a, b) => self.binary(a, "|>", b, PIPE),
+            S3T::Spanned(_, v) => return self.expr(v, prec),
+            // statements can't be in expressions
+            v => return self.stat(v),
+        };
+        if precedence(v) < prec {
+            return format!("({})", s);
+        }
+        s
+    }
+
+    // the next literal in the source if it's this number, x++ makes a 1 that isn't there
+    fn number(&mut self, v: &S3T) -> String {
+        if let Some((_, n, s)) = self.numbers.get(self.next_number) && n == v {
+            self.next_number += 1;
+            return s.clone();
+        }
+        match v {
+            S3T::Float(f) if !f.to_string().contains('.') => format!("{}.0", f),
+            S3T::Float(f) => f.to_string(),
+            S3T::Number(n) => n.to_string(),
+            _ => String::new(), // should never happen
+        }
+    }
+
+    // a op b, left to right
+    fn binary(&mut self, a: &S3T, op: &str, b: &S3T, prec: u8) -> String {
+        format!("{} {} {}", self.expr(a, prec), op, self.expr(b, prec + 1))
+    }
+}
+
+fn precedence(v: &S3T) -> u8 {
+    match v {
+        S3T::Function(None, _, _) => ARROW,
+        S3T::Pipe(_, _) => PIPE,
+        S3T::Ternary(_, _, _) => TERNARY,
+        S3T::OrThat(_, _) => OR_THAT,
+        S3T::Or(_, _) => OR,
+        S3T::Xor(_, _) => XOR,
+        S3T::And(_, _) => AND,
+        S3T::EqualTo(_, _) | S3T::NotEqualTo(_, _) | S3T::Less(_, _) | S3T::LessOrEqual(_, _) | S3T::Greater(_, _) | S3T::GreaterOrEqual(_, _) => COMPARISON,
+        S3T::Plus(_, _) | S3T::Minus(_, _) => PLUS_MINUS,
+        S3T::Times(_, _) | S3T::Divide(_, _) | S3T::Modulus(_, _) => TIMES_DIVIDE,
+        S3T::Negate(_) | S3T::Not(_) | S3T::IsntNull(_) => UNARY,
+        S3T::Property(_, _) | S3T::FnCall(_, _) => FIELD,
+        _ => PRIMARY,
+    }
+}
+
+// the statement-expressions, they need brackets inside other expressions. |args| expr is a function without a span in its body
+fn is_stat_expr(v: &S3T) -> bool {
+    match v {
+        S3T::If(_, _) | S3T::TryCatchFinally(_, _, _) | S3T::Loop(_, _, _, _, _, _) | S3T::Block(_) => true,
+        S3T::Function(Some(_), _, _) => true,
+        S3T::Function(None, args, body) => args.is_empty() || !matches!(body.as_slice(), [S3T::Return(_)]),
+        _ => false,
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    match s.chars().next() {
+        Some(ch) if !ch.is_ascii_digit() => {},
+        _ => return false,
+    }
+    !s.chars().any(|ch| ch.is_whitespace() || INVALID_IDENT_CHARS.contains(ch)) && !KEYWORDS.contains(&s) && !["elif", "elsif", "fun", "func", "function"].contains(&s)
+}
+
+fn string(s: &str) -> String {
+    let mut res = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '\\' => res.push_str("\\\\"),
+            '"' => res.push_str("\\\""),
+            '\0' => res.push_str("\\0"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            '\n' => res.push_str("\\n"),
+            ch => res.push(ch),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn operator_fn(op: Operator) -> &'static str {
+    match op {
+        Operator::Plus => "[+]",
+        Operator::Minus => "[-]",
+        Operator::Times => "[*]",
+        Operator::Divide => "[/]",
+        Operator::Modulus => "[%]",
+        Operator::OrThat => "[??]",
+        Operator::Ternary => "[?:]",
+        Operator::EqualTo => "[==]",
+        Operator::Not => "[!]",
+        Operator::NotEqualTo => "[!=]",
+        Operator::Less => "[<]",
+        Operator::LessOrEqual => "[<=]",
+        Operator::Greater => "[>]",
+        Operator::GreaterOrEqual => "[>=]",
+        Operator::And => "[&]",
+        Operator::Or => "[|]",
+        Operator::Xor => "[^]",
+        Operator::Property => "[.]",
+        Operator::Tuple => "[,]",
+        Operator::Pipe => "[|>]",
+        Operator::IsntNull => "[?]",
+    }
+}
+
+// puts the comments that were inside statements back next to the tokens they were next to. the tokens of the source and
+// of the formatted code are matched up first, they're mostly the same except for the spellings that were replaced and the
+// brackets. /* */ comments go before the token after them, // comments go at the end of the line of the token before them
+fn inline_comments(source: &str, tokens: &[(S1T, Span)], out: String, comments: &[(String, Span)]) -> String {
+    if comments.is_empty() {
+        return out;
+    }
+    let (out_tokens, _, _) = s1_trivia(out.clone());
+    let matched = match_tokens(tokens, &out_tokens);
+    let mut inserts = Vec::new(); // where in out and what
+    for (text, span) in comments {
+        let next = tokens.partition_point(|(_, t)| t.start < span.end);
+        let after = (next..tokens.len()).find_map(|k| matched[k]);
+        let before = (0..next).rev().find_map(|k| matched[k]);
+        let space_before = source[..span.start].ends_with(char::is_whitespace);
+        let space_after = source[span.end..].starts_with(char::is_whitespace);
+        let at = match (text.starts_with("//"), after, before) {
+            (false, Some(k), _) => {
+                let pos = out_tokens[k].1.start;
+                let mut s = String::new();
+                if space_before && !out[..pos].ends_with(char::is_whitespace) {
+                    s.push(' ');
+                }
+                s.push_str(text);
+                if space_after {
+                    s.push(' ');
+                }
+                (pos, s)
+            },
+            (_, _, Some(k)) => {
+                let end = out_tokens[k].1.end;
+                let pos = out[end..].find('\n').map(|i| end + i).unwrap_or(out.len());
+                (pos, format!(" {}", text))
+            },
+            // nothing was matched, the comment goes to the end
+            _ => (out.trim_end().len(), format!(" {}", text)),
+        };
+        inserts.push(at);
+    }
+    // from the end so the positions stay right, comments at the same place stay in order
+    let mut out = out;
+    for (pos, text) in inserts.into_iter().rev() {
+        out.insert_str(pos, &text);
+    }
+    out
+}
+
+// which token of b every token of a is, skipping over the tokens that are only in one of them
+fn match_tokens(a: &[(S1T, Span)], b: &[(S1T, Span)]) -> Vec<Option<usize>> {
+    const LOOKAHEAD: usize = 8;
+    let mut matched = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].0 == b[j].0 {
+            matched[i] = Some(j);
+            i += 1;
+            j += 1;
+            continue;
+        }
+        // the closest place where they're the same again
+        let skip = (1..=LOOKAHEAD * 2).find_map(|d| (0..=d).find(|n| {
+            let (x, y) = (i + n, j + d - n);
+            *n <= LOOKAHEAD && d - n <= LOOKAHEAD && x < a.len() && y < b.len() && a[x].0 == b[y].0
+        }).map(|n| (n, d - n)));
+        match skip {
+            Some((x, y)) => {
+                i += x;
+                j += y;
+            },
+            None => {
+                i += 1;
+                j += 1;
+            },
+        }
+    }
+    matched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+
+    fn fmt(code: &str) -> String {
+        format(code).unwrap()
+    }
+
+    #[test]
+    fn formats_statements() {
+        assert_eq!(fmt("let x=1;fn   f(a,b){return a+b;}"), "let x = 1;\nfn f(a, b) {\n    return a + b;\n}\n");
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        for code in [
+            "#!/usr/bin/env bodu\n// hi\nlet x=1; // x\n\n\n\nif x==1{print(1);}elif x==2{print(2);}else{print(3);}\n",
+            "let total = add(\n    1, // one\n    2 // two\n);\nlet z = 1 + /* plus */ 2;\n",
+            "fn f(a) {\n    // inside\n    return a;\n\n\n    // last\n}\nloop { if f(1) { break; } }\n",
+        ] {
+            let once = fmt(code);
+            assert_eq!(fmt(&once), once);
+        }
+    }
+
+    #[test]
+    fn keeps_the_shebang_and_comments() {
+        let code = "#!/usr/bin/env bodu\n// first\nlet x = 1; // x\n/* block */\nfn f() {\n    // inside\n    return x;\n    // last\n}\n";
+        assert_eq!(fmt(code), code);
+    }
+
+    // the comments inside a statement stay next to the tokens they were next to
+    #[test]
+    fn keeps_comments_inside_statements() {
+        assert_eq!(fmt("let z = 1 +   /* plus */ 2;\n"), "let z = 1 + /* plus */ 2;\n");
+        assert_eq!(fmt("let y = f(1, /* first */ 2 /* second */);\n"), "let y = f(1, /* first */ 2 /* second */);\n");
+        assert_eq!(fmt("if x { print(1); } /* c */ else { print(2); }\n"), "if x {\n    print(1);\n} /* c */ else {\n    print(2);\n}\n");
+        // a // comment goes at the end of the line of the token before it
+        assert_eq!(fmt("let total = add(\n    1, // one\n    2\n);\n"), "let total = add(1, 2); // one\n");
+    }
+
+    #[test]
+    fn collapses_blank_lines() {
+        assert_eq!(fmt("let x = 1;\n\n\n\nlet y = 2;\nlet z = 3;\n"), "let x = 1;\n\nlet y = 2;\nlet z = 3;\n");
+        assert_eq!(fmt("fn f() {\n\n    return 1;\n\n\n}\n"), "fn f() {\n    return 1;\n}\n");
+    }
+
+    #[test]
+    fn replaces_the_alternative_spellings() {
+        assert_eq!(fmt("if x { print(1); } elif y { print(2); }\n"), "if x {\n    print(1);\n} else if y {\n    print(2);\n}\n");
+        assert_eq!(fmt("func f() { return x && y; }\n"), "fn f() {\n    return x & y;\n}\n");
+    }
+}
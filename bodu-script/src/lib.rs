@@ -1,5 +1,63 @@
+use std::{fmt, sync::Arc};
+
+use vm::{DebugInfo, Instruction, Source, Span};
+
+pub mod format;
+pub mod lint;
 pub mod s1;
 pub mod s2;
 pub mod s3;
 pub mod s4;
-pub use bodu_vm as vm;
\ No newline at end of file
+pub use bodu_vm as vm;
+
+// an error from any of the parser stages
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+impl ParseError {
+    pub fn new(message: &str, span: Option<Span>) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    // file:line:col: message, followed by the source excerpt
+    pub fn report(&self, source: &Source) -> String {
+        source.report(self.span, &self.message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+// runs all the stages
+pub fn parse(source: Arc<Source>) -> Result<(Vec<Instruction>, DebugInfo), ParseError> {
+    let tokens = s1::s1(source.contents.clone())?;
+    let tokens = s2::s2(tokens)?;
+    let ast = s3::s3(tokens)?;
+    s4::s4(ast, source)
+}
+
+// runs all the stages without stopping at the first error and returns every error, sorted by where they are
+pub fn check(source: Arc<Source>) -> Vec<ParseError> {
+    let (tokens, mut errors) = s1::s1_recover(source.contents.clone());
+    let tokens = match s2::s2(tokens) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            errors.push(e);
+            return errors;
+        },
+    };
+    let (ast, e) = s3::s3_recover(tokens);
+    errors.extend(e);
+    errors.extend(s4::s4_errors(ast));
+    errors.sort_by_key(|e| e.span.map(|span| span.start));
+    errors
+}
@@ -0,0 +1,481 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::{s3::{LoopType, S3T}, vm::Span};
+
+// every rule, the names are what the config uses to turn them off
+pub const RULES: &[&str] = &[
+    "undefined-label",
+    "unreachable-code",
+    "unused-variable",
+    "unused-parameter",
+    "undeclared-assignment",
+    "shorthand-outside-pipe",
+    "break-outside-loop",
+    "continue-outside-loop",
+];
+
+// a problem found by the linter, the span is the one of the statement it's in
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Lint {
+    pub rule: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Let,
+    Param,
+    Other, // functions, binds, loop variables and catch variables, they aren't reported when unused
+}
+
+struct Var {
+    name: String,
+    kind: Kind,
+    span: Option<Span>,
+    used: bool,
+}
+
+// a function body, checked after everything around it so the names declared after the function are known
+struct Pending<'a> {
+    params: &'a [String],
+    body: &'a [S3T],
+    stack: Vec<usize>,
+    span: Option<Span>,
+}
+
+struct Linter<'a> {
+    globals: &'a [String],
+    vars: Vec<Var>,
+    scopes: Vec<Vec<usize>>, // the variables of every scope
+    stack: Vec<usize>, // the scopes around the code being checked
+    labels: Vec<Vec<String>>, // the labels of the blocks around the code being checked, inside the same function
+    in_loop: bool,
+    in_pipe: bool, // if $ has a value
+    span: Option<Span>,
+    pending: VecDeque<Pending<'a>>,
+    lints: Vec<Lint>,
+}
+
+// checks the S3 tree of a file. globals are the names that exist before the file runs, assigning to them isn't reported
+pub fn lint(ast: &[S3T], globals: &[String]) -> Vec<Lint> {
+    let mut l = Linter {
+        globals,
+        vars: Vec::new(),
+        scopes: Vec::new(),
+        stack: Vec::new(),
+        labels: Vec::new(),
+        in_loop: false,
+        in_pipe: false,
+        span: None,
+        pending: VecDeque::new(),
+        lints: Vec::new(),
+    };
+    l.push_scope();
+    l.body(ast);
+    while let Some(f) = l.pending.pop_front() {
+        l.function(f);
+    }
+    for var in l.vars.iter() {
+        if var.used || var.name.starts_with('_') {
+            continue;
+        }
+        let (rule, message) = match var.kind {
+            Kind::Let => ("unused-variable", format!("{} is never used", var.name)),
+            Kind::Param => ("unused-parameter", format!("the parameter {} is never used", var.name)),
+            Kind::Other => continue,
+        };
+        l.lints.push(Lint {
+            rule,
+            message,
+            span: var.span,
+        });
+    }
+    l.lints.sort_by_key(|lint| lint.span.map(|span| span.start));
+    l.lints
+}
+
+// the labels a goto can jump to from inside a list of statements
+fn labels_of(list: &[S3T]) -> Vec<String> {
+    list.iter().filter_map(|v| {
+        let mut v = v;
+        while let S3T::Spanned(_, v2) = v {
+            v = v2;
+        }
+        match v {
+            S3T::Label(name) => Some(name.clone()),
+            _ => None,
+        }
+    }).collect()
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: &'static str, message: String) {
+        self.lints.push(Lint {
+            rule,
+            message,
+            span: self.span,
+        });
+    }
+
+    fn push_scope(&mut self) {
+        self.stack.push(self.scopes.len());
+        self.scopes.push(Vec::new());
+    }
+
+    fn declare(&mut self, name: &str, kind: Kind) {
+        let scope = *self.stack.last().unwrap();
+        self.scopes[scope].push(self.vars.len());
+        self.vars.push(Var {
+            name: name.to_string(),
+            kind,
+            span: self.span,
+            used: false,
+        });
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.stack.iter().rev().find_map(|scope| self.scopes[*scope].iter().rev().find(|v| self.vars[**v].name == name).copied())
+    }
+
+    fn function(&mut self, f: Pending<'a>) {
+        self.stack = f.stack;
+        self.labels = Vec::new();
+        self.in_loop = false;
+        self.in_pipe = false;
+        self.span = f.span;
+        self.push_scope();
+        for param in f.params {
+            self.declare(param, Kind::Param);
+        }
+        self.body(f.body);
+    }
+
+    // the statements of a block, in the scope that's already there
+    fn body(&mut self, list: &'a [S3T]) {
+        self.labels.push(labels_of(list));
+        self.stat_list(list);
+        self.labels.pop();
+    }
+
+    fn block(&mut self, list: &'a [S3T]) {
+        self.push_scope();
+        self.body(list);
+        self.stack.pop();
+    }
+
+    fn stat_list(&mut self, list: &'a [S3T]) {
+        let mut jumped = None; // the statement that makes the next ones unreachable, until a label
+        for v in list {
+            let mut v = v;
+            while let S3T::Spanned(span, v2) = v {
+                self.span = Some(*span);
+                v = v2;
+            }
+            match v {
+                S3T::Label(_) => jumped = None,
+                _ => if let Some(name) = jumped.take() {
+                    self.report("unreachable-code", format!("this code is after {} so it never runs", name));
+                },
+            }
+            self.stat(v);
+            jumped = match v {
+                S3T::Return(_) => Some("return"),
+                S3T::Throw(_) => Some("throw"),
+                S3T::Goto(_) => Some("goto"),
+                S3T::Break(_) => Some("break"),
+                S3T::Continue(_) => Some("continue"),
+                _ => jumped,
+            };
+        }
+    }
+
+    fn stat(&mut self, v: &'a S3T) {
+        match v {
+            S3T::Let(name, e) => {
+                if let Some(e) = e {
+                    self.expr(e);
+                }
+                self.declare(name, Kind::Let);
+            },
+            S3T::MultiLet(names) => {
+                for name in names {
+                    self.declare(name, Kind::Let);
+                }
+            },
+            S3T::LetDetuple(names, e) => {
+                self.expr(e);
+                for name in names {
+                    self.declare(name, Kind::Let);
+                }
+            },
+            S3T::Bind(name, e) => {
+                self.declare(name, Kind::Other);
+                self.expr(e);
+            },
+            S3T::Function(Some(name), params, body) => {
+                self.declare(name, Kind::Other);
+                self.later(params, body);
+            },
+            S3T::Decorator(d, f) => {
+                self.expr(d);
+                self.stat(f);
+            },
+            S3T::Assign(left, right) => {
+                self.expr(right);
+                self.target(left);
+            },
+            S3T::Detuple(targets, e) => {
+                self.expr(e);
+                for t in targets {
+                    self.target(t);
+                }
+            },
+            S3T::Out(e) | S3T::Return(e) | S3T::Throw(e) => self.expr(e),
+            S3T::Goto(name) => {
+                if !self.labels.iter().any(|labels| labels.contains(name)) {
+                    self.report("undefined-label", format!("there's no label #{} for this goto to jump to", name));
+                }
+            },
+            S3T::Label(_) => {},
+            S3T::Break(e) => {
+                if !self.in_loop {
+                    self.report("break-outside-loop", "break is outside of a loop".to_string());
+                }
+                if let Some(e) = e {
+                    self.expr(e);
+                }
+            },
+            S3T::Continue(e) => {
+                if !self.in_loop {
+                    self.report("continue-outside-loop", "continue is outside of a loop".to_string());
+                }
+                if let Some(e) = e {
+                    self.expr(e);
+                }
+            },
+            S3T::Defer(list) => self.block(list),
+            v => self.expr(v),
+        }
+    }
+
+    // the left side of an assignment
+    fn target(&mut self, v: &'a S3T) {
+        match v {
+            S3T::Identifier(name) => {
+                if self.lookup(name).is_none() && !self.globals.contains(name) {
+                    self.report("undeclared-assignment", format!("{} isn't declared, assigning to it makes a global variable", name));
+                }
+            },
+            v => self.expr(v),
+        }
+    }
+
+    fn later(&mut self, params: &'a [String], body: &'a [S3T]) {
+        self.pending.push_back(Pending {
+            params,
+            body,
+            stack: self.stack.clone(),
+            span: self.span,
+        });
+    }
+
+    fn expr(&mut self, v: &'a S3T) {
+        match v {
+            S3T::Identifier(name) => {
+                if let Some(var) = self.lookup(name) {
+                    self.vars[var].used = true;
+                }
+            },
+            S3T::PipeShorthand if !self.in_pipe => {
+                self.report("shorthand-outside-pipe", "$ is only set on the right of |> and in the again and else blocks of a loop".to_string());
+            },
+            S3T::If(ifs, else_) => {
+                for (_, cond, list) in ifs {
+                    self.expr(cond);
+                    self.block(list);
+                }
+                if let Some(list) = else_ {
+                    self.block(list);
+                }
+            },
+            S3T::Block(list) => self.block(list),
+            S3T::TryCatchFinally(try_, catch, finally) => {
+                self.block(try_);
+                if let Some((name, list)) = catch {
+                    self.push_scope();
+                    self.declare(name, Kind::Other);
+                    self.body(list);
+                    self.stack.pop();
+                }
+                if let Some(list) = finally {
+                    self.block(list);
+                }
+            },
+            S3T::Function(_, params, body) => self.later(params, body),
+            S3T::Loop(before, type_, body, again, after, else_) => self.loop_(before, type_, body, again, after, else_),
+            S3T::Pipe(left, right) => {
+                self.expr(left);
+                let in_pipe = std::mem::replace(&mut self.in_pipe, true);
+                self.expr(right);
+                self.in_pipe = in_pipe;
+            },
+            S3T::Tuple(list) => {
+                for v in list {
+                    self.expr(v);
+                }
+            },
+            S3T::FnCall(f, args) => {
+                self.expr(f);
+                for v in args {
+                    self.expr(v);
+                }
+            },
+            S3T::Ternary(a, b, c) => {
+                self.expr(a);
+                self.expr(b);
+                self.expr(c);
+            },
+            S3T::Plus(a, b) | S3T::Minus(a, b) | S3T::Times(a, b) | S3T::Divide(a, b) | S3T::Modulus(a, b) | S3T::EqualTo(a, b) | S3T::NotEqualTo(a, b) | S3T::Less(a, b) | S3T::LessOrEqual(a, b) | S3T::Greater(a, b) | S3T::GreaterOrEqual(a, b) | S3T::And(a, b) | S3T::Or(a, b) | S3T::Xor(a, b) | S3T::Property(a, b) | S3T::OrThat(a, b) => {
+                self.expr(a);
+                self.expr(b);
+            },
+            S3T::Negate(a) | S3T::Not(a) | S3T::IsntNull(a) => self.expr(a),
+            S3T::Spanned(span, v) => {
+                self.span = Some(*span);
+                self.stat(v);
+            },
+            _ => {},
+        }
+    }
+
+    // all the parts of a loop are in the same scope, the iterable and the number of times are outside of it
+    fn loop_(&mut self, before: &'a [S3T], type_: &'a LoopType, body: &'a [S3T], again: &'a [S3T], after: &'a [S3T], else_: &'a [S3T]) {
+        match type_ {
+            LoopType::LoopN(e) | LoopType::For(_, e) | LoopType::ForWhile(_, e, _) | LoopType::ForUntil(_, e, _) => self.expr(e),
+            LoopType::Loop | LoopType::While(_) | LoopType::Until(_) => {},
+        }
+        self.push_scope();
+        self.labels.push([before, body, again, after, else_].into_iter().flat_map(labels_of).collect());
+        let in_loop = std::mem::replace(&mut self.in_loop, true);
+        let in_pipe = self.in_pipe;
+        self.stat_list(before);
+        match type_ {
+            LoopType::For(name, _) | LoopType::ForWhile(name, _, _) | LoopType::ForUntil(name, _, _) => self.declare(name, Kind::Other),
+            LoopType::Loop | LoopType::LoopN(_) | LoopType::While(_) | LoopType::Until(_) => {},
+        }
+        match type_ {
+            LoopType::While(cond) | LoopType::Until(cond) | LoopType::ForWhile(_, _, cond) | LoopType::ForUntil(_, _, cond) => self.expr(cond),
+            LoopType::Loop | LoopType::LoopN(_) | LoopType::For(_, _) => {},
+        }
+        self.stat_list(body);
+        self.in_pipe = true;
+        self.stat_list(again);
+        self.in_pipe = in_pipe;
+        self.stat_list(after);
+        self.in_pipe = true;
+        self.stat_list(else_);
+        self.in_pipe = in_pipe;
+        self.in_loop = in_loop;
+        self.labels.pop();
+        self.stack.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{s1::s1, s2::s2, s3::s3};
+
+    // the rule and the line of every lint, print is the only global
+    fn lints(code: &str) -> Vec<(&'static str, usize)> {
+        let ast = s1(code.to_string()).and_then(s2).and_then(s3).unwrap();
+        super::lint(&ast, &["print".to_string()]).into_iter().map(|lint| (lint.rule, lint.span.unwrap().line)).collect()
+    }
+
+    #[test]
+    fn clean_code_has_no_lints() {
+        assert_eq!(lints("let x = 1;\nfn f(a) { return a + x; }\nprint(f(2));\n"), []);
+    }
+
+    #[test]
+    fn undefined_label() {
+        assert_eq!(lints("goto #nowhere;\n"), [("undefined-label", 1)]);
+        assert_eq!(lints("print(1);\n#here\ngoto #here;\n"), []);
+        // a goto can't jump into a function
+        assert_eq!(lints("fn f() { goto #here; }\n#here\nf();\n"), [("undefined-label", 1)]);
+    }
+
+    #[test]
+    fn unreachable_code() {
+        assert_eq!(lints("fn f() {\n    return 1;\n    print(2);\n}\nf();\n"), [("unreachable-code", 3)]);
+        assert_eq!(lints("throw 1;\nprint(2);\n"), [("unreachable-code", 2)]);
+        assert_eq!(lints("fn f() {\n    if true { return 1; }\n    print(2);\n}\nf();\n"), []);
+    }
+
+    // a label can be jumped to, so the code after it runs again
+    #[test]
+    fn unreachable_code_ends_at_a_label() {
+        assert_eq!(lints("goto #here;\nprint(1);\n#here\nprint(2);\n"), [("unreachable-code", 2)]);
+    }
+
+    #[test]
+    fn unused_variable() {
+        assert_eq!(lints("let x = 1;\n"), [("unused-variable", 1)]);
+        assert_eq!(lints("let x = 1;\nprint(x);\n"), []);
+        // assigning isn't using
+        assert_eq!(lints("let x = 1;\nx = 2;\n"), [("unused-variable", 1)]);
+        // a use in a function declared before the variable counts
+        assert_eq!(lints("fn f() { print(x); }\nlet x = 1;\nf();\n"), []);
+    }
+
+    #[test]
+    fn names_starting_with_an_underscore_can_be_unused() {
+        assert_eq!(lints("let _x = 1;\nfn f(_a) {}\nf(1);\n"), []);
+    }
+
+    #[test]
+    fn unused_parameter() {
+        assert_eq!(lints("fn f(a, b) { return b; }\nf(1, 2);\n"), [("unused-parameter", 1)]);
+        assert_eq!(lints("fn f(a) { return a; }\nf(1);\n"), []);
+    }
+
+    #[test]
+    fn undeclared_assignment() {
+        assert_eq!(lints("x = 1;\n"), [("undeclared-assignment", 1)]);
+        assert_eq!(lints("let x = 1;\nx = 2;\nprint(x);\n"), []);
+        // the function assigns the variable declared after it
+        assert_eq!(lints("fn f() { x = 2; }\nlet x = 1;\nf();\nprint(x);\n"), []);
+    }
+
+    #[test]
+    fn assigning_to_a_global_is_not_undeclared() {
+        assert_eq!(lints("print = print;\n"), []);
+    }
+
+    #[test]
+    fn shorthand_outside_pipe() {
+        assert_eq!(lints("print($);\n"), [("shorthand-outside-pipe", 1)]);
+        assert_eq!(lints("1 |> print($);\n"), []);
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        assert_eq!(lints("break;\n"), [("break-outside-loop", 1)]);
+        assert_eq!(lints("loop { break; }\n"), []);
+    }
+
+    // a function doesn't run in the loop it's declared in
+    #[test]
+    fn break_in_a_function_in_a_loop_is_outside_of_it() {
+        assert_eq!(lints("loop {\n    fn f() { break; }\n    f();\n    break;\n}\n"), [("break-outside-loop", 2)]);
+    }
+
+    #[test]
+    fn continue_outside_loop() {
+        assert_eq!(lints("continue;\n"), [("continue-outside-loop", 1)]);
+        assert_eq!(lints("let n = 0;\nloop {\n    n = n + 1;\n    if n < 3 { continue; }\n    break;\n}\n"), []);
+        assert_eq!(lints("loop {\n    fn f() { continue; }\n    f();\n    break;\n}\n"), [("continue-outside-loop", 2)]);
+    }
+}
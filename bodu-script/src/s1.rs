@@ -1,6 +1,10 @@
-use std::{iter::{self, Peekable}, str::Chars};
+use std::{iter, str::Chars};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+use serde::Serialize;
+
+use crate::{vm::Span, ParseError};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
 pub enum Base {
     B10,
     B2,
@@ -8,7 +12,7 @@ pub enum Base {
     B16,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
 pub enum S1T {
     Identifier(String),
     KeywordLet, // let
@@ -26,6 +30,7 @@ pub enum S1T {
     String(String),
     KeywordTry, // try
     KeywordCatch, // catch
+    KeywordFinally, // finally
     KeywordReturn, // return
     KeywordThrow, // throw]
     KeywordLoop, // loop
@@ -73,6 +78,29 @@ pub enum S1T {
     KeywordPossibly, // possibly
 }
 
+impl S1T {
+    pub fn is_keyword(&self) -> bool {
+        match self {
+            S1T::KeywordLet | S1T::KeywordIf | S1T::True | S1T::False | S1T::KeywordElse | S1T::KeywordUnless | S1T::KeywordOut => true,
+            S1T::KeywordTry | S1T::KeywordCatch | S1T::KeywordFinally | S1T::KeywordReturn | S1T::KeywordThrow => true,
+            S1T::KeywordLoop | S1T::KeywordWhile | S1T::KeywordUntil | S1T::KeywordDefer | S1T::KeywordBind => true,
+            S1T::KeywordGoto | S1T::KeywordFn | S1T::KeywordNull | S1T::KeywordDebug | S1T::KeywordRelease | S1T::KeywordMaybe => true,
+            S1T::KeywordFor | S1T::KeywordIn | S1T::KeywordBefore | S1T::KeywordAgain | S1T::KeywordAfter => true,
+            S1T::KeywordContinue | S1T::KeywordBreak | S1T::KeywordProbably | S1T::KeywordPossibly => true,
+            _ => false,
+        }
+    }
+}
+
+// every keyword, in the order of SYNTAX.md
+pub const KEYWORDS: &[&str] = &[
+    "let", "if", "true", "false", "else", "unless", "out", "try", "catch", "finally", "return", "throw", "loop", "while", "until", "defer",
+    "bind", "goto", "fn", "null", "debug", "release", "maybe", "for", "in", "before", "again", "after", "continue", "break", "possibly", "probably",
+];
+
+// identifiers and labels can't have these (or whitespace)
+pub const INVALID_IDENT_CHARS: &str = "{}+-*/%?=!~<>&|^;@()[].:,\"\'$#";
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum InsideComment {
     No,
@@ -80,16 +108,109 @@ enum InsideComment {
     Multiline, // /**/
 }
 
-pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
-    let mut iterr = contents.chars().peekable();
+// a char iterator that keeps track of where it is
+pub struct Cursor<'a> {
+    chars: Chars<'a>,
+    pos: usize, // byte offset
+    line: usize,
+    col: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(s: &'a str) -> Cursor<'a> {
+        Cursor {
+            chars: s.chars(),
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.chars.clone().next()
+    }
+
+    pub fn next_if(&mut self, f: impl FnOnce(&char) -> bool) -> Option<char> {
+        match self.peek() {
+            Some(ch) if f(&ch) => self.next(),
+            _ => None,
+        }
+    }
+
+    // a zero-width span at the current position
+    pub fn here(&self) -> Span {
+        Span {
+            start: self.pos,
+            end: self.pos,
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    // the span from start to the current position
+    pub fn since(&self, start: Span) -> Span {
+        start.to(self.here())
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.next()?;
+        self.pos += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(ch)
+    }
+}
+
+pub fn s1(contents: String) -> Result<Vec<(S1T, Span)>, ParseError> {
+    let (res, errors) = s1_recover(contents);
+    match errors.into_iter().next() {
+        Some(e) => Err(e),
+        None => Ok(res),
+    }
+}
+
+// s1 but it doesn't stop at the first error, the tokens are still usable by the next stages
+pub fn s1_recover(contents: String) -> (Vec<(S1T, Span)>, Vec<ParseError>) {
+    let (res, _, errors) = s1_trivia(contents);
+    (res, errors)
+}
+
+// s1_recover that also keeps the comments (and the shebang line) with where they are, for tools that print the source back
+pub fn s1_trivia(contents: String) -> (Vec<(S1T, Span)>, Vec<(String, Span)>, Vec<ParseError>) {
+    let mut iterr = Cursor::new(&contents);
+    let mut errors = Vec::new();
     let mut res: Vec<S1T> = Vec::new();
-    let invalid_idents = "{}+-*/%?=!~<>&|^;@()[].:,\"\'$#";
+    let mut spans: Vec<Span> = Vec::new();
+    let mut comments = Vec::new();
+    let invalid_idents = INVALID_IDENT_CHARS;
     let mut inside_comment: InsideComment = InsideComment::No;
-    while let Some(ch) = iterr.next() {
+    if contents.starts_with("#!") {
+        inside_comment = InsideComment::Singleline; // a shebang line is skipped like a comment
+    }
+    let mut start = iterr.here();
+    let mut comment_start = start;
+    loop {
+        while spans.len() < res.len() {
+            spans.push(iterr.since(start)); // the tokens pushed by the last iteration
+        }
+        start = iterr.here();
+        let ch = match iterr.next() {
+            Some(ch) => ch,
+            None => break,
+        };
         match ch {
             ch if inside_comment == InsideComment::Singleline => {
                 if ch == '\n' {
                     inside_comment = InsideComment::No;
+                    comments.push((contents[comment_start.start..start.start].trim_end().to_string(), comment_start.to(start)));
                 }
                 continue;
             },
@@ -97,6 +218,8 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
                 if ch == '*' {
                     if let Some(_) = iterr.next_if(|s| *s == '/') {
                         inside_comment = InsideComment::No;
+                        let end = iterr.here();
+                        comments.push((contents[comment_start.start..end.start].to_string(), comment_start.to(end)));
                     }
                 }
                 continue;
@@ -120,21 +243,24 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
                                 if !"0123456789abcdef".chars().any(|chh| {
                                     ch2.to_lowercase().to_string().chars().nth(0).unwrap() == chh
                                 }) {
-                                    return Err("invalid number".to_string())
+                                    invalid_number(&mut errors, &mut res, iterr.since(start));
+                                    continue;
                                 }
                             },
                             Base::B2 => {
                                 if !"01".chars().any(|chh| {
                                     chh == ch2
                                 }) {
-                                    return Err("invalid number".to_string())
+                                    invalid_number(&mut errors, &mut res, iterr.since(start));
+                                    continue;
                                 }
                             },
                             Base::B8 => {
                                 if !"01234567".chars().any(|chh| {
                                     chh == ch2
                                 }) {
-                                    return Err("invalid number".to_string())
+                                    invalid_number(&mut errors, &mut res, iterr.since(start));
+                                    continue;
                                 }
                             },
                             Base::B10 => {}, // should never happen
@@ -142,7 +268,8 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
                         res.push(parse_number(&mut iterr, base, ch2));
                         continue;
                     }
-                    return Err("invalid number".to_string())
+                    invalid_number(&mut errors, &mut res, iterr.since(start));
+                    continue;
                 }
                 res.push(parse_number(&mut iterr, Base::B10, ch));
             },
@@ -167,6 +294,7 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
                     } else {
                         inside_comment = InsideComment::Multiline;
                     }
+                    comment_start = start;
                     continue;
                 }
                 res.push(S1T::Divide);
@@ -190,10 +318,16 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
             '\"' => {
                 let mut str = String::new();
                 loop {
-                    if let Some(chh) = parse_char(&mut iterr, true)? {
-                        str.push(chh);
-                    } else {
-                        break;
+                    match parse_char(&mut iterr, true) {
+                        Ok(Some(chh)) => str.push(chh),
+                        Ok(None) => break,
+                        Err(e) => {
+                            errors.push(ParseError::new(&e, Some(iterr.since(start))));
+                            // keep going after an invalid escape, an unfinished string ends the file
+                            if iterr.peek().is_none() {
+                                break;
+                            }
+                        },
                     }
                 }
                 res.push(S1T::String(str));
@@ -201,10 +335,16 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
             '\'' => {
                 let mut str = String::new();
                 loop {
-                    if let Some(chh) = parse_char(&mut iterr, false)? {
-                        str.push(chh);
-                    } else {
-                        break;
+                    match parse_char(&mut iterr, false) {
+                        Ok(Some(chh)) => str.push(chh),
+                        Ok(None) => break,
+                        Err(e) => {
+                            errors.push(ParseError::new(&e, Some(iterr.since(start))));
+                            // keep going after an invalid escape, an unfinished string ends the file
+                            if iterr.peek().is_none() {
+                                break;
+                            }
+                        },
                     }
                 }
                 res.push(S1T::String(str));
@@ -241,6 +381,7 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
                     "out" => S1T::KeywordOut,
                     "try" => S1T::KeywordTry,
                     "catch" => S1T::KeywordCatch,
+                    "finally" => S1T::KeywordFinally,
                     "return" => S1T::KeywordReturn,
                     "throw" => S1T::KeywordThrow,
                     "loop" => S1T::KeywordLoop,
@@ -268,7 +409,18 @@ pub fn s1(contents: String) -> Result<Vec<S1T>, String> {
             }
         }
     }
-    Ok(res)
+    // a comment that goes to the end of the file
+    if inside_comment != InsideComment::No {
+        let end = iterr.here();
+        comments.push((contents[comment_start.start..end.start].trim_end().to_string(), comment_start.to(end)));
+    }
+    (res.into_iter().zip(spans).collect(), comments, errors)
+}
+
+// a 0 stands in for the invalid number so the parser can go on
+fn invalid_number(errors: &mut Vec<ParseError>, res: &mut Vec<S1T>, span: Span) {
+    errors.push(ParseError::new("invalid number", Some(span)));
+    res.push(S1T::IntLiteral("0".to_string(), Base::B10));
 }
 
 // don't touch this!
@@ -280,7 +432,7 @@ pub fn from_fn_variable_len<T, F>(mut cb: F) -> Vec<T> where F: FnMut() -> Optio
     vec
 }
 
-fn parse_number(iterr: &mut Peekable<Chars<'_>>, base: Base, ch: char) -> S1T {
+fn parse_number(iterr: &mut Cursor<'_>, base: Base, ch: char) -> S1T {
     let chars = match base {
         Base::B10 => "0123456789",
         Base::B2 => "01",
@@ -310,7 +462,7 @@ fn parse_number(iterr: &mut Peekable<Chars<'_>>, base: Base, ch: char) -> S1T {
     }
 }
 
-pub fn parse_char(iterr: &mut Peekable<Chars<'_>>, doublequotes: bool) -> Result<Option<char>, String> {
+pub fn parse_char(iterr: &mut Cursor<'_>, doublequotes: bool) -> Result<Option<char>, String> {
     let ch = iterr.next();
     if let Some(ch) = ch {
         match ch {
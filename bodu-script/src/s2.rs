@@ -1,8 +1,10 @@
 use std::{iter::Peekable, slice::Iter};
 
-use crate::s1::{Base, S1T};
+use serde::Serialize;
 
-#[derive(Clone, PartialEq, Debug)]
+use crate::{s1::{Base, S1T}, vm::Span, ParseError};
+
+#[derive(Clone, PartialEq, Debug, Serialize)]
 pub enum S2T {
     Identifier(String),
     Let, // let
@@ -22,6 +24,7 @@ pub enum S2T {
     String(String),
     Try, // try
     Catch, // catch
+    Finally, // finally
     Return, // return
     Throw, // throw
     Loop, // loop
@@ -103,10 +106,13 @@ pub enum S2T {
     IsntNullFn, // [?]
 }
 
-pub fn s2(s1: Vec<S1T>) -> Result<Vec<S2T>, String> {
+pub fn s2(s1: Vec<(S1T, Span)>) -> Result<Vec<(S2T, Span)>, ParseError> {
+    let (s1, spans): (Vec<S1T>, Vec<Span>) = s1.into_iter().unzip();
     let mut res = Vec::new();
+    let mut marks = Vec::new();
     let mut iterr = s1.iter().peekable();
     while let Some(i) = iterr.next() {
+        marks.push((s1.len() - iterr.len() - 1, res.len()));
         match i {
             S1T::Identifier(s) => res.push(S2T::Identifier(s.clone())),
             S1T::KeywordLet => res.push(S2T::Let),
@@ -141,6 +147,7 @@ pub fn s2(s1: Vec<S1T>) -> Result<Vec<S2T>, String> {
             S1T::String(s) => res.push(S2T::String(s.clone())),
             S1T::KeywordTry => res.push(S2T::Try),
             S1T::KeywordCatch => res.push(S2T::Catch),
+            S1T::KeywordFinally => res.push(S2T::Finally),
             S1T::KeywordReturn => res.push(S2T::Return),
             S1T::KeywordThrow => res.push(S2T::Throw),
             S1T::KeywordLoop => res.push(S2T::Loop),
@@ -361,10 +368,13 @@ pub fn s2(s1: Vec<S1T>) -> Result<Vec<S2T>, String> {
             S1T::KeywordPossibly => res.push(S2T::Possibly),
         }
     }
+    let spans = map_spans(&marks, &spans, res.len());
     let s2 = res;
     let mut res = Vec::new();
+    let mut marks = Vec::new();
     let mut iterr = s2.iter().peekable();
     while let Some(i) = iterr.next() {
+        marks.push((s2.len() - iterr.len() - 1, res.len()));
         match i {
             S2T::OpenBrack => {
                 if let Some(s) = iterr.next_if(|t| {
@@ -520,7 +530,27 @@ pub fn s2(s1: Vec<S1T>) -> Result<Vec<S2T>, String> {
             i => res.push(i.clone()),
         }
     }
-    Ok(res)
+    let spans = map_spans(&marks, &spans, res.len());
+    Ok(res.into_iter().zip(spans).collect())
+}
+
+// marks has the (input index, output index) at the start of each iteration of a pass.
+// a token made from several input tokens spans all of them, otherwise they map one to one.
+fn map_spans(marks: &[(usize, usize)], spans: &[Span], len: usize) -> Vec<Span> {
+    let mut res = Vec::new();
+    for (n, (i, o)) in marks.iter().enumerate() {
+        let (i2, o2) = match marks.get(n + 1) {
+            Some(v) => *v,
+            None => (spans.len(), len),
+        };
+        if o2 - o == i2 - i {
+            res.extend_from_slice(&spans[*i..i2]);
+        } else {
+            let span = spans[*i].to(spans[i2 - 1]);
+            res.extend((*o..o2).map(|_| span));
+        }
+    }
+    res
 }
 
 fn opfn_helper(iterr: &mut Peekable<Iter<'_, S2T>>, tok: S2T, tokfail: S2T, res: &mut Vec<S2T>) {
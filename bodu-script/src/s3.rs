@@ -1,8 +1,12 @@
-use crate::s2::S2T;
+use std::cell::Cell;
+
+use serde::Serialize;
+
+use crate::{s2::S2T, vm::Span, ParseError};
 
 pub use crate::vm::Operator;
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize)]
 pub enum S3T {
     Identifier(String),
     Let(String, Option<Box<S3T>>), // let: name, expr
@@ -14,7 +18,7 @@ pub enum S3T {
     Number(i64),
     Float(f64),
     String(String),
-    TryCatchFinally(Vec<S3T>, String, Vec<S3T>), // try, catch, finally: try { ... } catch name { ... }
+    TryCatchFinally(Vec<S3T>, Option<(String, Vec<S3T>)>, Option<Vec<S3T>>), // try, catch, finally: try { ... } catch name { ... } finally { ... }
     Return(Box<S3T>), // return: expr
     Throw(Box<S3T>), // throw: expr
     Defer(Vec<S3T>), // defer: { ... }
@@ -60,15 +64,16 @@ pub enum S3T {
     Probably, // probably
     Possibly, // possibly
     IsntNull(Box<S3T>), // ?expr
+    Spanned(Span, Box<S3T>), // a statement and where it is in the source
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 pub enum ConditionType {
     If,
     Unless,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize)]
 pub enum LoopType {
     Loop,
     LoopN(Box<S3T>),
@@ -79,19 +84,132 @@ pub enum LoopType {
     ForUntil(String, Box<S3T>, Box<S3T>),
 }
 
-pub fn s3(input: Vec<S2T>) -> Result<Vec<S3T>, String> {
+// the tokens, their spans and how far the parser has looked
+struct Tokens {
+    toks: Vec<S2T>,
+    spans: Vec<Span>,
+    furthest: Cell<usize>,
+}
+
+impl Tokens {
+    fn get(&self, i: usize) -> Option<&S2T> {
+        if i > self.furthest.get() {
+            self.furthest.set(i.min(self.toks.len()));
+        }
+        self.toks.get(i)
+    }
+
+    fn len(&self) -> usize {
+        self.toks.len()
+    }
+
+    // the span from token start to token end - 1
+    fn span(&self, start: usize, end: usize) -> Span {
+        let end = end.max(start + 1).min(self.spans.len());
+        self.spans[start.min(end - 1)].to(self.spans[end - 1])
+    }
+
+    // the error for when the parser can't go any further
+    fn error(&self) -> ParseError {
+        match self.toks.get(self.furthest.get()) {
+            Some(_) => ParseError::new("couldn't parse", Some(self.spans[self.furthest.get()])),
+            None => {
+                let end = self.spans[self.spans.len() - 1];
+                ParseError::new("couldn't parse, unexpected end of file", Some(Span {
+                    start: end.end,
+                    ..end
+                }))
+            },
+        }
+    }
+}
+
+pub fn s3(input: Vec<(S2T, Span)>) -> Result<Vec<S3T>, ParseError> {
     let mut i: usize = 0;
     if input.len() == 0 {
         return Ok(Vec::new());
     }
+    let (toks, spans) = input.into_iter().unzip();
+    let input = Tokens {
+        toks,
+        spans,
+        furthest: Cell::new(0),
+    };
     let res = stat_list(&input, &mut i);
-    if i < input.len() - 1 {
-        return Err("couldn't parse".to_string());
+    if !parsed_all(&input, i) {
+        return Err(input.error());
+    }
+    res.ok_or_else(|| input.error()).map(|v| v.0)
+}
+
+// s3 but it doesn't stop at the first error: the statement with the error is cut out of the tokens and the parsing starts over
+pub fn s3_recover(input: Vec<(S2T, Span)>) -> (Vec<S3T>, Vec<ParseError>) {
+    let mut errors = Vec::new();
+    let (mut toks, mut spans): (Vec<S2T>, Vec<Span>) = input.into_iter().unzip();
+    loop {
+        if toks.len() == 0 {
+            return (Vec::new(), errors);
+        }
+        let input = Tokens {
+            toks,
+            spans,
+            furthest: Cell::new(0),
+        };
+        let mut i = 0;
+        let res = stat_list(&input, &mut i).map(|v| v.0).unwrap_or_default();
+        if parsed_all(&input, i) {
+            return (res, errors);
+        }
+        errors.push(input.error());
+        let at = input.furthest.get();
+        if at >= input.len() {
+            return (res, errors);
+        }
+        let (start, end) = bad_statement(&input.toks, at);
+        toks = input.toks;
+        spans = input.spans;
+        toks.drain(start..end);
+        spans.drain(start..end);
     }
-    res.ok_or("couldn't parse".to_string()).map(|v| v.0)
 }
 
-fn primary(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+// only a ; at the end is allowed to be left over, so a file can end with }; after a block
+fn parsed_all(input: &Tokens, i: usize) -> bool {
+    i >= input.len() || (i == input.len() - 1 && matches!(input.toks[i], S2T::Semicolon))
+}
+
+// the tokens of the statement the token at is in: from the previous ; { or } to the next ; (included) or } (not included) outside of blocks
+fn bad_statement(toks: &[S2T], at: usize) -> (usize, usize) {
+    let mut start = at;
+    while start > 0 {
+        match toks[start - 1] {
+            S2T::Semicolon | S2T::OpenBrace | S2T::CloseBrace => break,
+            _ => start -= 1,
+        }
+    }
+    let mut end = at;
+    let mut depth = 0;
+    while end < toks.len() {
+        match toks[end] {
+            S2T::OpenBrace => depth += 1,
+            S2T::CloseBrace if depth == 0 => break,
+            S2T::CloseBrace => depth -= 1,
+            S2T::Semicolon if depth == 0 => {
+                end += 1;
+                break;
+            },
+            _ => {},
+        }
+        end += 1;
+    }
+    // a } that doesn't close anything
+    if start == end {
+        end += 1;
+    }
+    (start, end)
+}
+
+fn primary(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match input.get(*i) {
         Some(S2T::Identifier(s)) => {
             *i += 1;
@@ -262,7 +380,7 @@ fn primary(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn field(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn field(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match primary(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -291,7 +409,7 @@ fn field(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
                             Some((vv, nn)) => {
                                 n += nn;
                                 match input.get(*i) {
-                                    Some(S2T::CloseParen) => {
+                                    Some(S2T::CloseBrack) => {
                                         *i += 1;
                                         n += 1;
                                         S3T::Property(Box::new(v), Box::new(vv))
@@ -348,7 +466,7 @@ fn field(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn expr_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<S3T>, usize)> {
+fn expr_list(input: &Tokens, i: &mut usize) -> Option<(Vec<S3T>, usize)> {
     let mut res = Vec::new();
     let mut n = 0;
     match stat_expr(input, i) {
@@ -406,7 +524,7 @@ fn expr_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<S3T>, usize)> {
     Some((res, n))
 }
 
-fn fn_call(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn fn_call(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     field(input, i)
 }
 
@@ -418,7 +536,7 @@ enum UnaryOp {
     IsntNull,
 }
 
-fn unary(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn unary(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     let mut n = 0;
     let op = match input.get(*i) {
         Some(S2T::Minus) => {
@@ -462,7 +580,7 @@ fn unary(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn times_divide(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn times_divide(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match unary(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -520,7 +638,7 @@ fn times_divide(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn plus_minus(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn plus_minus(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match times_divide(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -574,7 +692,7 @@ enum ComparisonOp {
     LessOrEqual,
 }
 
-fn comparison(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn comparison(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match plus_minus(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -616,7 +734,7 @@ fn comparison(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn and(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn and(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match comparison(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -646,7 +764,7 @@ fn and(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn xor(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn xor(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match and(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -676,7 +794,7 @@ fn xor(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn or(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn or(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match xor(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -706,7 +824,7 @@ fn or(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn or_that(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn or_that(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match or(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -736,7 +854,7 @@ fn or_that(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn ternary(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn ternary(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match or_that(input, i) {
         Some((v1, n)) => {
             let mut n = n;
@@ -783,7 +901,7 @@ fn ternary(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn pipe(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn pipe(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match ternary(input, i) {
         Some((v, n)) => {
             let mut v = v;
@@ -813,7 +931,7 @@ fn pipe(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     }
 }
 
-fn expr(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn expr(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     let t = pipe(input, i);
     let t = match t {
         Some(t) => Some(t),
@@ -891,7 +1009,7 @@ enum AssignOp {
     Modulus,
 }
 
-fn stat(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn stat(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     let t = match stat_expr(input, i) {
         Some(t) => Some(t),
         _ => None,
@@ -1143,12 +1261,13 @@ fn stat(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
                                                 n += 1;
                                                 let t = match stat_expr(input, i) {
                                                     Some((v, nn)) => {
-                                                        *i += 1;
                                                         match input.get(*i) {
-                                                            Some(S2T::Semicolon) => Some((S3T::LetDetuple(ident.clone(), Box::new(v)), nn + n + 1)),
+                                                            Some(S2T::Semicolon) => {
+                                                                *i += 1;
+                                                                Some((S3T::LetDetuple(ident.clone(), Box::new(v)), nn + n + 1))
+                                                            },
                                                             _ => {
                                                                 *i -= nn;
-                                                                *i -= 1;
                                                                 None
                                                             },
                                                         }
@@ -1159,12 +1278,13 @@ fn stat(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
                                                     Some(t) => Some(t),
                                                     _ => match expr(input, i) {
                                                         Some((v, nn)) => {
-                                                            *i += 1;
                                                             match input.get(*i) {
-                                                                Some(S2T::Semicolon) => Some((S3T::LetDetuple(ident.clone(), Box::new(v)), nn + n + 1)),
+                                                                Some(S2T::Semicolon) => {
+                                                                    *i += 1;
+                                                                    Some((S3T::LetDetuple(ident.clone(), Box::new(v)), nn + n + 1))
+                                                                },
                                                                 _ => {
                                                                     *i -= nn;
-                                                                    *i -= 1;
                                                                     None
                                                                 },
                                                             }
@@ -1172,7 +1292,13 @@ fn stat(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
                                                         _ => None,
                                                     },
                                                 };
-                                                t
+                                                match t {
+                                                    Some(t) => Some(t),
+                                                    None => {
+                                                        *i -= n;
+                                                        None
+                                                    },
+                                                }
                                             },
                                             Some(S2T::Semicolon) => {
                                                 *i += 1;
@@ -1688,7 +1814,7 @@ fn stat(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     t
 }
 
-fn stat_expr(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn stat_expr(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     let t = match if_list(input, i) {
         Some((v, n)) => {
             let mut n = n;
@@ -1789,7 +1915,7 @@ fn stat_expr(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
                                                     Some(S2T::CloseBrace) => {
                                                         n += 1;
                                                         *i += 1;
-                                                        (s.clone(), v)
+                                                        Some((s.clone(), v))
                                                     },
                                                     _ => {
                                                         *i -= n;
@@ -1815,12 +1941,51 @@ fn stat_expr(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
                             },
                         }
                     },
-                    _ => {
-                        *i -= n;
-                        return None;
+                    _ => None,
+                };
+                let finally = match input.get(*i) {
+                    Some(S2T::Finally) => {
+                        *i += 1;
+                        n += 1;
+                        match input.get(*i) {
+                            Some(S2T::OpenBrace) => {
+                                *i += 1;
+                                n += 1;
+                                match stat_list(input, i) {
+                                    Some((v, nn)) => {
+                                        n += nn;
+                                        match input.get(*i) {
+                                            Some(S2T::CloseBrace) => {
+                                                n += 1;
+                                                *i += 1;
+                                                Some(v)
+                                            },
+                                            _ => {
+                                                *i -= n;
+                                                return None;
+                                            }
+                                        }
+                                    },
+                                    _ => {
+                                        *i -= n;
+                                        return None;
+                                    },
+                                }
+                            },
+                            _ => {
+                                *i -= n;
+                                return None;
+                            },
+                        }
                     },
+                    _ => None,
                 };
-                Some((S3T::TryCatchFinally(try_, catch.0, catch.1), n))
+                // try needs a catch, a finally or both
+                if catch.is_none() && finally.is_none() {
+                    *i -= n;
+                    return None;
+                }
+                Some((S3T::TryCatchFinally(try_, catch, finally), n))
             },
             _ => None,
         },
@@ -1873,7 +2038,7 @@ fn stat_expr(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     t
 }
 
-fn loop_(input: &Vec<S2T>, i: &mut usize) -> Option<((Vec<S3T>, LoopType, Vec<S3T>, Vec<S3T>, Vec<S3T>, Vec<S3T>), usize)> {
+fn loop_(input: &Tokens, i: &mut usize) -> Option<((Vec<S3T>, LoopType, Vec<S3T>, Vec<S3T>, Vec<S3T>, Vec<S3T>), usize)> {
     let mut n = 0;
     let v1 = match input.get(*i) {
         Some(S2T::Before) => {
@@ -2175,7 +2340,7 @@ fn loop_(input: &Vec<S2T>, i: &mut usize) -> Option<((Vec<S3T>, LoopType, Vec<S3
     Some(((v1, v2, v3, v4, v5, v6), n))
 }
 
-fn function(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
+fn function(input: &Tokens, i: &mut usize) -> Option<(S3T, usize)> {
     match input.get(*i) {
         Some(S2T::Fn) => {},
         _ => return None,
@@ -2246,7 +2411,7 @@ fn function(input: &Vec<S2T>, i: &mut usize) -> Option<(S3T, usize)> {
     Some((S3T::Function(name, args, body), n))
 }
 
-fn ident_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<String>, usize)> {
+fn ident_list(input: &Tokens, i: &mut usize) -> Option<(Vec<String>, usize)> {
     let mut res = Vec::new();
     let mut n = 0;
     match input.get(*i) {
@@ -2279,7 +2444,7 @@ fn ident_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<String>, usize)> {
     Some((res, n))
 }
 
-fn if_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, usize)> {
+fn if_list(input: &Tokens, i: &mut usize) -> Option<(Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, usize)> {
     let mut res = Vec::new();
     let mut n = 0;
     {
@@ -2381,24 +2546,70 @@ fn if_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<(ConditionType, Box<S
     Some((res, n))
 }
 
-fn stat_list(input: &Vec<S2T>, i: &mut usize) -> Option<(Vec<S3T>, usize)> {
+fn stat_list(input: &Tokens, i: &mut usize) -> Option<(Vec<S3T>, usize)> {
     let mut res = Vec::new();
     let mut n = 0;
+    let start = *i;
     match stat(input, i) {
         Some((v, nn)) => {
             n += nn;
-            res.push(v);
+            res.push(S3T::Spanned(input.span(start, *i), Box::new(v)));
         },
         _ => return Some((res, n)),
     }
     loop {
+        let start = *i;
         match stat(input, i) {
             Some((v, nn)) => {
                 n += nn;
-                res.push(v);
+                res.push(S3T::Spanned(input.span(start, *i), Box::new(v)));
             },
             _ => break,
         }
     }
     Some((res, n))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{s3, s3_recover, S3T};
+    use crate::{s1::s1, s2::s2};
+
+    // the statements without their spans
+    fn parse(code: &str) -> Result<Vec<S3T>, String> {
+        let ast = s1(code.to_string()).and_then(s2).and_then(s3).map_err(|e| e.message)?;
+        Ok(ast.into_iter().map(unspan).collect())
+    }
+
+    fn unspan(v: S3T) -> S3T {
+        match v {
+            S3T::Spanned(_, v) => unspan(*v),
+            v => v,
+        }
+    }
+
+    fn ident(name: &str) -> Box<S3T> {
+        Box::new(S3T::Identifier(name.to_string()))
+    }
+
+    #[test]
+    fn index_ends_at_the_close_bracket() {
+        assert_eq!(parse("x = a[b];"), Ok(vec![S3T::Assign(ident("x"), Box::new(S3T::Property(ident("a"), ident("b"))))]));
+        assert!(parse("x = a[b);").is_err());
+    }
+
+    #[test]
+    fn let_detuple() {
+        assert_eq!(parse("let (a, b) = x;"), Ok(vec![S3T::LetDetuple(vec!["a".to_string(), "b".to_string()], ident("x"))]));
+    }
+
+    // the detuple gives up without losing a token, so the statements after it parse
+    #[test]
+    fn a_failed_detuple_is_followed_by_a_plain_let() {
+        let tokens = s1("let (a, b) = f() g;\nlet c = 1;\n".to_string()).and_then(s2).unwrap();
+        let (ast, errors) = s3_recover(tokens);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span.map(|span| (span.line, span.col)), Some((1, 18)));
+        assert_eq!(ast.into_iter().map(unspan).collect::<Vec<_>>(), [S3T::Let("c".to_string(), Some(Box::new(S3T::Number(1))))]);
+    }
+}
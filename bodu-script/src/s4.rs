@@ -1,22 +1,167 @@
-use crate::{s3::{ConditionType, LoopType, S3T}, vm::{Instruction, Label, Operator, VarIndex}};
+use std::{collections::HashMap, sync::Arc};
 
-pub fn s4(input: Vec<S3T>) -> Result<Vec<Instruction>, String> {
+use crate::{s3::{ConditionType, LoopType, S3T}, vm::{DebugInfo, Instruction, Label, Operator, Source, Span, VarIndex}, ParseError};
+
+pub fn s4(input: Vec<S3T>, source: Arc<Source>) -> Result<(Vec<Instruction>, DebugInfo), ParseError> {
     let mut tempi: u64 = 1; // outi = 0, conti = 0, breaki = 0
     let mut labeli: u64 = 1; // outli = 0, contli = 0, breakli = 0
-    let mut res = Vec::new();
+    let mut res = Code::default();
     for i in input {
         stat(i, &mut res, &mut tempi, &mut labeli, 0, 0, 0, 0, 0, 0)?;
     }
     res.push(Instruction::Label(Label::Unnamed(0)));
     res.push(Instruction::Return(VarIndex::Temp(0)));
-    Ok(res)
+    let dbg = debug_info(&res.instrs, Some(&res.spans), &source, Some("<top level>".to_string()), None);
+    Ok((res.instrs, dbg))
+}
+
+// like s4, but the value of the last statement is returned if it's an expression, for eval and the repl
+pub fn s4_eval(input: Vec<S3T>, source: Arc<Source>) -> Result<(Vec<Instruction>, DebugInfo), ParseError> {
+    let mut input = input;
+    if let Some(S3T::Spanned(span, v)) = input.pop() {
+        let v = match *v {
+            v if is_expr(&v) => S3T::Out(Box::new(v)), // out at the top level sets what it returns
+            v => v,
+        };
+        input.push(S3T::Spanned(span, Box::new(v)));
+    }
+    s4(input, source)
+}
+
+// whether stat compiles it as an expression
+fn is_expr(v: &S3T) -> bool {
+    match v {
+        S3T::Let(_, _) => false,
+        S3T::Out(_) => false,
+        S3T::Label(_) => false,
+        S3T::Goto(_) => false,
+        S3T::Return(_) => false,
+        S3T::Throw(_) => false,
+        S3T::Defer(_) => false,
+        S3T::Bind(_, _) => false,
+        S3T::Assign(_, _) => false,
+        S3T::Detuple(_, _) => false,
+        S3T::LetDetuple(_, _) => false,
+        S3T::Decorator(_, _) => false,
+        S3T::MultiLet(_) => false,
+        S3T::Break(_) => false,
+        S3T::Continue(_) => false,
+        S3T::Function(Some(_), _, _) => false,
+        _ => true,
+    }
+}
+
+// compiles every top level statement on its own so all of their errors are found, the instructions are thrown away
+pub fn s4_errors(input: Vec<S3T>) -> Vec<ParseError> {
+    let mut tempi: u64 = 1;
+    let mut labeli: u64 = 1;
+    let mut errors = Vec::new();
+    for i in input {
+        let mut res = Code::default();
+        if let Err(e) = stat(i, &mut res, &mut tempi, &mut labeli, 0, 0, 0, 0, 0, 0) {
+            errors.push(e);
+        }
+    }
+    errors
+}
+
+// the instructions and the span of the statement each one came from, side by side. the spans of the nested lists of an
+// instruction (blocks, catch, defer, finally and functions) are kept under its index
+#[derive(Clone, Default)]
+struct Code {
+    instrs: Vec<Instruction>,
+    spans: Spans,
+    span: Option<Span>, // the statement being compiled
+}
+
+#[derive(Clone, Default)]
+struct Spans {
+    spans: Vec<Option<Span>>,
+    children: HashMap<usize, Vec<Spans>>,
+}
+
+impl Code {
+    fn push(&mut self, instr: Instruction) {
+        self.instrs.push(instr);
+        self.spans.spans.push(self.span);
+    }
+
+    // for instructions with nested lists, in the order they're in the instruction
+    fn push_nested(&mut self, instr: Instruction, children: Vec<Spans>) {
+        self.spans.children.insert(self.instrs.len(), children);
+        self.push(instr);
+    }
+
+    // the instructions of other before its first statement get the span of this one, like if they were made here
+    fn extend(&mut self, other: Code) {
+        let offset = self.instrs.len();
+        self.spans.children.extend(other.spans.children.into_iter().map(|(i, c)| (i + offset, c)));
+        self.spans.spans.extend(other.spans.spans.into_iter().map(|s| s.or(self.span)));
+        self.instrs.extend(other.instrs);
+        if other.span.is_some() {
+            self.span = other.span;
+        }
+    }
+}
+
+// instructions without a span get the span of the instruction that has their list, so do nested lists made without Code.
+// blocks keep the name of the function they're in
+fn debug_info(instrs: &[Instruction], spans: Option<&Spans>, source: &Arc<Source>, name: Option<String>, span: Option<Span>) -> DebugInfo {
+    let mut dbg = DebugInfo::new(source.clone(), name);
+    for (j, instr) in instrs.iter().enumerate() {
+        let span = spans.and_then(|s| s.spans.get(j).copied().flatten()).or(span);
+        let child = |k: usize| spans.and_then(|s| s.children.get(&j)).and_then(|c| c.get(k));
+        match instr {
+            Instruction::Block(v) | Instruction::Catch(_, _, v) | Instruction::Defer(v) => {
+                let d = debug_info(v, child(0), source, dbg.name.clone(), span);
+                dbg.children.insert(j, Arc::new(d));
+            },
+            Instruction::Finally(v1, v2) => {
+                let d1 = debug_info(v1, child(0), source, dbg.name.clone(), span);
+                let d2 = debug_info(v2, child(1), source, dbg.name.clone(), span);
+                let mut d = DebugInfo::new(source.clone(), dbg.name.clone());
+                d.spans = vec![span, span];
+                d.children.insert(0, Arc::new(d1));
+                d.children.insert(1, Arc::new(d2));
+                dbg.children.insert(j, Arc::new(d));
+            },
+            Instruction::MakeFunction(r, v) => {
+                // fn name() {} and let name = fn() {} are a MakeFunction, a Decl and an Assign
+                let name = instrs[j + 1..].iter().take(2).find_map(|i| match i {
+                    Instruction::Assign(VarIndex::Ident(name), VarIndex::Temp(t)) => match r {
+                        VarIndex::Temp(r) if r == t => Some(name.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                });
+                let d = debug_info(v, child(0), source, name, span);
+                dbg.children.insert(j, Arc::new(d));
+            },
+            _ => {},
+        }
+        dbg.spans.push(span);
+    }
+    dbg
 }
 
-fn stat(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+// errors without a span get the span of the statement they came from
+fn at(e: ParseError, span: Span) -> ParseError {
+    match e.span {
+        Some(_) => e,
+        None => ParseError::new(&e.message, Some(span)),
+    }
+}
+
+fn stat(v: S3T, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     Ok(match v {
+        S3T::Spanned(span, v) => {
+            res.span = Some(span);
+            stat(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli).map_err(|e| at(e, span))?
+        },
         S3T::Let(name, e) => let_(name, e, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?,
         S3T::Out(v) => out(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?,
         S3T::Label(a) => label(a, res)?,
+        S3T::Goto(a) => goto(a, res)?,
         S3T::Return(v) => return_(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?,
         S3T::Throw(v) => throw(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?,
         S3T::Defer(v) => defer(v, res, tempi, labeli, conti, contli, breaki, breakli)?,
@@ -41,7 +186,7 @@ fn stat(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, o
     })
 }
 
-fn expr(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn expr(v: S3T, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     match v {
         S3T::Identifier(v) => identifier(v),
         S3T::If(a, b) => if_(a, b, res, tempi, labeli, conti, contli, breaki, breakli),
@@ -84,15 +229,16 @@ fn expr(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, o
         S3T::Probably => probably(res, tempi),
         S3T::Possibly => possibly(res, tempi),
         S3T::IsntNull(v) => isnt_null(v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli),
-        _ => Err("invalid expression".to_string()),
+        S3T::Spanned(span, v) => expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli).map_err(|e| at(e, span)),
+        _ => Err(ParseError::new("invalid expression", None)),
     }
 }
 
-fn identifier(v: String) -> Result<VarIndex, String> {
+fn identifier(v: String) -> Result<VarIndex, ParseError> {
     Ok(VarIndex::Ident(v.clone()))
 }
 
-fn let_(name: String, e: Option<Box<S3T>>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn let_(name: String, e: Option<Box<S3T>>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     if let Some(e) = e {
         let e = expr(*e, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
         res.push(Instruction::Decl(VarIndex::Ident(name.clone())));
@@ -104,7 +250,7 @@ fn let_(name: String, e: Option<Box<S3T>>, res: &mut Vec<Instruction>, tempi: &m
     }
 }
 
-fn if_(ifs: Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, else_: Option<Vec<S3T>>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64,  conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn if_(ifs: Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, else_: Option<Vec<S3T>>, res: &mut Code, tempi: &mut u64, labeli: &mut u64,  conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let ifs = ifs.iter().map(|v| {
         match v.0 {
             ConditionType::If => (v.1.clone(), v.2.clone()),
@@ -116,11 +262,11 @@ fn if_(ifs: Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, else_: Option<Vec<S3T>>, r
     let iouti = *tempi;
     *tempi += 1;
     let ifs = ifs.iter().map(|v| {
-        let mut vec_ = Vec::new();
+        let mut vec_ = Code::default();
         for i in v.1.clone() {
             stat(i, &mut vec_, tempi, labeli, iouti, after, conti, contli, breaki, breakli)?;
         }
-        Ok::<_, String>((v.0.clone(), vec_))
+        Ok::<_, ParseError>((v.0.clone(), vec_))
     }).collect::<Vec<_>>();
     let mut ifs2 = Vec::new();
     for i in ifs {
@@ -147,18 +293,18 @@ fn if_(ifs: Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, else_: Option<Vec<S3T>>, r
     }
     for i in labels.iter().zip(ifs.clone()) {
         res.push(Instruction::Label(Label::Unnamed(*i.0)));
-        res.push(Instruction::Block(i.1.1));
+        res.push_nested(Instruction::Block(i.1.1.instrs), vec![i.1.1.spans]);
         res.push(Instruction::Goto(Label::Unnamed(after)));
     }
     match else_ {
         None => {},
         Some(v) => {
             res.push(Instruction::Label(Label::Unnamed(else_label)));
-            let mut vec_ = Vec::new();
+            let mut vec_ = Code::default();
             for i in v {
                 stat(i, &mut vec_, tempi, labeli, iouti, after, conti, contli, breaki, breakli)?;
             }
-            res.push(Instruction::Block(vec_));
+            res.push_nested(Instruction::Block(vec_.instrs), vec![vec_.spans]);
             res.push(Instruction::Goto(Label::Unnamed(after)));
         },
     }
@@ -166,115 +312,151 @@ fn if_(ifs: Vec<(ConditionType, Box<S3T>, Vec<S3T>)>, else_: Option<Vec<S3T>>, r
     Ok(VarIndex::Temp(iouti))
 }
 
-fn boolean(v: bool, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn boolean(v: bool, res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let i = *tempi;
     *tempi += 1;
     res.push(Instruction::Boolean(VarIndex::Temp(i), v));
     Ok(VarIndex::Temp(i))
 }
 
-fn block(v: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn block(v: Vec<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let outli = *labeli;
     *labeli += 1;
     let outi = *tempi;
     *tempi += 1;
-    let mut vec_ = Vec::new();
+    let mut vec_ = Code::default();
     for i in v {
         stat(i, &mut vec_, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     }
-    res.push(Instruction::Block(vec_));
+    res.push_nested(Instruction::Block(vec_.instrs), vec![vec_.spans]);
     res.push(Instruction::Label(Label::Unnamed(outli)));
     Ok(VarIndex::Temp(outi))
 }
 
-fn out(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn out(v: S3T, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let v = expr(v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     res.push(Instruction::Assign(VarIndex::Temp(outi), v));
     res.push(Instruction::Goto(Label::Unnamed(outli)));
     Ok(())
 }
 
-fn label(v: String, res: &mut Vec<Instruction>) -> Result<(), String> {
+fn label(v: String, res: &mut Code) -> Result<(), ParseError> {
     res.push(Instruction::Label(Label::Named(v.clone())));
     Ok(())
 }
 
-fn number(v: i64, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn goto(v: String, res: &mut Code) -> Result<(), ParseError> {
+    res.push(Instruction::Goto(Label::Named(v.clone())));
+    Ok(())
+}
+
+fn number(v: i64, res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let i = *tempi;
     *tempi += 1;
     res.push(Instruction::Number(VarIndex::Temp(i), v));
     Ok(VarIndex::Temp(i))
 }
 
-fn float(v: f64, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn float(v: f64, res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let i = *tempi;
     *tempi += 1;
     res.push(Instruction::Float(VarIndex::Temp(i), v));
     Ok(VarIndex::Temp(i))
 }
 
-fn string(v: String, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn string(v: String, res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let i = *tempi;
     *tempi += 1;
     res.push(Instruction::String(VarIndex::Temp(i), v.clone()));
     Ok(VarIndex::Temp(i))
 }
 
-fn try_catch_finally(try_body: Vec<S3T>, name: String, catch_body: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn try_catch_finally(try_body: Vec<S3T>, catch: Option<(String, Vec<S3T>)>, finally: Option<Vec<S3T>>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let outli = *labeli;
     *labeli += 1;
     let outi = *tempi;
     *tempi += 1;
-    let is_err = *tempi;
-    *tempi += 1;
-    let err = *tempi;
-    *tempi += 1;
-    let catchli = *labeli;
-    *labeli += 1;
-    let mut try_vec = Vec::new();
-    for i in try_body {
-        stat(i, &mut try_vec, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
+    let mut vec_ = Code::default();
+    match catch {
+        Some((name, catch_body)) => {
+            let is_err = *tempi;
+            *tempi += 1;
+            let err = *tempi;
+            *tempi += 1;
+            let catchli = *labeli;
+            *labeli += 1;
+            let mut try_vec = Code::default();
+            for i in try_body {
+                stat(i, &mut try_vec, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
+            }
+            vec_.push_nested(Instruction::Catch(VarIndex::Temp(is_err), VarIndex::Temp(err), try_vec.instrs), vec![try_vec.spans]);
+            vec_.push(Instruction::GotoIf(Label::Unnamed(catchli), VarIndex::Temp(is_err)));
+            vec_.push(Instruction::Goto(Label::Unnamed(outli)));
+            vec_.push(Instruction::Label(Label::Unnamed(catchli)));
+            let mut catch_vec = Code::default();
+            catch_vec.push(Instruction::Decl(VarIndex::Ident(name.clone())));
+            catch_vec.push(Instruction::Assign(VarIndex::Ident(name.clone()), VarIndex::Temp(err)));
+            for i in catch_body {
+                stat(i, &mut catch_vec, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
+            }
+            vec_.push_nested(Instruction::Block(catch_vec.instrs), vec![catch_vec.spans]);
+            vec_.push(Instruction::Goto(Label::Unnamed(outli)));
+        },
+        None => {
+            for i in try_body {
+                stat(i, &mut vec_, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
+            }
+        },
     }
-    res.push(Instruction::Catch(VarIndex::Temp(is_err), VarIndex::Temp(err), try_vec));
-    res.push(Instruction::GotoIf(Label::Unnamed(catchli), VarIndex::Temp(is_err)));
-    res.push(Instruction::Goto(Label::Unnamed(outli)));
-    res.push(Instruction::Label(Label::Unnamed(catchli)));
-    let mut catch_vec = vec![Instruction::Decl(VarIndex::Ident(name.clone())), Instruction::Assign(VarIndex::Ident(name.clone()), VarIndex::Temp(err))];
-    for i in catch_body {
-        stat(i, &mut catch_vec, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
+    match finally {
+        Some(finally_body) => {
+            // out inside the finally block only leaves the finally block, like in defer
+            let finally_outli = *labeli;
+            *labeli += 1;
+            let finally_outi = *tempi;
+            *tempi += 1;
+            let mut finally_vec = Code::default();
+            for i in finally_body {
+                stat(i, &mut finally_vec, tempi, labeli, finally_outi, finally_outli, conti, contli, breaki, breakli)?;
+            }
+            finally_vec.push(Instruction::Label(Label::Unnamed(finally_outli)));
+            res.push_nested(Instruction::Finally(vec_.instrs, finally_vec.instrs), vec![vec_.spans, finally_vec.spans]);
+        },
+        None => res.extend(vec_),
     }
-    res.push(Instruction::Block(catch_vec));
-    res.push(Instruction::Goto(Label::Unnamed(outli)));
     res.push(Instruction::Label(Label::Unnamed(outli)));
     Ok(VarIndex::Temp(outi))
 }
 
-fn return_(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn return_(v: S3T, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let v = expr(v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     res.push(Instruction::Return(v));
     Ok(())
 }
 
-fn throw(v: S3T, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn throw(v: S3T, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let v = expr(v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     res.push(Instruction::Throw(v));
     Ok(())
 }
 
-fn defer(body: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn defer(body: Vec<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let outli = *labeli;
     *labeli += 1;
     let outi = *tempi;
     *tempi += 1;
-    let mut vec_ = Vec::new();
+    let mut vec_ = Code::default();
     for i in body {
         stat(i, &mut vec_, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     }
-    res.push(Instruction::Defer(vec![Instruction::Block(vec_), Instruction::Label(Label::Unnamed(outli))]));
+    let mut defer_vec = Code::default();
+    defer_vec.push_nested(Instruction::Block(vec_.instrs), vec![vec_.spans]);
+    defer_vec.push(Instruction::Label(Label::Unnamed(outli)));
+    res.push_nested(Instruction::Defer(defer_vec.instrs), vec![defer_vec.spans]);
     Ok(())
 }
 
-fn bind(name: String, v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<(), String> {
+fn bind(name: String, v: Box<S3T>, res: &mut Code, tempi: &mut u64) -> Result<(), ParseError> {
     let f = fn_(vec![], vec![S3T::Return(v)], res, tempi)?;
     let vi = *tempi;
     *tempi += 1;
@@ -284,10 +466,10 @@ fn bind(name: String, v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64)
     Ok(())
 }
 
-fn fn_(args: Vec<String>, body: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn fn_(args: Vec<String>, body: Vec<S3T>, res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let mut tempi2: u64 = 1; // outi = 0, conti = 0, breaki = 0
     let mut labeli2: u64 = 1; // outli = 0, contli = 0, breakli = 0
-    let mut res2 = Vec::new();
+    let mut res2 = Code::default();
     for i in args.iter().enumerate() {
         res2.push(Instruction::Decl(VarIndex::Ident(i.1.clone())));
         res2.push(Instruction::Assign(VarIndex::Ident(i.1.clone()), VarIndex::Arg(i.0)));
@@ -299,12 +481,12 @@ fn fn_(args: Vec<String>, body: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mu
     res2.push(Instruction::Return(VarIndex::Temp(0)));
     let vi = *tempi;
     *tempi += 1;
-    res.push(Instruction::MakeFunction(VarIndex::Temp(vi), res2));
+    res.push_nested(Instruction::MakeFunction(VarIndex::Temp(vi), res2.instrs), vec![res2.spans]);
     Ok(VarIndex::Temp(vi))
 }
 
 
-fn assign(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn assign(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     match *left {
         S3T::Identifier(s) => res.push(Instruction::Assign(VarIndex::Ident(s), right)),
@@ -315,12 +497,12 @@ fn assign(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &m
             let prop = expr(*prop, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
             res.push(Instruction::Set(VarIndex::Temp(res_), obj, prop, right));
         },
-        _ => return Err("invalid assignment".to_string())
+        _ => return Err(ParseError::new("invalid assignment", None))
     }
     Ok(())
 }
 
-fn plus(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn plus(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -329,7 +511,7 @@ fn plus(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn minus(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn minus(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -338,7 +520,7 @@ fn minus(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mu
     Ok(VarIndex::Temp(vi))
 }
 
-fn negate(v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn negate(v: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let v = expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
     *tempi += 1;
@@ -346,7 +528,7 @@ fn negate(v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn times(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn times(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -355,7 +537,7 @@ fn times(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mu
     Ok(VarIndex::Temp(vi))
 }
 
-fn divide(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn divide(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -364,7 +546,7 @@ fn divide(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &m
     Ok(VarIndex::Temp(vi))
 }
 
-fn remainder(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn remainder(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -373,13 +555,13 @@ fn remainder(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi:
     Ok(VarIndex::Temp(vi))
 }
 
-fn ternary(cond: Box<S3T>, i: Box<S3T>, e: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn ternary(cond: Box<S3T>, i: Box<S3T>, e: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let ifs = vec![(ConditionType::If, cond, vec![S3T::Out(i)])];
     let else_ = Some(vec![S3T::Out(e)]);
     if_(ifs, else_, res, tempi, labeli, conti, contli, breaki, breakli)
 }
 
-fn eql(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn eql(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -388,7 +570,7 @@ fn eql(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn not(v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn not(v: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let v = expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
     *tempi += 1;
@@ -396,7 +578,7 @@ fn not(v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u6
     Ok(VarIndex::Temp(vi))
 }
 
-fn neql(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn neql(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -405,7 +587,7 @@ fn neql(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn gt(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn gt(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -414,7 +596,7 @@ fn gt(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u
     Ok(VarIndex::Temp(vi))
 }
 
-fn ge(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn ge(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -423,7 +605,7 @@ fn ge(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u
     Ok(VarIndex::Temp(vi))
 }
 
-fn lt(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn lt(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -432,7 +614,7 @@ fn lt(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u
     Ok(VarIndex::Temp(vi))
 }
 
-fn le(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn le(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -441,7 +623,7 @@ fn le(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u
     Ok(VarIndex::Temp(vi))
 }
 
-fn and(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn and(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -450,7 +632,7 @@ fn and(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn or(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn or(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -459,7 +641,7 @@ fn or(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u
     Ok(VarIndex::Temp(vi))
 }
 
-fn xor(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn xor(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -468,7 +650,7 @@ fn xor(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn prop(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn prop(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -477,7 +659,7 @@ fn prop(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn tuple(v: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn tuple(v: Vec<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let mut v2 = Vec::new();
     for i in v {
         v2.push(expr(i, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?);
@@ -489,7 +671,7 @@ fn tuple(v: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn detuple(r: Vec<S3T>, v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn detuple(r: Vec<S3T>, v: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let vi = r.iter().map(|_| {
         let i = *tempi;
         *tempi += 1;
@@ -507,13 +689,13 @@ fn detuple(r: Vec<S3T>, v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64
                 let prop = expr(*prop, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
                 res.push(Instruction::Set(VarIndex::Temp(res_), obj, prop, i.1));
             },
-            _ => return Err("invalid assignment".to_string())
+            _ => return Err(ParseError::new("invalid assignment", None))
         }
     }
     Ok(())
 }
 
-fn let_detuple(r: Vec<String>, v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn let_detuple(r: Vec<String>, v: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let vi = r.iter().map(|_| {
         let i = *tempi;
         *tempi += 1;
@@ -528,7 +710,7 @@ fn let_detuple(r: Vec<String>, v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &
     Ok(())
 }
 
-fn fn_call(v: Box<S3T>, args: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn fn_call(v: Box<S3T>, args: Vec<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let v = expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let mut args2 = Vec::new();
     for i in args {
@@ -541,7 +723,7 @@ fn fn_call(v: Box<S3T>, args: Vec<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     Ok(VarIndex::Temp(vi))
 }
 
-fn decorator(d: Box<S3T>, f: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn decorator(d: Box<S3T>, f: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     let d = expr(*d, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     match *f {
         S3T::Function(Some(name), args, body) => {
@@ -553,7 +735,7 @@ fn decorator(d: Box<S3T>, f: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u
             res.push(Instruction::Assign(VarIndex::Ident(name.clone()), VarIndex::Temp(i2)));
             Ok(())
         },
-        _ => Err("decorators can only be used with named functions".to_string()),
+        _ => Err(ParseError::new("decorators can only be used with named functions", None)),
     }
 }
 
@@ -589,13 +771,20 @@ fn includes_fnshorthand(v: Box<S3T>) -> bool {
             b
         },
         S3T::Out(v) => includes_fnshorthand(v),
-        S3T::TryCatchFinally(v1, _, v2) => {
+        S3T::TryCatchFinally(v1, v2, v3) => {
             let mut b = false;
             for i in v1 {
                 b = b || includes_fnshorthand(Box::new(i));
             }
-            for i in v2 {
-                b = b || includes_fnshorthand(Box::new(i));
+            if let Some((_, v2)) = v2 {
+                for i in v2 {
+                    b = b || includes_fnshorthand(Box::new(i));
+                }
+            }
+            if let Some(v3) = v3 {
+                for i in v3 {
+                    b = b || includes_fnshorthand(Box::new(i));
+                }
             }
             b
         },
@@ -662,21 +851,22 @@ fn includes_fnshorthand(v: Box<S3T>) -> bool {
             b
         },
         S3T::IsntNull(v) => includes_fnshorthand(v),
+        S3T::Spanned(_, v) => includes_fnshorthand(v),
         _ => false,
     }
 }
 
-fn pipe(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn pipe(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     if includes_fnshorthand(right.clone()) {
         let i = VarIndex::Temp(*tempi);
         *tempi += 1;
         res.push(Instruction::GetPipeShorthand(i.clone()));
         let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
-        let mut vec_ = Vec::new();
+        let mut vec_ = Code::default();
         vec_.push(Instruction::SetPipeShorthand(left));
         let right = expr(*right, &mut vec_, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
         vec_.push(Instruction::Defer(vec![Instruction::SetPipeShorthand(i)]));
-        res.push(Instruction::Block(vec_));
+        res.push_nested(Instruction::Block(vec_.instrs), vec![vec_.spans]);
         Ok(right)
     } else {
         let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
@@ -688,7 +878,7 @@ fn pipe(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut
     }
 }
 
-fn orthat(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn orthat(left: Box<S3T>, right: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let left = expr(*left, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let right = expr(*right, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
@@ -697,55 +887,55 @@ fn orthat(left: Box<S3T>, right: Box<S3T>, res: &mut Vec<Instruction>, tempi: &m
     Ok(VarIndex::Temp(vi))
 }
 
-fn operatorfn(operator: Operator, res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn operatorfn(operator: Operator, res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let vi = *tempi;
     *tempi += 1;
     res.push(Instruction::OperatorFn(VarIndex::Temp(vi), operator));
     Ok(VarIndex::Temp(vi))
 }
 
-fn multilet(v: Vec<String>, res: &mut Vec<Instruction>) -> Result<(), String> {
+fn multilet(v: Vec<String>, res: &mut Code) -> Result<(), ParseError> {
     for i in v {
         res.push(Instruction::Decl(VarIndex::Ident(i)));
     }
     Ok(())
 }
 
-fn null(tempi: &mut u64) -> Result<VarIndex, String> {
+fn null(tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     Ok(VarIndex::Temp(r))
 }
 
-fn pipe_shorthand(res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn pipe_shorthand(res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     res.push(Instruction::GetPipeShorthand(VarIndex::Temp(r)));
     Ok(VarIndex::Temp(r))
 }
 
-fn debug(res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn debug(res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     res.push(Instruction::Debug(VarIndex::Temp(r)));
     Ok(VarIndex::Temp(r))
 }
 
-fn release(res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn release(res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     res.push(Instruction::Release(VarIndex::Temp(r)));
     Ok(VarIndex::Temp(r))
 }
 
-fn maybe(res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn maybe(res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     res.push(Instruction::Maybe(VarIndex::Temp(r)));
     Ok(VarIndex::Temp(r))
 }
 
-fn break_(v: Option<Box<S3T>>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn break_(v: Option<Box<S3T>>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     match v {
         Some(v) => {
             let vi = expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
@@ -760,7 +950,7 @@ fn break_(v: Option<Box<S3T>>, res: &mut Vec<Instruction>, tempi: &mut u64, labe
     }
 }
 
-fn continue_(v: Option<Box<S3T>>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), String> {
+fn continue_(v: Option<Box<S3T>>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<(), ParseError> {
     match v {
         Some(v) => {
             let vi = expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
@@ -775,7 +965,7 @@ fn continue_(v: Option<Box<S3T>>, res: &mut Vec<Instruction>, tempi: &mut u64, l
     }
 }
 
-fn loop_(v: (Vec<S3T>, LoopType, Vec<S3T>, Vec<S3T>, Vec<S3T>, Vec<S3T>), res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn loop_(v: (Vec<S3T>, LoopType, Vec<S3T>, Vec<S3T>, Vec<S3T>, Vec<S3T>), res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let beforeli = *labeli;
     *labeli += 1;
     let condli = *labeli;
@@ -809,7 +999,7 @@ fn loop_(v: (Vec<S3T>, LoopType, Vec<S3T>, Vec<S3T>, Vec<S3T>, Vec<S3T>), res: &
         },
         _ => None,
     };
-    let mut vec_ = Vec::new();
+    let mut vec_ = Code::default();
     vec_.push(Instruction::Label(Label::Unnamed(beforeli)));
     for i in v.0 {
         stat(i, &mut vec_, tempi, labeli, outi2, outli2, outi2, outli2, elsei, elseli)?;
@@ -917,25 +1107,25 @@ fn loop_(v: (Vec<S3T>, LoopType, Vec<S3T>, Vec<S3T>, Vec<S3T>, Vec<S3T>), res: &
     }
     vec_.push(Instruction::Goto(Label::Unnamed(outli2)));
     vec_.push(Instruction::Label(Label::Unnamed(outli2)));
-    res.push(Instruction::Block(vec_));
+    res.push_nested(Instruction::Block(vec_.instrs), vec![vec_.spans]);
     Ok(VarIndex::Temp(outi2))
 }
 
-fn probably(res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn probably(res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     res.push(Instruction::Probably(VarIndex::Temp(r)));
     Ok(VarIndex::Temp(r))
 }
 
-fn possibly(res: &mut Vec<Instruction>, tempi: &mut u64) -> Result<VarIndex, String> {
+fn possibly(res: &mut Code, tempi: &mut u64) -> Result<VarIndex, ParseError> {
     let r = *tempi;
     *tempi += 1;
     res.push(Instruction::Possibly(VarIndex::Temp(r)));
     Ok(VarIndex::Temp(r))
 }
 
-fn isnt_null(v: Box<S3T>, res: &mut Vec<Instruction>, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, String> {
+fn isnt_null(v: Box<S3T>, res: &mut Code, tempi: &mut u64, labeli: &mut u64, outi: u64, outli: u64, conti: u64, contli: u64, breaki: u64, breakli: u64) -> Result<VarIndex, ParseError> {
     let v = expr(*v, res, tempi, labeli, outi, outli, conti, contli, breaki, breakli)?;
     let vi = *tempi;
     *tempi += 1;
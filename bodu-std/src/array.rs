@@ -2,7 +2,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use tokio::sync::Mutex;
 
-use crate::vm::{make_container, make_err, op::{make_object, make_object_base, make_tuple, set_base, to_number_base, to_string_base}, Container, Function, Gi, StateContainer, Value};
+use crate::vm::{make_container, make_err, op::{make_object, make_object_base, make_tuple, set_base, to_number_base, to_string_base}, Container, ErrorKind, Function, Gi, StateContainer, Value};
 
 // Function pointer wrappers for array operations
 fn array_get_wrapper(state: StateContainer, args: Vec<Container>, gi: Gi) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Container, Container>> + Send>> {
@@ -141,7 +141,7 @@ pub async fn new_with_vec(state: StateContainer, data: Vec<Container>) -> Result
 
 pub async fn is_array(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("array.is_array requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "array.is_array requires 1 argument"));
     }
     let o = args[0].clone();
     let o = o.lock().await.clone();
@@ -171,7 +171,7 @@ async fn get(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Cont
     let mut o = o.lock().await;
     let o = o.downcast_mut::<Vec<Container>>().unwrap();
     if args.len() == 0 {
-        return Err(make_container(Value::String("array.get requires 1 argument".to_string())))
+        return Err(make_err(ErrorKind::Argument, "array.get requires 1 argument"))
     }
     let mut i = to_number_base(state.clone(), args[0].clone()).await?;
     if i < -(o.len() as i64) || i >= o.len() as i64 {
@@ -193,7 +193,7 @@ async fn set(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Cont
     let mut o = o.lock().await;
     let o = o.downcast_mut::<Vec<Container>>().unwrap();
     if args.len() < 2 {
-        return Err(make_container(Value::String("array.set requires 2 argument".to_string())))
+        return Err(make_err(ErrorKind::Argument, "array.set requires 2 argument"))
     }
     let mut i = to_number_base(state.clone(), args[0].clone()).await?;
     if i < -(o.len() as i64) || i >= o.len() as i64 {
@@ -262,7 +262,7 @@ async fn iter(state: StateContainer, _: Vec<Container>, gi: Gi) -> Result<Contai
 
 async fn meta_add(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("array's metaobj add requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "array's metaobj add requires 2 arguments"));
     }
     let a = {
         let o = args[0].clone();
@@ -271,16 +271,16 @@ async fn meta_add(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
             _ => None,
         } {
             Some(o) => o,
-            _ => return Err(make_err("array's metaobj add requires 2 arrays")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj add requires 2 arrays")),
         };
         let o = match o.externals.get(&0) {
             Some(o) => o.clone(),
-            _ => return Err(make_err("array's metaobj add requires 2 arrays")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj add requires 2 arrays")),
         };
         let mut o = o.lock().await;
         let o = match o.downcast_mut::<Vec<Container>>() {
             Some(o) => o.clone(),
-            _ => return Err(make_err("array's metaobj add requires 2 arrays")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj add requires 2 arrays")),
         };
         o
     };
@@ -291,16 +291,16 @@ async fn meta_add(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
             _ => None,
         } {
             Some(o) => o,
-            _ => return Err(make_err("array's metaobj add requires 2 arrays")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj add requires 2 arrays")),
         };
         let o = match o.externals.get(&0) {
             Some(o) => o.clone(),
-            _ => return Err(make_err("array's metaobj add requires 2 arrays")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj add requires 2 arrays")),
         };
         let mut o = o.lock().await;
         let o = match o.downcast_mut::<Vec<Container>>() {
             Some(o) => o.clone(),
-            _ => return Err(make_err("array's metaobj add requires 2 arrays")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj add requires 2 arrays")),
         };
         o
     };
@@ -309,7 +309,7 @@ async fn meta_add(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 async fn meta_to_string(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("array's metaobj to_string requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "array's metaobj to_string requires 1 argument"));
     }
     let a = {
         let o = args[0].clone();
@@ -318,16 +318,16 @@ async fn meta_to_string(state: StateContainer, args: Vec<Container>, _: Gi) -> R
             _ => None,
         } {
             Some(o) => o,
-            _ => return Err(make_err("array's metaobj to_string requires 1 array")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj to_string requires 1 array")),
         };
         let o = match o.externals.get(&0) {
             Some(o) => o.clone(),
-            _ => return Err(make_err("array's metaobj to_string requires 1 array")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj to_string requires 1 array")),
         };
         let mut o = o.lock().await;
         let o = match o.downcast_mut::<Vec<Container>>() {
             Some(o) => o.clone(),
-            _ => return Err(make_err("array's metaobj to_string requires 1 array")),
+            _ => return Err(make_err(ErrorKind::Type, "array's metaobj to_string requires 1 array")),
         };
         o
     };
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+
+use crate::vm::{make_container, make_err, make_error, op::{call, eql_base, get_base, is_error, is_exit, resolve_bind, to_float_base, to_string_base}, Container, ErrorKind, Gi, ObjectProp, StateContainer, Value};
+
+// the elements of an array, None if v isn't one
+async fn array_items(v: &Container) -> Option<Vec<Container>> {
+    let o = match &*v.lock().await {
+        Value::Object(o) => o.clone(),
+        _ => return None,
+    };
+    match o.internals.get(&u64::MAX) {
+        Some(tag) if matches!(&*tag.lock().await, Value::String(s) if s == "array") => {},
+        _ => return None,
+    }
+    let items = o.externals.get(&0)?.clone();
+    let items = items.lock().await;
+    items.downcast_ref::<Vec<Container>>().cloned()
+}
+
+// the properties of an object that isn't an array, getters are called
+async fn object_props(state: StateContainer, v: &Container) -> Result<Option<Vec<(String, Container)>>, Container> {
+    let o = match &*v.lock().await {
+        Value::Object(o) => o.clone(),
+        _ => return Ok(None),
+    };
+    let mut props = Vec::new();
+    for (name, prop) in o.props.iter() {
+        let v = match prop {
+            ObjectProp::Value(v) => v.clone(),
+            ObjectProp::GetSet(get, _) => call(state.clone(), get.clone(), Vec::new()).await?,
+        };
+        props.push((name.clone(), v));
+    }
+    props.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(Some(props))
+}
+
+// a value the way it's written, strings are quoted and arrays and objects show what's in them
+async fn show(state: StateContainer, v: Container) -> String {
+    let v = match resolve_bind(state.clone(), v).await {
+        Ok(v) => v,
+        Err(_) => return "<bind>".to_string(),
+    };
+    if let Some(items) = array_items(&v).await {
+        let mut s = Vec::new();
+        for i in items {
+            s.push(Box::pin(show(state.clone(), i)).await);
+        }
+        return format!("[{}]", s.join(", "));
+    }
+    let plain = match &*v.lock().await {
+        Value::String(s) => return format!("{:?}", s),
+        Value::Function(_) => return "<function>".to_string(),
+        Value::Object(o) => o.internals.is_empty(),
+        _ => false,
+    };
+    if plain && let Ok(Some(props)) = object_props(state.clone(), &v).await {
+        let mut s = Vec::new();
+        for (name, v) in props {
+            s.push(format!("{}: {}", name, Box::pin(show(state.clone(), v)).await));
+        }
+        return format!("{{{}}}", s.join(", "));
+    }
+    match to_string_base(state.clone(), v).await {
+        Ok(s) => s,
+        Err(_) => "<object>".to_string(),
+    }
+}
+
+// every place where a and b aren't equal, one line each. arrays are compared element by element and objects property by property,
+// seen has the pairs being compared already so cyclic values don't go on forever
+async fn diff(state: StateContainer, a: Container, b: Container, path: String, seen: &mut Vec<(Container, Container)>, out: &mut Vec<String>) -> Result<(), Container> {
+    let a = resolve_bind(state.clone(), a).await?;
+    let b = resolve_bind(state.clone(), b).await?;
+    if Arc::ptr_eq(&a, &b) || seen.iter().any(|(x, y)| Arc::ptr_eq(x, &a) && Arc::ptr_eq(y, &b)) {
+        return Ok(());
+    }
+    seen.push((a.clone(), b.clone()));
+    if let (Some(x), Some(y)) = (array_items(&a).await, array_items(&b).await) {
+        for i in 0..x.len().max(y.len()) {
+            let path = format!("{}[{}]", path, i);
+            match (x.get(i), y.get(i)) {
+                (Some(x), Some(y)) => Box::pin(diff(state.clone(), x.clone(), y.clone(), path, seen, out)).await?,
+                (Some(x), None) => out.push(format!("{}: {} != nothing", path, show(state.clone(), x.clone()).await)),
+                (None, Some(y)) => out.push(format!("{}: nothing != {}", path, show(state.clone(), y.clone()).await)),
+                (None, None) => {},
+            }
+        }
+        return Ok(());
+    }
+    let both_objects = matches!((&*a.lock().await, &*b.lock().await), (Value::Object(x), Value::Object(y)) if !is_special(x) && !is_special(y));
+    if both_objects && let (Some(x), Some(y)) = (object_props(state.clone(), &a).await?, object_props(state.clone(), &b).await?) {
+        let mut names = x.iter().chain(y.iter()).map(|(name, _)| name.clone()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        for name in names {
+            let path = format!("{}.{}", path, name);
+            let x = x.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone());
+            let y = y.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone());
+            match (x, y) {
+                (Some(x), Some(y)) => Box::pin(diff(state.clone(), x, y, path, seen, out)).await?,
+                (Some(x), None) => out.push(format!("{}: {} != nothing", path, show(state.clone(), x).await)),
+                (None, Some(y)) => out.push(format!("{}: nothing != {}", path, show(state.clone(), y).await)),
+                (None, None) => {},
+            }
+        }
+        return Ok(());
+    }
+    if !eql_base(state.clone(), a.clone(), b.clone()).await? {
+        out.push(format!("{}: {} != {}", path, show(state.clone(), a).await, show(state.clone(), b).await));
+    }
+    Ok(())
+}
+
+// objects made by the standard library (errors, arrays, ...) are only equal if they are the same object
+fn is_special(o: &crate::vm::Object) -> bool {
+    !o.internals.is_empty() || !o.externals.is_empty()
+}
+
+// throws an assert error, the message given to the assert function goes first
+async fn fail(state: StateContainer, message: Option<&Container>, s: String) -> Result<Container, Container> {
+    let s = match message {
+        Some(m) => format!("{}: {}", to_string_base(state.clone(), m.clone()).await?, s),
+        None => s,
+    };
+    Err(make_error("assert", &s, make_container(Value::Null)))
+}
+
+pub async fn eq(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() < 2 {
+        return Err(make_err(ErrorKind::Argument, "assert.eq requires 2 or 3 arguments"));
+    }
+    let mut out = Vec::new();
+    diff(state.clone(), args[0].clone(), args[1].clone(), String::new(), &mut Vec::new(), &mut out).await?;
+    if out.is_empty() {
+        return Ok(make_container(Value::Null));
+    }
+    let s = match out.as_slice() {
+        [line] if line.starts_with(':') => format!("assert.eq failed{}", line),
+        _ => format!("assert.eq failed, the differences are:\n    {}", out.join("\n    ")),
+    };
+    fail(state.clone(), args.get(2), s).await
+}
+
+pub async fn ne(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() < 2 {
+        return Err(make_err(ErrorKind::Argument, "assert.ne requires 2 or 3 arguments"));
+    }
+    let mut out = Vec::new();
+    diff(state.clone(), args[0].clone(), args[1].clone(), String::new(), &mut Vec::new(), &mut out).await?;
+    if !out.is_empty() {
+        return Ok(make_container(Value::Null));
+    }
+    let s = format!("assert.ne failed: both are {}", show(state.clone(), args[0].clone()).await);
+    fail(state.clone(), args.get(2), s).await
+}
+
+pub async fn throws(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() == 0 {
+        return Err(make_err(ErrorKind::Argument, "assert.throws requires 1 or 2 arguments"));
+    }
+    let e = match call(state.clone(), args[0].clone(), Vec::new()).await {
+        Ok(_) => return fail(state.clone(), None, "assert.throws failed: the function didn't throw".to_string()).await,
+        Err(e) if is_exit(e.clone()).await => return Err(e),
+        Err(e) => e,
+    };
+    if let Some(kind) = args.get(1) {
+        let kind = to_string_base(state.clone(), kind.clone()).await?;
+        let thrown = if is_error(e.clone()).await {
+            Some(to_string_base(state.clone(), get_base(state.clone(), e.clone(), "kind".to_string()).await?).await?)
+        } else {
+            None
+        };
+        if thrown.as_ref() != Some(&kind) {
+            let s = format!("assert.throws failed: expected a {} error, the function threw {}", kind, show(state.clone(), e).await);
+            return fail(state.clone(), None, s).await;
+        }
+    }
+    Ok(e)
+}
+
+pub async fn approx(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() < 2 {
+        return Err(make_err(ErrorKind::Argument, "assert.approx requires 2 or 3 arguments"));
+    }
+    let a = to_float_base(state.clone(), args[0].clone()).await?;
+    let b = to_float_base(state.clone(), args[1].clone()).await?;
+    let epsilon = match args.get(2) {
+        Some(e) => to_float_base(state.clone(), e.clone()).await?,
+        None => 1e-9,
+    };
+    if (a - b).abs() <= epsilon {
+        return Ok(make_container(Value::Null));
+    }
+    fail(state.clone(), None, format!("assert.approx failed: {} and {} are {} apart, more than {}", a, b, (a - b).abs(), epsilon)).await
+}
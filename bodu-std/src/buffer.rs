@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::Arc};
 use bodu_vm::op::make_tuple;
 use tokio::sync::Mutex;
 
-use crate::vm::{make_container, make_err, op::{make_object, make_object_base, set_base, to_number_base, to_string_base}, Container, Function, Gi, StateContainer, Value};
+use crate::vm::{make_container, make_err, op::{make_object, make_object_base, set_base, to_number_base, to_string_base}, Container, ErrorKind, Function, Gi, StateContainer, Value};
 
 macro_rules! helper1 {
     ($state:expr, $fcall:expr, $o:expr, $prop:expr) => {{
@@ -40,7 +40,7 @@ macro_rules! helper2 {
 
 pub async fn from_string_utf8(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("buffer.from_string_utf8 requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "buffer.from_string_utf8 requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let s = s.as_bytes().to_vec();
@@ -49,7 +49,7 @@ pub async fn from_string_utf8(state: StateContainer, args: Vec<Container>, _: Gi
 
 pub async fn from_string_utf16be(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("buffer.from_string_utf16be requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "buffer.from_string_utf16be requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let s = s.encode_utf16().flat_map(|v| v.to_be_bytes()).collect();
@@ -58,7 +58,7 @@ pub async fn from_string_utf16be(state: StateContainer, args: Vec<Container>, _:
 
 pub async  fn from_string_utf16le(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("buffer.from_string_utf16le requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "buffer.from_string_utf16le requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let s = s.encode_utf16().flat_map(|v| v.to_le_bytes()).collect();
@@ -93,7 +93,7 @@ async fn get(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Cont
     let mut o = o.lock().await;
     let o = o.downcast_mut::<Vec<u8>>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("buffer.get requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "buffer.get requires 1 argument"));
     }
     let mut i = to_number_base(state.clone(), args[0].clone()).await?;
     if i < -(o.len() as i64) || i >= o.len() as i64 {
@@ -119,7 +119,7 @@ async fn len(_: StateContainer, _: Vec<Container>, gi: Gi) -> Result<Container,
 
 async fn meta_to_string(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("buffer's metaobj to_string requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "buffer's metaobj to_string requires 1 argument"));
     }
     let a = {
         let o = args[0].clone();
@@ -128,16 +128,16 @@ async fn meta_to_string(_: StateContainer, args: Vec<Container>, _: Gi) -> Resul
             _ => None,
         } {
             Some(o) => o,
-            _ => return Err(make_err("buffer's metaobj to_string requires 1 buffer")),
+            _ => return Err(make_err(ErrorKind::Type, "buffer's metaobj to_string requires 1 buffer")),
         };
         let o = match o.externals.get(&0) {
             Some(o) => o.clone(),
-            _ => return Err(make_err("buffer's metaobj to_string requires 1 buffer")),
+            _ => return Err(make_err(ErrorKind::Type, "buffer's metaobj to_string requires 1 buffer")),
         };
         let mut o = o.lock().await;
         let o = match o.downcast_mut::<Vec<u8>>() {
             Some(o) => o.clone(),
-            _ => return Err(make_err("buffer's metaobj to_string requires 1 buffer")),
+            _ => return Err(make_err(ErrorKind::Type, "buffer's metaobj to_string requires 1 buffer")),
         };
         o
     };
@@ -157,7 +157,7 @@ async fn to_string_utf8(_: StateContainer, _: Vec<Container>, gi: Gi) -> Result<
     let s = String::from_utf8(o.clone());
     match s {
         Ok(s) => Ok(make_container(Value::String(s))),
-        Err(_) => Err(make_err("buffer.to_string_utf8 can't decode invalid utf8")),
+        Err(_) => Err(make_err(ErrorKind::Value, "buffer.to_string_utf8 can't decode invalid utf8")),
     }
 }
 
@@ -213,7 +213,7 @@ async fn to_string_utf16be(_: StateContainer, _: Vec<Container>, gi: Gi) -> Resu
     let mut o = o.lock().await;
     let o = o.downcast_mut::<Vec<u8>>().unwrap();
     if o.len() % 2 != 0 {
-        return Err(make_err("buffer.to_string_utf16be can't decode invalid buffer"))
+        return Err(make_err(ErrorKind::Value, "buffer.to_string_utf16be can't decode invalid buffer"))
     }
     let u16_slice = o
         .chunks_exact(2)
@@ -222,7 +222,7 @@ async fn to_string_utf16be(_: StateContainer, _: Vec<Container>, gi: Gi) -> Resu
     let s = String::from_utf16(&u16_slice);
     match s {
         Ok(s) => Ok(make_container(Value::String(s))),
-        Err(_) => Err(make_err("buffer.to_string_utf16be can't decode invalid buffer")),
+        Err(_) => Err(make_err(ErrorKind::Value, "buffer.to_string_utf16be can't decode invalid buffer")),
     }
 }
 
@@ -236,7 +236,7 @@ async fn to_string_utf16le(_: StateContainer, _: Vec<Container>, gi: Gi) -> Resu
     let mut o = o.lock().await;
     let o = o.downcast_mut::<Vec<u8>>().unwrap();
     if o.len() % 2 != 0 {
-        return Err(make_err("buffer.to_string_utf16le can't decode invalid buffer"))
+        return Err(make_err(ErrorKind::Value, "buffer.to_string_utf16le can't decode invalid buffer"))
     }
     let u16_slice = o
         .chunks_exact(2)
@@ -245,6 +245,6 @@ async fn to_string_utf16le(_: StateContainer, _: Vec<Container>, gi: Gi) -> Resu
     let s = String::from_utf16(&u16_slice);
     match s {
         Ok(s) => Ok(make_container(Value::String(s))),
-        Err(_) => Err(make_err("buffer.to_string_utf16le can't decode invalid buffer")),
+        Err(_) => Err(make_err(ErrorKind::Value, "buffer.to_string_utf16le can't decode invalid buffer")),
     }
 }
\ No newline at end of file
@@ -0,0 +1,25 @@
+use crate::vm::{make_container, make_err, make_error, op::{is_error as is_error_base, set_base, stack_trace, to_string_base}, Container, ErrorKind, Gi, StateContainer, Value};
+
+pub async fn new(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() < 2 {
+        return Err(make_err(ErrorKind::Argument, "error.new requires 2 or 3 arguments"));
+    }
+    let kind = to_string_base(state.clone(), args[0].clone()).await?;
+    let message = to_string_base(state.clone(), args[1].clone()).await?;
+    let cause = match args.get(2) {
+        Some(v) => v.clone(),
+        None => make_container(Value::Null),
+    };
+    // the stack is where the error was made, a vm error gets its stack when it's caught
+    let e = make_error(&kind, &message, cause);
+    let stack = stack_trace(state.clone()).await.iter().map(|f| f.describe()).collect::<Vec<_>>().join("\n");
+    set_base(state.clone(), e.clone(), "stack".to_string(), make_container(Value::String(stack))).await?;
+    Ok(e)
+}
+
+pub async fn is_error(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() == 0 {
+        return Err(make_err(ErrorKind::Argument, "error.is_error requires 1 argument"));
+    }
+    Ok(make_container(Value::Boolean(is_error_base(args[0].clone()).await)))
+}
@@ -2,7 +2,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use tokio::sync::Mutex;
 
-use crate::vm::{make_container, make_err, op::{call, eql_base, make_object_base, set_base, to_string_base}, Container, Function, Gi, StateContainer, Value};
+use crate::vm::{make_container, make_err, op::{call, eql_base, make_object_base, set_base, to_string_base}, Container, ErrorKind, Function, Gi, StateContainer, Value};
 
 #[derive(Clone)]
 struct EventsData {
@@ -81,7 +81,7 @@ async fn on(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Conta
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() < 2 {
-        return Err(make_err("event.on requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "event.on requires 2 arguments"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     let f = args[1].clone();
@@ -101,7 +101,7 @@ async fn once(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Con
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() < 2 {
-        return Err(make_err("event.once requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "event.once requires 2 arguments"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     let f = args[1].clone();
@@ -121,7 +121,7 @@ async fn off(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Cont
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() < 2 {
-        return Err(make_err("event.off requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "event.off requires 2 arguments"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     let f = args[1].clone();
@@ -161,7 +161,7 @@ async fn isactive(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("event.isactive requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "event.isactive requires 1 argument"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     init_event(o, name.clone());
@@ -178,7 +178,7 @@ async fn activate(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("event.activate requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "event.activate requires 1 argument"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     init_event(o, name.clone());
@@ -196,7 +196,7 @@ async fn deactivate(state: StateContainer, args: Vec<Container>, gi: Gi) -> Resu
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("event.deactivate requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "event.deactivate requires 1 argument"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     init_event(o, name.clone());
@@ -214,7 +214,7 @@ async fn clear(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Co
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("event.clear requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "event.clear requires 1 argument"));
     }
     let name = to_string_base(state.clone(), args[0].clone()).await?;
     o.data.remove(&name);
@@ -231,7 +231,7 @@ async fn emit(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result<Con
     let mut o = o.lock().await;
     let o = o.downcast_mut::<EventsData>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("event.emit requires at least 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "event.emit requires at least 1 argument"));
     }
     let mut args = args.clone();
     let name = to_string_base(state.clone(), args.remove(0)).await?;
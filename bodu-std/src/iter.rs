@@ -3,11 +3,11 @@ use std::{collections::HashMap, sync::Arc};
 use bodu_vm::op::{add, get_base, gt, lt, make_object, set_base, to_number_base, to_string_base};
 use tokio::sync::Mutex;
 
-use crate::{array::{self, new_with_vec}, vm::{make_container, make_err, op::{call, call_prop, detuple, make_object_base, make_tuple, to_boolean_base}, Container, Function, Gi, StateContainer, Value}};
+use crate::{array::{self, new_with_vec}, vm::{make_container, make_err, op::{call, call_prop, detuple, make_object_base, make_tuple, to_boolean_base}, Container, ErrorKind, Function, Gi, StateContainer, Value}};
 
 pub async fn collect(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.collect requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.collect requires 1 argument"))
     }
     let arg = args[0].clone();
     let mut v = Vec::new();
@@ -15,14 +15,14 @@ pub async fn collect(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
         let r = call(state.clone(), arg.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.collect")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.collect")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.collect")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.collect")),
             Some(v) => v.clone(),
         };
         v.push(rv);
@@ -32,7 +32,7 @@ pub async fn collect(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
 
 pub async fn reverse(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.reverse requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.reverse requires 1 argument"))
     }
     let arg = args[0].clone();
     let mut v = Vec::new();
@@ -40,14 +40,14 @@ pub async fn reverse(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
         let r = call(state.clone(), arg.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.collect")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.collect")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.collect")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.collect")),
             Some(v) => v.clone(),
         };
         v.push(rv);
@@ -88,7 +88,7 @@ fn reverse_next_wrapper(_: StateContainer, _: Vec<Container>, gi: Gi) -> std::pi
 
 pub async fn all(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("iter.all requires 2 arguments"))
+        return Err(make_err(ErrorKind::Argument, "iter.all requires 2 arguments"))
     }
     let i = args[0].clone();
     let f = args[1].clone();
@@ -96,14 +96,14 @@ pub async fn all(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.all")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.all")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.all")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.all")),
             Some(v) => v.clone(),
         };
         let rv = call(state.clone(), f.clone(), vec![rv]).await?;
@@ -116,7 +116,7 @@ pub async fn all(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn any(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("iter.any requires 2 arguments"))
+        return Err(make_err(ErrorKind::Argument, "iter.any requires 2 arguments"))
     }
     let i = args[0].clone();
     let f = args[1].clone();
@@ -124,14 +124,14 @@ pub async fn any(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.any")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.any")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.any")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.any")),
             Some(v) => v.clone(),
         };
         let rv = call(state.clone(), f.clone(), vec![rv]).await?;
@@ -144,7 +144,7 @@ pub async fn any(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn chain(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("iter.chain requires 2 arguments"))
+        return Err(make_err(ErrorKind::Argument, "iter.chain requires 2 arguments"))
     }
     let f = args[0].clone();
     let g = args[1].clone();
@@ -160,12 +160,12 @@ pub async fn chain(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
                     let r = call(state.clone(), f, Vec::new()).await?;
                     let r = detuple(state.clone(), r).await?;
                     let b = match r.get(0) {
-                        None => return Err(make_err("invalid iterator passed to iter.chain")),
+                        None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.chain")),
                         Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                     };
                     if b {
                         let rv = match r.get(1) {
-                            None => return Err(make_err("invalid iterator passed to iter.chain")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.chain")),
                             Some(v) => v.clone(),
                         };
                         Ok(make_tuple(vec![make_container(Value::Boolean(true)), rv]))
@@ -174,12 +174,12 @@ pub async fn chain(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
                         let r = call(state.clone(), f, Vec::new()).await?;
                         let r = detuple(state.clone(), r).await?;
                         let b = match r.get(0) {
-                            None => return Err(make_err("invalid iterator passed to iter.chain")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.chain")),
                             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                         };
                         if b {
                             let rv = match r.get(1) {
-                                None => return Err(make_err("invalid iterator passed to iter.chain")),
+                                None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.chain")),
                                 Some(v) => v.clone(),
                             };
                             Ok(make_tuple(vec![make_container(Value::Boolean(true)), rv]))
@@ -198,7 +198,7 @@ pub async fn chain(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn cycle(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.cycle requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.cycle requires 1 argument"))
     }
     let f = args[0].clone();
     let g = {
@@ -215,19 +215,19 @@ pub async fn cycle(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
                     let obj = &mut *obj.lock().await;
                     let obj = match obj {
                         Value::Object(obj) => obj,
-                        _ => return Err(make_err("data corrupted")),
+                        _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                     };
                     let arr = obj.internals[&0].clone();
                     let f = obj.internals[&1].clone();
                     let r = call(state.clone(), f, Vec::new()).await?;
                     let r = detuple(state.clone(), r).await?;
                     let b = match r.get(0) {
-                        None => return Err(make_err("invalid iterator passed to iter.cycle")),
+                        None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.cycle")),
                         Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                     };
                     if b {
                         let rv = match r.get(1) {
-                            None => return Err(make_err("invalid iterator passed to iter.chain")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.chain")),
                             Some(v) => v.clone(),
                         };
                         call_prop(state.clone(), arr.clone(), vec![rv.clone()], "push".to_string()).await?;
@@ -241,12 +241,12 @@ pub async fn cycle(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
                         let r = call(state.clone(), f, Vec::new()).await?;
                         let r = detuple(state.clone(), r).await?;
                         let b = match r.get(0) {
-                            None => return Err(make_err("invalid iterator passed to iter.cycle")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.cycle")),
                             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                         };
                         if b {
                             let rv = match r.get(1) {
-                                None => return Err(make_err("invalid iterator passed to iter.chain")),
+                                None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.chain")),
                                 Some(v) => v.clone(),
                             };
                             call_prop(state.clone(), arr.clone(), vec![rv.clone()], "push".to_string()).await?;
@@ -266,7 +266,7 @@ pub async fn cycle(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn count(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.count requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.count requires 1 argument"))
     }
     let i = args[0].clone();
     let mut count = 0;
@@ -274,7 +274,7 @@ pub async fn count(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.count")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.count")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
@@ -287,7 +287,7 @@ pub async fn count(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn enumerate(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.enumerate requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.enumerate requires 1 argument"))
     }
     let f = args[0].clone();
     let g = {
@@ -307,12 +307,12 @@ pub async fn enumerate(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
                     let r = call(state.clone(), f.clone(), vec![]).await?;
                     let r = detuple(state.clone(), r).await?;
                     let b = match r.get(0) {
-                        None => return Err(make_err("invalid iterator passed to iter.enumerate")),
+                        None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.enumerate")),
                         Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                     };
                     if b {
                         let rv = match r.get(1) {
-                            None => return Err(make_err("invalid iterator passed to iter.enumerate")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.enumerate")),
                             Some(v) => v.clone(),
                         };
                         set_base(state.clone(), obj.clone(), "i".to_string(), make_container(Value::Number(i+1))).await?;
@@ -331,7 +331,7 @@ pub async fn enumerate(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn filter(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("iter.filter requires 2 arguments"))
+        return Err(make_err(ErrorKind::Argument, "iter.filter requires 2 arguments"))
     }
     let it = args[0].clone();
     let f = args[1].clone();
@@ -349,12 +349,12 @@ pub async fn filter(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
                         let r = call(state.clone(), it.clone(), vec![]).await?;
                         let r = detuple(state.clone(), r).await?;
                         let b = match r.get(0) {
-                            None => return Err(make_err("invalid iterator passed to iter.filter")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.filter")),
                             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                         };
                         if b {
                             let rv = match r.get(1) {
-                                None => return Err(make_err("invalid iterator passed to iter.filter")),
+                                None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.filter")),
                                 Some(v) => v.clone(),
                             };
                             let b = call(state.clone(), f.clone(), vec![rv.clone()]).await?;
@@ -378,7 +378,7 @@ pub async fn filter(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
 
 pub async fn map(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("iter.map requires 2 arguments"))
+        return Err(make_err(ErrorKind::Argument, "iter.map requires 2 arguments"))
     }
     let it = args[0].clone();
     let f = args[1].clone();
@@ -395,12 +395,12 @@ pub async fn map(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
                     let r = call(state.clone(), it.clone(), vec![]).await?;
                     let r = detuple(state.clone(), r).await?;
                     let b = match r.get(0) {
-                        None => return Err(make_err("invalid iterator passed to iter.map")),
+                        None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.map")),
                         Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                     };
                     if b {
                         let rv = match r.get(1) {
-                            None => return Err(make_err("invalid iterator passed to iter.map")),
+                            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.map")),
                             Some(v) => v.clone(),
                         };
                         let rv = call(state.clone(), f.clone(), vec![rv.clone()]).await?;
@@ -419,7 +419,7 @@ pub async fn map(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn sum(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.sum requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.sum requires 1 argument"))
     }
     let i = args[0].clone();
     let mut sum = make_container(Value::Number(0));
@@ -427,14 +427,14 @@ pub async fn sum(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.sum")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.sum")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.sum")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.sum")),
             Some(v) => v.clone(),
         };
         sum = add(state.clone(), sum, rv).await?;
@@ -444,7 +444,7 @@ pub async fn sum(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn min(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.min requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.min requires 1 argument"))
     }
     let i = args[0].clone();
     let mut min = make_container(Value::Number(0));
@@ -452,14 +452,14 @@ pub async fn min(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.min")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.min")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             return Ok(min);
         }
         min = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.min")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.min")),
             Some(v) => v.clone(),
         };
     }
@@ -467,14 +467,14 @@ pub async fn min(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.min")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.min")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.min")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.min")),
             Some(v) => v.clone(),
         };
         let lower = lt(state.clone(), rv.clone(), min.clone()).await?;
@@ -487,7 +487,7 @@ pub async fn min(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn max(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("iter.max requires 1 argument"))
+        return Err(make_err(ErrorKind::Argument, "iter.max requires 1 argument"))
     }
     let i = args[0].clone();
     let mut max = make_container(Value::Number(0));
@@ -495,14 +495,14 @@ pub async fn max(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.max")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.max")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             return Ok(max);
         }
         max = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.max")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.max")),
             Some(v) => v.clone(),
         };
     }
@@ -510,14 +510,14 @@ pub async fn max(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.max")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.max")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.max")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.max")),
             Some(v) => v.clone(),
         };
         let greater = gt(state.clone(), rv.clone(), max.clone()).await?;
@@ -530,7 +530,7 @@ pub async fn max(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn join(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("iter.join requires 2 arguments"))
+        return Err(make_err(ErrorKind::Argument, "iter.join requires 2 arguments"))
     }
     let i = args[0].clone();
     let sep = to_string_base(state.clone(), args[1].clone()).await?;
@@ -539,14 +539,14 @@ pub async fn join(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.join")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.join")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             return Ok(make_container(Value::String(res)));
         }
         let r =  match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.join")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.join")),
             Some(v) => to_string_base(state.clone(), v.clone()).await?,
         };
         res += &r;
@@ -555,14 +555,14 @@ pub async fn join(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
         let r = call(state.clone(), i.clone(), Vec::new()).await?;
         let r = detuple(state.clone(), r).await?;
         let b = match r.get(0) {
-            None => return Err(make_err("invalid iterator passed to iter.join")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.join")),
             Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
         };
         if !b {
             break;
         }
         let rv = match r.get(1) {
-            None => return Err(make_err("invalid iterator passed to iter.join")),
+            None => return Err(make_err(ErrorKind::Type, "invalid iterator passed to iter.join")),
             Some(v) => v.clone(),
         };
         res += &sep;
@@ -1,10 +1,10 @@
 use std::sync::Arc;
 
-use crate::{array, vm::{make_container, make_err, op::{call, make_object_base}, Container, Gi, ObjectProp, StateContainer, Value}};
+use crate::{array, vm::{make_container, make_err, op::{call, make_object_base}, Container, ErrorKind, Gi, ObjectProp, StateContainer, Value}};
 
 pub async fn encode(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("json.encode requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "json.encode requires 1 argument"));
     }
     let mut visited = Vec::new();
     let v = encode_base(state.clone(), args[0].clone(), &mut visited).await?;
@@ -13,7 +13,7 @@ pub async fn encode(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
 
 async fn encode_base(state: StateContainer, v: Container, visited: &mut Vec<Container>) -> Result<serde_json::Value, Container> {
     if visited.iter().any(|a| Arc::ptr_eq(a, &v)) {
-        return Err(make_err("json.encode was called with a cyclic object"))
+        return Err(make_err(ErrorKind::Value, "json.encode was called with a cyclic object"))
     }
     visited.push(v.clone());
     let isarr = {
@@ -41,11 +41,11 @@ async fn encode_base(state: StateContainer, v: Container, visited: &mut Vec<Cont
         Value::Null => Ok(serde_json::Value::Null),
         Value::Number(a) => match serde_json::Number::from_f64(*a as f64) {
             Some(a) => Ok(serde_json::Value::Number(a)),
-            None => Err(make_err("json.encode was unable to convert number or float")),
+            None => Err(make_err(ErrorKind::Value, "json.encode was unable to convert number or float")),
         },
         Value::Float(a) => match serde_json::Number::from_f64(*a) {
             Some(a) => Ok(serde_json::Value::Number(a)),
-            None => Err(make_err("json.encode was unable to convert number or float")),
+            None => Err(make_err(ErrorKind::Value, "json.encode was unable to convert number or float")),
         },
         Value::String(a) => Ok(serde_json::Value::String(a.clone())),
         Value::Boolean(a) => Ok(serde_json::Value::Bool(*a)),
@@ -55,12 +55,12 @@ async fn encode_base(state: StateContainer, v: Container, visited: &mut Vec<Cont
                 let o = {
                     let o = match o.externals.get(&0) {
                         Some(o) => o.clone(),
-                        _ => return Err(make_err("json.encode can't encode array")),
+                        _ => return Err(make_err(ErrorKind::Type, "json.encode can't encode array")),
                     };
                     let mut o = o.lock().await;
                     let o = match o.downcast_mut::<Vec<Container>>() {
                         Some(o) => o.clone(),
-                        _ => return Err(make_err("json.encode can't encode array")),
+                        _ => return Err(make_err(ErrorKind::Type, "json.encode can't encode array")),
                     };
                     o
                 };
@@ -79,20 +79,20 @@ async fn encode_base(state: StateContainer, v: Container, visited: &mut Vec<Cont
                 Ok(serde_json::Value::Object(map))
             }
         },
-        _ => Err(make_err("json.encode cannot encode tuples or functions")),
+        _ => Err(make_err(ErrorKind::Type, "json.encode cannot encode tuples or functions")),
     }
 }
 
 pub async fn decode(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("json.decode requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "json.decode requires 1 argument"));
     }
     let a = args[0].clone();
     let a = match a.lock().await.clone() {
         Value::String(s) => s,
-        _ => return Err(make_err("json.decode requires 1 string")),
+        _ => return Err(make_err(ErrorKind::Type, "json.decode requires 1 string")),
     };
-    let v: serde_json::Value = serde_json::from_str(&a).map_err(|_| make_err("json.decode couldn't decode the JSON string"))?;
+    let v: serde_json::Value = serde_json::from_str(&a).map_err(|_| make_err(ErrorKind::Parse, "json.decode couldn't decode the JSON string"))?;
     decode_base(state.clone(), v).await
 }
 
@@ -103,7 +103,7 @@ async fn decode_base(state: StateContainer, v: serde_json::Value) -> Result<Cont
             let a = a.as_f64();
             match a {
                 Some(a) => Ok(make_container(Value::Float(a))),
-                None => Err(make_err("json.decode couldn't decode a float")),
+                None => Err(make_err(ErrorKind::Parse, "json.decode couldn't decode a float")),
             }
         },
         serde_json::Value::Bool(a) => Ok(make_container(Value::Boolean(a))),
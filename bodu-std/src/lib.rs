@@ -8,10 +8,12 @@ use base64::Engine;
 
 use tokio::sync::Mutex;
 
-use crate::vm::{make_container, make_err, op::{call, make_object, make_object_base, make_tuple, resolve_bind, set_base, to_boolean, to_float, to_number, to_number_base, to_string, to_string_base}, Container, Function, Gi, GlobalData, State, StateContainer, Value};
+use crate::vm::{make_container, make_err, make_exit, op::{call, make_object, make_object_base, make_tuple, resolve_bind, set_base, to_boolean, to_float, to_number, to_number_base, to_string, to_string_base}, Container, ErrorKind, Function, Gi, GlobalData, Source, State, StateContainer, Task, Value, TASK};
 
 mod array;
+mod assert;
 mod buffer;
+mod error;
 mod event;
 mod iter;
 mod json;
@@ -101,6 +103,10 @@ pub async fn new_global_state(debug: bool, path: PathBuf) -> StateContainer {
         register: HashMap::new(),
         pkgs_release: HashMap::new(),
         pkgs_debug: HashMap::new(),
+        errtrace: None,
+        task: Arc::new(Task::default()),
+        hook: None,
+        limits: None,
     }));
     s.lock().await.globaldata = Some(gd);
     s
@@ -128,6 +134,14 @@ pub async fn init_global_state(state: StateContainer, args_: Vec<String>) {
         make_function!(state, array_object, "new", array::new, "array.new");
         set_base(state.clone(), scope.clone(), "array".to_string(), array_object).await.unwrap();
     }
+    {
+        let assert_obj = make_object();
+        make_function!(state, assert_obj, "approx", assert::approx, "assert.approx");
+        make_function!(state, assert_obj, "eq", assert::eq, "assert.eq");
+        make_function!(state, assert_obj, "ne", assert::ne, "assert.ne");
+        make_function!(state, assert_obj, "throws", assert::throws, "assert.throws");
+        set_base(state.clone(), scope.clone(), "assert".to_string(), assert_obj).await.unwrap();
+    }
     make_function_true!(state, scope, "async", async_, "async");
     make_function!(state, scope, "atob", atob, "atob");
     make_function!(state, scope, "await", await_, "await");
@@ -144,12 +158,19 @@ pub async fn init_global_state(state: StateContainer, args_: Vec<String>) {
     }
     make_function!(state, scope, "chr", chr, "chr");
     make_function!(state, scope, "eprint", eprint, "eprint");
+    {
+        let error_obj = make_object();
+        make_function!(state, error_obj, "is_error", error::is_error, "error.is_error");
+        make_function!(state, error_obj, "new", error::new, "error.new");
+        set_base(state.clone(), scope.clone(), "error".to_string(), error_obj).await.unwrap();
+    }
     {
         let event_obj = make_object();
         make_function!(state, event_obj, "new", event::new, "event.new");
         set_base(state.clone(), scope.clone(), "event".to_string(), event_obj).await.unwrap();
     }
     make_function!(state, scope, "exec", exec, "exec");
+    make_function!(state, scope, "exit", exit, "exit");
     make_function!(state, scope, "float", float, "float");
     make_function!(state, scope, "from_bin", from_bin, "from_bin");
     make_function!(state, scope, "from_hex", from_hex, "from_hex");
@@ -282,6 +303,7 @@ pub async fn init_global_state(state: StateContainer, args_: Vec<String>) {
         make_function!(state, regex_obj, "splitn", regex::splitn, "regex.splitn");
         set_base(state.clone(), scope.clone(), "regex".to_string(), regex_obj).await.unwrap();
     }
+    make_function!(state, scope, "set_exit_code", set_exit_code, "set_exit_code");
     make_function!(state, scope, "sleep", sleep, "sleep");
     make_function!(state, scope, "stderr", stderr, "stderr");
     make_function!(state, scope, "stdout", stdout, "stdout");
@@ -432,7 +454,7 @@ async fn input(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Con
 
 async fn async_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_container(Value::String("async requires 1 argument".to_string())))
+        return Err(make_err(ErrorKind::Argument, "async requires 1 argument"))
     }
     let f = args[0].clone();
     let mut internals = HashMap::new();
@@ -452,9 +474,9 @@ async fn async_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
                     let f = f.clone();
                     let state = state.clone();
                     let args = args.clone();
-                    async move {
+                    TASK.scope(Arc::new(Task::default()), async move {
                         let _ = tx.send(call(state, f, args).await);
-                    }
+                    })
                 }));
                 let mut obj = make_object_base();
                 obj.externals.insert(0, Arc::new(Mutex::new(Box::new(tid))));
@@ -469,28 +491,28 @@ async fn async_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
 
 async fn await_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_container(Value::String("await requires 1 argument".to_string())))
+        return Err(make_err(ErrorKind::Argument, "await requires 1 argument"))
     }
     let p = resolve_bind(state.clone(), args[0].clone()).await?;
     let p = match p.lock().await.clone() {
         Value::Object(obj) => obj,
-        _ => return Err(make_container(Value::String("await requires its argument to be a promise".to_string()))),
+        _ => return Err(make_err(ErrorKind::Argument, "await requires its argument to be a promise")),
     };
     let p = match p.externals.get(&0) {
-        None => return Err(make_container(Value::String("await requires its argument to be a promise".to_string()))),
+        None => return Err(make_err(ErrorKind::Argument, "await requires its argument to be a promise")),
         Some(a) => a,
     };
     let mut p = p.lock().await;
     let p = p.downcast_mut::<u64>();
     let p = match p {
         Some(a) => *a,
-        None => return Err(make_container(Value::String("await requires its argument to be a promise".to_string()))),
+        None => return Err(make_err(ErrorKind::Argument, "await requires its argument to be a promise")),
     };
     let p = {
         let threads = &mut *state.lock().await;
         let threads = &mut *threads.globaldata.as_mut().unwrap().lock().await;
         if threads.threadawaited.contains_key(&p) {
-            return Err(make_err("promise passed to await was already awaited"));
+            return Err(make_err(ErrorKind::Value, "promise passed to await was already awaited"));
         }
         threads.threadawaited.insert(p, ());
         threads.threadresult.remove(&p).unwrap()
@@ -500,7 +522,7 @@ async fn await_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
 
 async fn awaitfn(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("awaitfn requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "awaitfn requires 1 argument"));
     }
     let mut internals = HashMap::new();
     internals.insert(0, args[0].clone());
@@ -522,7 +544,7 @@ async fn awaitfn(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 async fn string(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     let args = args[1..].to_vec();
     if args.len() == 0 {
-        return Err(make_container(Value::String("string requires 1 argument".to_string())))
+        return Err(make_err(ErrorKind::Argument, "string requires 1 argument"))
     }
     let v = args[0].clone();
     to_string(state.clone(), v).await
@@ -530,7 +552,7 @@ async fn string(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
 
 async fn range(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("range requires from 1 to 3 arguments"))
+        return Err(make_err(ErrorKind::Argument, "range requires from 1 to 3 arguments"))
     }
     let mut step: i64 = 1;
     let mut start: i64 = 0;
@@ -561,22 +583,22 @@ async fn range(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Con
                     let obj = &mut *obj.lock().await;
                     let obj = match obj {
                         Value::Object(obj) => obj,
-                        _ => return Err(make_err("data corrupted")),
+                        _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                     };
                     let start = match obj.internals.get(&0).unwrap().clone().lock().await.clone() {
                         Value::Number(n) => n,
-                        _ => return Err(make_err("data corrupted")),
+                        _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                     };
                     let stop = match obj.internals.get(&1).unwrap().clone().lock().await.clone() {
                         Value::Number(n) => n,
-                        _ => return Err(make_err("data corrupted")),
+                        _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                     };
                     let step = match obj.internals.get(&2).unwrap().clone().lock().await.clone() {
                         Value::Number(n) => n,
-                        _ => return Err(make_err("data corrupted")),
+                        _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                     };
                     if step == 0 {
-                        return Err(make_err("a step of 0 was passed into range"))
+                        return Err(make_err(ErrorKind::Value, "a step of 0 was passed into range"))
                     }
                     let cond = if step > 0 {
                         start < stop
@@ -600,7 +622,7 @@ async fn range(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Con
 
 async fn btoa(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("btoa requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "btoa requires 1 argument"));
     }
     let data = {
         let o = args[0].clone();
@@ -609,16 +631,16 @@ async fn btoa(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Containe
             _ => None,
         } {
             Some(o) => o,
-            _ => return Err(make_err("btoa requires 1 buffer")),
+            _ => return Err(make_err(ErrorKind::Type, "btoa requires 1 buffer")),
         };
         let o = match o.externals.get(&0) {
             Some(o) => o.clone(),
-            _ => return Err(make_err("btoa requires 1 buffer")),
+            _ => return Err(make_err(ErrorKind::Type, "btoa requires 1 buffer")),
         };
         let mut o = o.lock().await;
         let o = match o.downcast_mut::<Vec<u8>>() {
             Some(o) => o.clone(),
-            _ => return Err(make_err("btoa requires 1 buffer")),
+            _ => return Err(make_err(ErrorKind::Type, "btoa requires 1 buffer")),
         };
         o
     };
@@ -630,34 +652,34 @@ async fn btoa(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Containe
 
 async fn atob(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("atob requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "atob requires 1 argument"));
     }
     let data = to_string_base(state.clone(), args[0].clone()).await?;
 
-    let output = base64::engine::general_purpose::STANDARD.decode(data).map_err(|_| make_err("error decoding base64 string"))?;
+    let output = base64::engine::general_purpose::STANDARD.decode(data).map_err(|_| make_err(ErrorKind::Parse, "error decoding base64 string"))?;
 
     buffer::new_from_vec(state.clone(), output).await
 }
 
 async fn chr(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("chr requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "chr requires 1 argument"));
     }
     let a = to_number_base(state.clone(), args[0].clone()).await?;
     let a = char::from_u32(a as u32);
     match a {
         Some(a) => Ok(make_container(Value::String(a.to_string()))),
-        None => Err(make_err("chr received an invalid codepoint")),
+        None => Err(make_err(ErrorKind::Value, "chr received an invalid codepoint")),
     }
 }
 
 async fn ord(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("ord requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "ord requires 1 argument"));
     }
     let a = to_string_base(state.clone(), args[0].clone()).await?;
     if a.len() == 0 {
-        Err(make_err("ord received an empty string"))
+        Err(make_err(ErrorKind::Value, "ord received an empty string"))
     } else {
         Ok(make_container(Value::Number((a.chars().next().unwrap() as u32) as i64)))
     }
@@ -665,7 +687,7 @@ async fn ord(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Conta
 
 async fn sleep(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("sleep requires 1 arguemnt"));
+        return Err(make_err(ErrorKind::Argument, "sleep requires 1 arguemnt"));
     }
     let n = to_number_base(state.clone(), args[0].clone()).await?;
     std::thread::sleep(std::time::Duration::from_millis(n as u64));
@@ -674,7 +696,7 @@ async fn sleep(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Con
 
 async fn id(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("id requires 1 arguemnt"));
+        return Err(make_err(ErrorKind::Argument, "id requires 1 arguemnt"));
     }
     let v = args[0].clone();
     let v = &*v as *const _ as usize;
@@ -683,7 +705,7 @@ async fn id(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container,
 
 async fn bin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("bin requires 1 arguemnt"));
+        return Err(make_err(ErrorKind::Argument, "bin requires 1 arguemnt"));
     }
     let n = to_number_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(format!("{:b}", n))))
@@ -691,7 +713,7 @@ async fn bin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Conta
 
 async fn oct(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("oct requires 1 arguemnt"));
+        return Err(make_err(ErrorKind::Argument, "oct requires 1 arguemnt"));
     }
     let n = to_number_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(format!("{:o}", n))))
@@ -699,7 +721,7 @@ async fn oct(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Conta
 
 async fn hex(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("hex requires 1 arguemnt"));
+        return Err(make_err(ErrorKind::Argument, "hex requires 1 arguemnt"));
     }
     let n = to_number_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(format!("{:x}", n))))
@@ -707,7 +729,7 @@ async fn hex(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Conta
 
 async fn hex_upper(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("hex_upper requires 1 arguemnt"));
+        return Err(make_err(ErrorKind::Argument, "hex_upper requires 1 arguemnt"));
     }
     let n = to_number_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(format!("{:X}", n))))
@@ -715,55 +737,55 @@ async fn hex_upper(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 async fn number(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("number requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "number requires 1 argument"));
     }
     to_number(state, args[0].clone()).await
 }
 
 async fn boolean(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("boolean requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "boolean requires 1 argument"));
     }
     to_boolean(state, args[0].clone()).await
 }
 
 async fn float(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("float requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "float requires 1 argument"));
     }
     to_float(state, args[0].clone()).await
 }
 
 async fn from_bin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("from_bin requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "from_bin requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
-    let n = i64::from_str_radix(&s, 2).map_err(|_| make_err("from_bin couldn't parse the binary number"))?;
+    let n = i64::from_str_radix(&s, 2).map_err(|_| make_err(ErrorKind::Parse, "from_bin couldn't parse the binary number"))?;
     Ok(make_container(Value::Number(n)))
 }
 
 async fn from_oct(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("from_oct requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "from_oct requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
-    let n = i64::from_str_radix(&s, 8).map_err(|_| make_err("from_oct couldn't parse the octal number"))?;
+    let n = i64::from_str_radix(&s, 8).map_err(|_| make_err(ErrorKind::Parse, "from_oct couldn't parse the octal number"))?;
     Ok(make_container(Value::Number(n)))
 }
 
 async fn from_hex(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("from_hex requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "from_hex requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
-    let n = i64::from_str_radix(&s, 16).map_err(|_| make_err("from_hex couldn't parse the hexadecimal number"))?;
+    let n = i64::from_str_radix(&s, 16).map_err(|_| make_err(ErrorKind::Parse, "from_hex couldn't parse the hexadecimal number"))?;
     Ok(make_container(Value::Number(n)))
 }
 
 async fn type_(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("type requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "type requires 1 argument"));
     }
     let v = args[0].clone().lock().await.clone();
     Ok(make_container(Value::String(match v {
@@ -775,7 +797,7 @@ async fn type_(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Contain
         Value::Object(_) => "object".to_string(),
         Value::Tuple(_) => "tuple".to_string(),
         Value::Function(_) => "function".to_string(),
-        Value::Bind(_) => return Err(make_err("type failed to get type of value")),
+        Value::Bind(_) => return Err(make_err(ErrorKind::Type, "type failed to get type of value")),
     })))
 }
 
@@ -788,7 +810,7 @@ enum Debug {
 
 async fn load(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("load requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "load requires 1 argument"));
     }
     let code = to_string_base(state.clone(), args[0].clone()).await?;
     let global = state.as_ref().lock().await.global.clone().unwrap().clone();
@@ -804,10 +826,8 @@ async fn load(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Cont
             }
         },
     };
-    let code = bodu_script::s1::s1(code).map_err(|s| make_err(&format!("parsing error inside load (S1): {}", s)))?;
-    let code = bodu_script::s2::s2(code).map_err(|s| make_err(&format!("parsing error inside load (S2): {}", s)))?;
-    let code = bodu_script::s3::s3(code).map_err(|s| make_err(&format!("parsing error inside load (S3): {}", s)))?;
-    let code = bodu_script::s4::s4(code).map_err(|s| make_err(&format!("parsing error inside load (S4): {}", s)))?;
+    let source = Source::new("<load>".to_string(), code);
+    let (code, dbg) = bodu_script::parse(source.clone()).map_err(|e| make_err(ErrorKind::Parse, &format!("parsing error inside load: {}", e.report(&source))))?;
     let s = match debug {
         Debug::Inherit => None,
         Debug::Release => {
@@ -825,26 +845,24 @@ async fn load(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Cont
             Some(s)
         },
     };
-    let f = make_function(global.clone(), code, s).await?;
+    let f = make_function(global.clone(), code, s, Some(Arc::new(dbg))).await?;
     Ok(f)
 }
 
 async fn load_here(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("load_here requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "load_here requires 1 argument"));
     }
     let code = to_string_base(state.clone(), args[0].clone()).await?;
-    let code = bodu_script::s1::s1(code).map_err(|s| make_err(&format!("parsing error inside load_here (S1): {}", s)))?;
-    let code = bodu_script::s2::s2(code).map_err(|s| make_err(&format!("parsing error inside load_here (S2): {}", s)))?;
-    let code = bodu_script::s3::s3(code).map_err(|s| make_err(&format!("parsing error inside load_here (S3): {}", s)))?;
-    let code = bodu_script::s4::s4(code).map_err(|s| make_err(&format!("parsing error inside load_here (S4): {}", s)))?;
-    let f = make_function(state.clone(), code, Some(state.clone())).await?;
+    let source = Source::new("<load_here>".to_string(), code);
+    let (code, dbg) = bodu_script::parse(source.clone()).map_err(|e| make_err(ErrorKind::Parse, &format!("parsing error inside load_here: {}", e.report(&source))))?;
+    let f = make_function(state.clone(), code, Some(state.clone()), Some(Arc::new(dbg))).await?;
     Ok(f)
 }
 
 async fn exec(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("run requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "run requires 1 argument"));
     }
     let load_ = make_fn!(state, load);
     let f = call(state.clone(), load_, args.clone()).await?;
@@ -853,7 +871,7 @@ async fn exec(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Cont
 
 async fn push_gdefer(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("push_gdefer requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "push_gdefer requires 1 argument"));
     }
     {
         let threads = &mut *state.lock().await;
@@ -863,9 +881,43 @@ async fn push_gdefer(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
     Ok(make_container(Value::Null))
 }
 
+async fn exit(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    let code = match args.get(0) {
+        Some(v) => exit_code(state.clone(), v.clone(), "exit").await?,
+        None => 0,
+    };
+    {
+        let threads = &mut *state.lock().await;
+        let threads = &mut *threads.globaldata.as_mut().unwrap().lock().await;
+        threads.exitcode = code;
+    }
+    Err(make_exit())
+}
+
+async fn set_exit_code(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
+    if args.len() == 0 {
+        return Err(make_err(ErrorKind::Argument, "set_exit_code requires 1 argument"));
+    }
+    let code = exit_code(state.clone(), args[0].clone(), "set_exit_code").await?;
+    {
+        let threads = &mut *state.lock().await;
+        let threads = &mut *threads.globaldata.as_mut().unwrap().lock().await;
+        threads.exitcode = code;
+    }
+    Ok(make_container(Value::Null))
+}
+
+async fn exit_code(state: StateContainer, v: Container, name: &str) -> Result<u8, Container> {
+    let n = to_number_base(state.clone(), v).await?;
+    match u8::try_from(n) {
+        Ok(n) => Ok(n),
+        Err(_) => Err(make_err(ErrorKind::Value, &format!("{} requires an exit code between 0 and 255", name))),
+    }
+}
+
 async fn load_lib_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("load_lib requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "load_lib requires 1 argument"));
     }
     let a = to_string_base(state.clone(), args[0].clone()).await?;
     let f = load_lib(state.clone(), a).await?;
@@ -898,7 +950,7 @@ enum ImportMode {
 
 async fn import(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("import requires 1 or 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "import requires 1 or 2 arguments"));
     }
     let p = to_string_base(state.clone(), args[0].clone()).await?;
     let opts = if args.len() > 1 {
@@ -958,7 +1010,7 @@ async fn import(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
             },
         }
         let parentp = match rp.parent() {
-            None => return Err(make_err("import received an invalid path")),
+            None => return Err(make_err(ErrorKind::Import, "import received an invalid path")),
             Some(p) => p.to_path_buf(),
         };
         s.curdir = parentp.clone();
@@ -981,12 +1033,10 @@ async fn import(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
     }
     let f = match optsv.1 {
         ImportMode::Bodu => {
-            let code = std::fs::read_to_string(rp.clone()).map_err(|_| make_err("import couldn't read file contents"))?;
-            let code = bodu_script::s1::s1(code).map_err(|s| make_err(&format!("parsing error inside import (S1): {}", s)))?;
-            let code = bodu_script::s2::s2(code).map_err(|s| make_err(&format!("parsing error inside import (S2): {}", s)))?;
-            let code = bodu_script::s3::s3(code).map_err(|s| make_err(&format!("parsing error inside import (S3): {}", s)))?;
-            let code = bodu_script::s4::s4(code).map_err(|s| make_err(&format!("parsing error inside import (S4): {}", s)))?;
-            make_function(s.clone(), code, Some(s.clone())).await?
+            let code = std::fs::read_to_string(rp.clone()).map_err(|_| make_err(ErrorKind::Io, "import couldn't read file contents"))?;
+            let source = Source::new(rp.to_string_lossy().to_string(), code);
+            let (code, dbg) = bodu_script::parse(source.clone()).map_err(|e| make_err(ErrorKind::Parse, &format!("parsing error inside import: {}", e.report(&source))))?;
+            make_function(s.clone(), code, Some(s.clone()), Some(Arc::new(dbg))).await?
         },
         ImportMode::Native => load_lib(s.clone(), rp.to_string_lossy().to_string()).await?,
     };
@@ -1,8 +1,8 @@
-use crate::vm::{make_container, make_err, op::to_float_base, Container, Gi, StateContainer, Value};
+use crate::vm::{make_container, make_err, op::to_float_base, Container, ErrorKind, Gi, StateContainer, Value};
 
 pub async fn abs(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.abs requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.abs requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.abs())))
@@ -10,7 +10,7 @@ pub async fn abs(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn acos(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.acos requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.acos requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.acos())))
@@ -18,7 +18,7 @@ pub async fn acos(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn acosh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.acosh requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.acosh requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.acosh())))
@@ -26,7 +26,7 @@ pub async fn acosh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn asin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.asin requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.asin requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.asin())))
@@ -34,7 +34,7 @@ pub async fn asin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn asinh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.asinh requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.asinh requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.asinh())))
@@ -42,7 +42,7 @@ pub async fn asinh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn atan(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.atan requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.atan requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.atan())))
@@ -50,7 +50,7 @@ pub async fn atan(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn atan2(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("math.atan2 requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "math.atan2 requires 2 arguments"));
     }
     let a = to_float_base(state.clone(), args[0].clone()).await?;
     let b = to_float_base(state.clone(), args[1].clone()).await?;
@@ -59,7 +59,7 @@ pub async fn atan2(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn atanh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.atanh requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.atanh requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.atanh())))
@@ -67,7 +67,7 @@ pub async fn atanh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn cbrt(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.cbrt requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.cbrt requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.cbrt())))
@@ -75,7 +75,7 @@ pub async fn cbrt(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn ceil(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.ceil requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.ceil requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.ceil())))
@@ -83,7 +83,7 @@ pub async fn ceil(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn copysign(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("math.copysign requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "math.copysign requires 2 arguments"));
     }
     let a = to_float_base(state.clone(), args[0].clone()).await?;
     let b = to_float_base(state.clone(), args[1].clone()).await?;
@@ -92,7 +92,7 @@ pub async fn copysign(state: StateContainer, args: Vec<Container>, _: Gi) -> Res
 
 pub async fn cos(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.cos requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.cos requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.cos())))
@@ -100,7 +100,7 @@ pub async fn cos(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn cosh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.cosh requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.cosh requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.cosh())))
@@ -108,7 +108,7 @@ pub async fn cosh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn exp(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.exp requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.exp requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.exp())))
@@ -116,7 +116,7 @@ pub async fn exp(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn exp2(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.exp2 requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.exp2 requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.exp2())))
@@ -124,7 +124,7 @@ pub async fn exp2(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn exp_m1(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.exp_m1 requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.exp_m1 requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.exp_m1())))
@@ -132,7 +132,7 @@ pub async fn exp_m1(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
 
 pub async fn floor(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.floor requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.floor requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.floor())))
@@ -140,7 +140,7 @@ pub async fn floor(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn fract(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.fract requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.fract requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.fract())))
@@ -148,7 +148,7 @@ pub async fn fract(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn hypot(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("math.hypot requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "math.hypot requires 2 arguments"));
     }
     let a = to_float_base(state.clone(), args[0].clone()).await?;
     let b = to_float_base(state.clone(), args[1].clone()).await?;
@@ -157,7 +157,7 @@ pub async fn hypot(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn is_finite(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_finite requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_finite requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_finite())))
@@ -165,7 +165,7 @@ pub async fn is_finite(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn is_infinite(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_infinite requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_infinite requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_infinite())))
@@ -173,7 +173,7 @@ pub async fn is_infinite(state: StateContainer, args: Vec<Container>, _: Gi) ->
 
 pub async fn is_nan(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_nan requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_nan requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_nan())))
@@ -181,7 +181,7 @@ pub async fn is_nan(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
 
 pub async fn is_normal(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_normal requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_normal requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_normal())))
@@ -189,7 +189,7 @@ pub async fn is_normal(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn is_sign_negative(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_sign_negative requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_sign_negative requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_sign_negative())))
@@ -197,7 +197,7 @@ pub async fn is_sign_negative(state: StateContainer, args: Vec<Container>, _: Gi
 
 pub async fn is_sign_positive(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_sign_positive requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_sign_positive requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_sign_positive())))
@@ -205,7 +205,7 @@ pub async fn is_sign_positive(state: StateContainer, args: Vec<Container>, _: Gi
 
 pub async fn is_subnormal(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.is_subnormal requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.is_subnormal requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(f.is_subnormal())))
@@ -213,7 +213,7 @@ pub async fn is_subnormal(state: StateContainer, args: Vec<Container>, _: Gi) ->
 
 pub async fn ln(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.ln requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.ln requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.ln())))
@@ -221,7 +221,7 @@ pub async fn ln(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Co
 
 pub async fn ln_1p(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.ln_1p requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.ln_1p requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.ln_1p())))
@@ -229,7 +229,7 @@ pub async fn ln_1p(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn log(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("math.log requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "math.log requires 2 arguments"));
     }
     let a = to_float_base(state.clone(), args[0].clone()).await?;
     let b = to_float_base(state.clone(), args[1].clone()).await?;
@@ -238,7 +238,7 @@ pub async fn log(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn log2(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.log2 requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.log2 requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.log2())))
@@ -246,7 +246,7 @@ pub async fn log2(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn log10(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.log10 requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.log10 requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.log10())))
@@ -254,7 +254,7 @@ pub async fn log10(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn next_down(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.next_down requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.next_down requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.next_down())))
@@ -262,7 +262,7 @@ pub async fn next_down(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn next_up(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.next_up requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.next_up requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.next_up())))
@@ -270,7 +270,7 @@ pub async fn next_up(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
 
 pub async fn pow(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("math.pow requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "math.pow requires 2 arguments"));
     }
     let a = to_float_base(state.clone(), args[0].clone()).await?;
     let b = to_float_base(state.clone(), args[1].clone()).await?;
@@ -279,7 +279,7 @@ pub async fn pow(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn recip(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.recip requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.recip requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.recip())))
@@ -287,7 +287,7 @@ pub async fn recip(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn round(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.round requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.round requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.round())))
@@ -295,7 +295,7 @@ pub async fn round(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn round_ties_even(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.round_ties_even requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.round_ties_even requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.round_ties_even())))
@@ -303,7 +303,7 @@ pub async fn round_ties_even(state: StateContainer, args: Vec<Container>, _: Gi)
 
 pub async fn signum(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.signum requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.signum requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.signum())))
@@ -311,7 +311,7 @@ pub async fn signum(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
 
 pub async fn sin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.sin requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.sin requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.sin())))
@@ -319,7 +319,7 @@ pub async fn sin(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn sinh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.sinh requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.sinh requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.sinh())))
@@ -327,7 +327,7 @@ pub async fn sinh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn sqrt(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.sqrt requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.sqrt requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.sqrt())))
@@ -335,7 +335,7 @@ pub async fn sqrt(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn tan(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.tan requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.tan requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.tan())))
@@ -343,7 +343,7 @@ pub async fn tan(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn tanh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.sqrt requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.sqrt requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.tanh())))
@@ -351,7 +351,7 @@ pub async fn tanh(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn to_degrees(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.to_degrees requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.to_degrees requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.to_degrees())))
@@ -359,7 +359,7 @@ pub async fn to_degrees(state: StateContainer, args: Vec<Container>, _: Gi) -> R
 
 pub async fn to_radians(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.to_radians requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.to_radians requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.to_radians())))
@@ -367,7 +367,7 @@ pub async fn to_radians(state: StateContainer, args: Vec<Container>, _: Gi) -> R
 
 pub async fn trunc(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("math.trunc requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "math.trunc requires 1 argument"));
     }
     let f = to_float_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Float(f.trunc())))
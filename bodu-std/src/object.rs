@@ -1,6 +1,6 @@
 use std::{collections::HashMap, sync::Arc};
 
-use bodu_vm::{make_container, make_err, op::{make_object_base, make_tuple}, Container, Function, Gi, StateContainer, Value};
+use bodu_vm::{make_container, make_err, op::{make_object_base, make_tuple}, Container, ErrorKind, Function, Gi, StateContainer, Value};
 use tokio::sync::Mutex;
 
 pub async fn new(_: StateContainer, _: Vec<Container>, _: Gi) -> Result<Container, Container> {
@@ -11,14 +11,14 @@ pub async fn new(_: StateContainer, _: Vec<Container>, _: Gi) -> Result<Containe
 
 pub async fn keys(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("object.keys requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "object.keys requires 1 argument"));
     }
     let keys = {
         let o = args[0].clone();
         let o = &mut *o.lock().await;
         let o = match o {
             Value::Object(o) => o,
-            _ => return Err(make_err("object.keys requires 1 object"))
+            _ => return Err(make_err(ErrorKind::Type, "object.keys requires 1 object"))
         };
         o.props.keys().map(|s| s.clone()).collect::<Vec<_>>()
     };
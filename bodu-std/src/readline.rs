@@ -1,6 +1,6 @@
 use std::{collections::HashMap, sync::Arc};
 
-use bodu_vm::{make_container, make_err, op::{make_object_base, set_base, to_string_base}, Container, Function, Gi, StateContainer, Value};
+use bodu_vm::{make_container, make_err, op::{make_object_base, set_base, to_string_base}, Container, ErrorKind, Function, Gi, StateContainer, Value};
 use rustyline::DefaultEditor;
 use tokio::sync::Mutex;
 
@@ -22,7 +22,7 @@ macro_rules! helper1 {
 }
 
 pub async fn new(state: StateContainer, _: Vec<Container>, _: Gi) -> Result<Container, Container> {
-    let rl = DefaultEditor::new().map_err(|_| make_err("readile.new was unable to create a new readline"))?;
+    let rl = DefaultEditor::new().map_err(|_| make_err(ErrorKind::Io, "readile.new was unable to create a new readline"))?;
     let mut o = make_object_base();
     o.internals.insert(u64::MAX, make_container(Value::String("array".to_string())));
     o.externals.insert(0, Arc::new(Mutex::new(Box::new(rl))));
@@ -50,11 +50,11 @@ async fn readline(state: StateContainer, args: Vec<Container>, gi: Gi) -> Result
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("readline.readline requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "readline.readline requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     match o.readline(&s) {
-        Err(_) => Err(make_err("readline.readline was unable to read a line")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.readline was unable to read a line")),
         Ok(s) => Ok(make_container(Value::String(s))),
     }
 }
@@ -69,13 +69,13 @@ async fn readline_with_initial(state: StateContainer, args: Vec<Container>, gi:
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     if args.len() < 3 {
-        return Err(make_err("readline.readline_with_initial requires 3 arguments"));
+        return Err(make_err(ErrorKind::Argument, "readline.readline_with_initial requires 3 arguments"));
     }
     let s1 = to_string_base(state.clone(), args[0].clone()).await?;
     let s2 = to_string_base(state.clone(), args[1].clone()).await?;
     let s3 = to_string_base(state.clone(), args[2].clone()).await?;
     match o.readline_with_initial(&s1, (&s2, &s3)) {
-        Err(_) => Err(make_err("readline.readline_with_initial was unable to read a line")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.readline_with_initial was unable to read a line")),
         Ok(s) => Ok(make_container(Value::String(s))),
     }
 }
@@ -90,11 +90,11 @@ async fn load_history(state: StateContainer, args: Vec<Container>, gi: Gi) -> Re
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("readline.load_history requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "readline.load_history requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     match o.load_history(&s) {
-        Err(_) => Err(make_err("readline.load_history was unable to load history from file")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.load_history was unable to load history from file")),
         Ok(_) => Ok(make_container(Value::Null)),
     }
 }
@@ -109,11 +109,11 @@ async fn save_history(state: StateContainer, args: Vec<Container>, gi: Gi) -> Re
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("readline.save_history requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "readline.save_history requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     match o.save_history(&s) {
-        Err(_) => Err(make_err("readline.save_history was unable to save history to file")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.save_history was unable to save history to file")),
         Ok(_) => Ok(make_container(Value::Null)),
     }
 }
@@ -128,11 +128,11 @@ async fn append_history(state: StateContainer, args: Vec<Container>, gi: Gi) ->
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("readline.append_history requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "readline.append_history requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     match o.append_history(&s) {
-        Err(_) => Err(make_err("readline.append_history was unable to append history to file")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.append_history was unable to append history to file")),
         Ok(_) => Ok(make_container(Value::Null)),
     }
 }
@@ -147,11 +147,11 @@ async fn add_history_entry(state: StateContainer, args: Vec<Container>, gi: Gi)
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     if args.len() == 0 {
-        return Err(make_err("readline.add_history_entry requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "readline.add_history_entry requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     match o.add_history_entry(&s) {
-        Err(_) => Err(make_err("readline.add_history_entry was unable to add a history entry")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.add_history_entry was unable to add a history entry")),
         Ok(s) => Ok(make_container(Value::Boolean(s))),
     }
 }
@@ -166,7 +166,7 @@ async fn clear_history(_: StateContainer, _: Vec<Container>, gi: Gi) -> Result<C
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     match o.clear_history() {
-        Err(_) => Err(make_err("readline.clear_history was unable to clear history")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.clear_history was unable to clear history")),
         Ok(_) => Ok(make_container(Value::Null)),
     }
 }
@@ -181,7 +181,7 @@ async fn clear_screen(_: StateContainer, _: Vec<Container>, gi: Gi) -> Result<Co
     let mut o = o.lock().await;
     let o = o.downcast_mut::<DefaultEditor>().unwrap();
     match o.clear_screen() {
-        Err(_) => Err(make_err("readline.clear_screen was unable to clear the screen")),
+        Err(_) => Err(make_err(ErrorKind::Io, "readline.clear_screen was unable to clear the screen")),
         Ok(_) => Ok(make_container(Value::Null)),
     }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use bodu_vm::{make_container, make_err, op::{make_object, set_base, to_number_base, to_string_base}, Container, Gi, StateContainer, Value};
+use bodu_vm::{make_container, make_err, op::{make_object, set_base, to_number_base, to_string_base}, Container, ErrorKind, Gi, StateContainer, Value};
 use regex::{Captures, Match, Regex};
 
 use crate::array::new_with_vec;
@@ -7,18 +7,18 @@ async fn lookup_regex(state: StateContainer, s: String) -> Result<Regex, Contain
     let global = state.lock().await.globaldata.clone().unwrap();
     let regmap = &mut global.lock().await.regex;
     match regmap.get(&s) {
-        Some(s) => s.clone().map_err(|_| make_err("can't parse regex")),
+        Some(s) => s.clone().map_err(|_| make_err(ErrorKind::Parse, "can't parse regex")),
         None => {
             let r = Regex::new(&s).map_err(|_| ());
             regmap.insert(s.clone(), r.clone());
-            r.map_err(|_| make_err("can't parse regex"))
+            r.map_err(|_| make_err(ErrorKind::Parse, "can't parse regex"))
         },
     }
 }
 
 pub async fn is_match(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("regex.is_match requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.is_match requires 2 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -44,7 +44,7 @@ async fn make_match(state: StateContainer, match_: Match<'_>) -> Result<Containe
 
 pub async fn find(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("regex.find requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.find requires 2 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -58,7 +58,7 @@ pub async fn find(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn find_many(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("regex.find_many requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.find_many requires 2 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -85,7 +85,7 @@ async fn make_capture(state: StateContainer, cap: Captures<'_>) -> Result<Contai
 
 pub async fn captures(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("regex.captures requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.captures requires 2 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -98,7 +98,7 @@ pub async fn captures(state: StateContainer, args: Vec<Container>, _: Gi) -> Res
 
 pub async fn captures_many(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("regex.captures_many requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.captures_many requires 2 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -113,7 +113,7 @@ pub async fn captures_many(state: StateContainer, args: Vec<Container>, _: Gi) -
 
 pub async fn split(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("regex.split requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.split requires 2 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -125,7 +125,7 @@ pub async fn split(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn splitn(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 3 {
-        return Err(make_err("regex.splitn requires 3 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.splitn requires 3 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -138,7 +138,7 @@ pub async fn splitn(state: StateContainer, args: Vec<Container>, _: Gi) -> Resul
 
 pub async fn replace(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 3 {
-        return Err(make_err("regex.replace requires 3 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.replace requires 3 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -150,7 +150,7 @@ pub async fn replace(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
 
 pub async fn replace_all(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 3 {
-        return Err(make_err("regex.replace_all requires 3 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.replace_all requires 3 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -162,7 +162,7 @@ pub async fn replace_all(state: StateContainer, args: Vec<Container>, _: Gi) ->
 
 pub async fn replacen(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 4 {
-        return Err(make_err("regex.replacen requires 4 arguments"));
+        return Err(make_err(ErrorKind::Argument, "regex.replacen requires 4 arguments"));
     }
     let reg = to_string_base(state.clone(), args[0].clone()).await?;
     let reg = lookup_regex(state.clone(), reg).await?;
@@ -1,11 +1,11 @@
 use std::{collections::HashMap, sync::Arc};
 
-use bodu_vm::{make_container, make_err, op::{make_object_base, make_tuple, to_number_base, to_string_base}, Container, Function, Gi, StateContainer, Value};
+use bodu_vm::{make_container, make_err, op::{make_object_base, make_tuple, to_number_base, to_string_base}, Container, ErrorKind, Function, Gi, StateContainer, Value};
 use tokio::sync::Mutex;
 
 pub async fn len(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.len requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.len requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Number(s.len() as i64)))
@@ -13,7 +13,7 @@ pub async fn len(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn count_chars(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.count_chars requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.count_chars requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Number(s.chars().count() as i64)))
@@ -21,7 +21,7 @@ pub async fn count_chars(state: StateContainer, args: Vec<Container>, _: Gi) ->
 
 pub async fn chars(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.chars requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.chars requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let f = {
@@ -59,7 +59,7 @@ fn chars_next_wrapper(_: StateContainer, _: Vec<Container>, gi: Gi) -> std::pin:
 
 pub async fn ords(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.ords requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.ords requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let f = {
@@ -97,7 +97,7 @@ fn ords_next_wrapper(_: StateContainer, _: Vec<Container>, gi: Gi) -> std::pin::
 
 pub async fn trim(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.trim requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.trim requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(s.trim().to_string())))
@@ -105,7 +105,7 @@ pub async fn trim(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn reverse(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.trim requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.trim requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(s.chars().rev().collect())))
@@ -113,7 +113,7 @@ pub async fn reverse(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
 
 pub async fn uppercase(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.uppercase requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.uppercase requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(s.to_uppercase())))
@@ -121,7 +121,7 @@ pub async fn uppercase(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn lowercase(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.lowercase requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.lowercase requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::String(s.to_lowercase())))
@@ -129,7 +129,7 @@ pub async fn lowercase(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn capitalize(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.capitalize requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.capitalize requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let s = {
@@ -146,7 +146,7 @@ pub async fn capitalize(state: StateContainer, args: Vec<Container>, _: Gi) -> R
 
 pub async fn contains(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("string.contains requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "string.contains requires 2 arguments"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let pat = to_string_base(state.clone(), args[1].clone()).await?;
@@ -155,7 +155,7 @@ pub async fn contains(state: StateContainer, args: Vec<Container>, _: Gi) -> Res
 
 pub async fn ends_with(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("string.ends_with requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "string.ends_with requires 2 arguments"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let pat = to_string_base(state.clone(), args[1].clone()).await?;
@@ -164,7 +164,7 @@ pub async fn ends_with(state: StateContainer, args: Vec<Container>, _: Gi) -> Re
 
 pub async fn find(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("string.find requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "string.find requires 2 arguments"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let pat = to_string_base(state.clone(), args[1].clone()).await?;
@@ -177,7 +177,7 @@ pub async fn find(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn is_ascii(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.is_ascii requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.is_ascii requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(s.is_ascii())))
@@ -185,7 +185,7 @@ pub async fn is_ascii(state: StateContainer, args: Vec<Container>, _: Gi) -> Res
 
 pub async fn is_char_boundary(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("string.is_char_boundary requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "string.is_char_boundary requires 2 arguments"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let i = to_number_base(state.clone(), args[1].clone()).await?;
@@ -194,7 +194,7 @@ pub async fn is_char_boundary(state: StateContainer, args: Vec<Container>, _: Gi
 
 pub async fn is_empty(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.is_empty requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.is_empty requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     Ok(make_container(Value::Boolean(s.is_empty())))
@@ -202,7 +202,7 @@ pub async fn is_empty(state: StateContainer, args: Vec<Container>, _: Gi) -> Res
 
 pub async fn lines(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("string.lines requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "string.lines requires 1 argument"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let s = s.lines().map(|s| s.to_string()).collect::<Vec<String>>();
@@ -241,7 +241,7 @@ fn lines_next_wrapper(_: StateContainer, _: Vec<Container>, gi: Gi) -> std::pin:
 
 pub async fn starts_with(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("string.starts_with requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "string.starts_with requires 2 arguments"));
     }
     let s = to_string_base(state.clone(), args[0].clone()).await?;
     let pat = to_string_base(state.clone(), args[1].clone()).await?;
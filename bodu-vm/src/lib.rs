@@ -1,6 +1,7 @@
-use std::{any::Any, collections::HashMap, path::PathBuf, pin::Pin, sync::Arc};
+use std::{any::Any, collections::HashMap, path::PathBuf, pin::Pin, sync::{atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering}, Arc, OnceLock}, time::{Duration, Instant}};
 
 use libloading::Library;
+use serde::Serialize;
 use tokio::{sync::Mutex, task::JoinHandle};
 
 pub mod op; // standard operations that bodu code can do
@@ -83,13 +84,133 @@ pub struct GlobalData {
     pub register: HashMap<String, Container>,
     pub pkgs_release: HashMap<PathBuf, Container>,
     pub pkgs_debug: HashMap<PathBuf, Container>,
+    pub errtrace: Option<ErrorTrace>, // where the last error was thrown and the functions it went through
+    pub task: Arc<Task>, // the task of the main code, async functions run in tasks of their own
+    pub hook: Option<Arc<dyn VmHook>>, // a debugger, profiler, etc. watching the code run
+    pub limits: Option<Arc<Limits>>,
+}
+
+// limits on the code a global state runs, going over one throws a limit error. the steps and the time are for all the
+// code together, async functions included, and the depth is for every task on its own
+#[derive(Debug)]
+pub struct Limits {
+    pub max_steps: Option<u64>, // instructions
+    pub max_depth: Option<usize>, // bodu function calls inside each other
+    pub timeout: Option<Duration>,
+    steps: AtomicU64,
+    deadline: Option<Instant>,
+    exceeded: OnceLock<String>, // the message of the step or time limit that was hit
+    grace: AtomicU64, // instructions the catch block that caught the error still gets to run
+    forgiven: AtomicBool, // the grace was given, it's only given once
+}
+
+// enough for a catch block to log the error or clean up, not to keep the program going
+const GRACE_STEPS: u64 = 1000;
+
+impl Limits {
+    pub fn new(max_steps: Option<u64>, max_depth: Option<usize>, timeout: Option<Duration>) -> Limits {
+        Limits {
+            max_steps,
+            max_depth,
+            timeout,
+            steps: AtomicU64::new(0),
+            deadline: timeout.map(|t| Instant::now() + t),
+            exceeded: OnceLock::new(),
+            grace: AtomicU64::new(0),
+            forgiven: AtomicBool::new(false),
+        }
+    }
+
+    // before every instruction. once the steps or the time run out they stay out, so catching the error doesn't let the
+    // code keep going: after the grace of the first catch the next instruction throws it again
+    pub fn step(&self) -> Result<(), Container> {
+        if let Some(message) = self.exceeded.get() {
+            if self.grace.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |g| g.checked_sub(1)).is_ok() {
+                return Ok(());
+            }
+            return Err(make_err(ErrorKind::Limit, message));
+        }
+        if let Some(max) = self.max_steps && self.steps.fetch_add(1, Ordering::Relaxed) >= max {
+            return Err(self.exceed(format!("the code ran more than {} instructions", max)));
+        }
+        if let Some(deadline) = self.deadline && Instant::now() >= deadline {
+            return Err(self.exceed(format!("the code ran for more than {:?}", self.timeout.unwrap())));
+        }
+        Ok(())
+    }
+
+    fn exceed(&self, message: String) -> Container {
+        make_err(ErrorKind::Limit, self.exceeded.get_or_init(|| message))
+    }
+
+    // a catch block caught an error. if the steps or the time ran out that's the limit error, and the first time the catch
+    // block gets GRACE_STEPS instructions to run
+    pub fn caught(&self) {
+        if self.exceeded.get().is_some() && !self.forgiven.swap(true, Ordering::Relaxed) {
+            self.grace.store(GRACE_STEPS, Ordering::Relaxed);
+        }
+    }
+
+    // a bodu function is called by a task that's depth calls deep. the calls unwind when it throws, so this one can be caught
+    pub fn enter(&self, depth: usize) -> Result<(), Container> {
+        if let Some(max) = self.max_depth && depth >= max {
+            return Err(make_err(ErrorKind::Limit, &format!("more than {} function calls inside each other", max)));
+        }
+        Ok(())
+    }
+}
+
+// what a hook gets to see before an instruction runs
+pub struct Step<'a> {
+    pub state: StateContainer, // the state of the innermost block
+    pub args: &'a Vec<Container>,
+    pub tmps: &'a HashMap<u64, Container>,
+    pub dbg: &'a Option<Arc<DebugInfo>>,
+    pub pos: usize, // the index of the instruction in its block
+    pub instr: &'a Instruction,
+}
+
+impl Step<'_> {
+    // the source and the span of the instruction, if there's debug info for it
+    pub fn location(&self) -> Option<(Arc<Source>, Span)> {
+        self.dbg.as_ref().and_then(|dbg| dbg.span(self.pos).map(|span| (dbg.source.clone(), span)))
+    }
+}
+
+pub type HookFuture<'a> = Pin<Box<dyn Future<Output = Result<(), Container>> + Send + 'a>>;
+
+// watches the code run, set in GlobalData.hook. an error returned from step or enter is thrown in the bodu code
+pub trait VmHook: Send + Sync + std::fmt::Debug {
+    // before every instruction
+    fn step<'a>(&'a self, _step: Step<'a>) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    // a bodu function starts running, state is its state
+    fn enter<'a>(&'a self, _state: StateContainer, _dbg: Option<Arc<DebugInfo>>) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    // the function from the last enter returned or threw
+    fn leave<'a>(&'a self, _result: &'a Result<Container, Container>) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    // an error was just thrown by the instruction of step, caught is false if no catch block is running.
+    // what this returns is ignored, the error keeps going
+    fn throw<'a>(&'a self, _step: Step<'a>, _error: Container, _caught: bool) -> HookFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    // the program is done, called by whatever ran it right before exiting
+    fn finish(&self) {}
 }
 
 // Container but for States
 pub type StateContainer = Arc<Mutex<State>>;
 
 // instructions for the VM
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum Instruction {
     Add(VarIndex, VarIndex, VarIndex), // 1: result, op1, op2
     Return(VarIndex), // 2: op
@@ -141,31 +262,277 @@ pub enum Instruction {
     Probably(VarIndex), // 30: result
     Possibly(VarIndex), // 31: result
     IsntNull(VarIndex, VarIndex), // 32: result, op
+    Finally(Vec<Instruction>, Vec<Instruction>), // 33: block, finally block
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum VarIndex {
     Arg(usize), // 0
     Ident(String), // 1
     Temp(u64), // 2
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub enum Label {
     Named(String), // 0
     Unnamed(u64), // 1
 }
 
+// a range of source code. start and end are byte offsets, line and col (both starting at 1) are where it starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Span {
+    // the span from the start of self to the end of other
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            col: self.col,
+        }
+    }
+}
+
+// a source file, used to show where something happened
+#[derive(Debug)]
+pub struct Source {
+    pub name: String,
+    pub contents: String,
+}
+
+impl Source {
+    pub fn new(name: String, contents: String) -> Arc<Source> {
+        Arc::new(Source { name, contents })
+    }
+
+    // file:line:col
+    pub fn location(&self, span: Span) -> String {
+        format!("{}:{}:{}", self.name, span.line, span.col)
+    }
+
+    // the line of the span with carets under the spanned part. a span without a line or col (like Span::default()) points at the start
+    pub fn excerpt(&self, span: Span) -> String {
+        let line = self.contents.lines().nth(span.line.saturating_sub(1)).unwrap_or("");
+        let width = if span.end > span.start {
+            let len = self.contents.get(span.start..span.end).unwrap_or("").lines().next().unwrap_or("").chars().count();
+            len.min(line.chars().count().saturating_sub(span.col.saturating_sub(1))).max(1)
+        } else {
+            1
+        };
+        let number = span.line.to_string();
+        let pad = " ".repeat(number.len());
+        format!("{} |\n{} | {}\n{} | {}{}", pad, number, line, pad, " ".repeat(span.col.saturating_sub(1)), "^".repeat(width))
+    }
+
+    // file:line:col: message, followed by the excerpt
+    pub fn report(&self, span: Option<Span>, message: &str) -> String {
+        match span {
+            Some(span) => format!("{}: {}\n{}", self.location(span), message, self.excerpt(span)),
+            None => format!("{}: {}", self.name, message),
+        }
+    }
+}
+
+// debug info of a list of instructions: the span of each instruction and the debug info of the nested lists (Block, Catch, Defer, Finally and MakeFunction)
+// Finally has two lists, so its child has the debug info of the block as child 0 and of the finally block as child 1
+#[derive(Clone, Debug)]
+pub struct DebugInfo {
+    pub source: Arc<Source>,
+    pub name: Option<String>, // the name of the function these instructions are in, if known
+    pub spans: Vec<Option<Span>>,
+    pub children: HashMap<usize, Arc<DebugInfo>>,
+}
+
+impl DebugInfo {
+    pub fn new(source: Arc<Source>, name: Option<String>) -> DebugInfo {
+        DebugInfo {
+            source,
+            name,
+            spans: Vec::new(),
+            children: HashMap::new(),
+        }
+    }
+
+    pub fn span(&self, i: usize) -> Option<Span> {
+        self.spans.get(i).cloned().flatten()
+    }
+
+    pub fn child(&self, i: usize) -> Option<Arc<DebugInfo>> {
+        self.children.get(&i).cloned()
+    }
+}
+
+tokio::task_local! {
+    // the task the code is running in, when it isn't the main code
+    pub static TASK: Arc<Task>;
+}
+
+// code that runs one thing after the other: the main code or an async function. every task has its own calls and catch blocks
+#[derive(Debug, Default)]
+pub struct Task {
+    calls: std::sync::Mutex<Vec<Call>>,
+    catching: AtomicUsize, // how many catch blocks are running, so a hook knows if an error will be caught
+}
+
+// a bodu function being called and the instruction it's at
+#[derive(Debug)]
+struct Call {
+    name: Option<String>,
+    dbg: Option<Arc<DebugInfo>>, // the debug info of the block the instruction is in
+    pos: usize,
+}
+
+impl Task {
+    // a bodu function starts running, leave must follow
+    pub fn enter(&self, dbg: &Option<Arc<DebugInfo>>) {
+        self.calls.lock().unwrap().push(Call {
+            name: dbg.as_ref().and_then(|dbg| dbg.name.clone()),
+            dbg: dbg.clone(),
+            pos: 0,
+        });
+    }
+
+    pub fn leave(&self) {
+        self.calls.lock().unwrap().pop();
+    }
+
+    // how many functions are being called
+    pub fn depth(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+
+    // before every instruction of the innermost function
+    pub fn at(&self, dbg: &Option<Arc<DebugInfo>>, pos: usize) {
+        if let Some(call) = self.calls.lock().unwrap().last_mut() {
+            let same = match (&call.dbg, dbg) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            };
+            if !same {
+                call.dbg = dbg.clone();
+            }
+            call.pos = pos;
+        }
+    }
+
+    pub fn start_catch(&self) {
+        self.catching.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn end_catch(&self) {
+        self.catching.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn is_catching(&self) -> bool {
+        self.catching.load(Ordering::Relaxed) > 0
+    }
+
+    // the functions being called, the innermost first
+    pub fn frames(&self) -> Vec<Frame> {
+        self.calls.lock().unwrap().iter().rev().map(|call| Frame {
+            name: call.name.clone(),
+            location: call.dbg.as_ref().and_then(|dbg| dbg.span(call.pos).map(|span| (dbg.source.clone(), span))),
+        }).collect()
+    }
+}
+
+// a function in a stack trace
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub name: Option<String>, // None for functions without a name
+    pub location: Option<(Arc<Source>, Span)>, // where the error was in the function, None without debug info
+}
+
+impl Frame {
+    // name (file:line:col)
+    pub fn describe(&self) -> String {
+        let name = self.name.clone().unwrap_or("<anonymous>".to_string());
+        match &self.location {
+            Some((source, span)) => format!("{} ({})", name, source.location(*span)),
+            None => format!("{} (unknown location)", name),
+        }
+    }
+}
+
+// the stack trace of an error, taken from its task where it was thrown. the innermost frame comes first
+#[derive(Clone, Debug)]
+pub struct ErrorTrace {
+    pub error: Container,
+    pub frames: Vec<Frame>,
+}
+
 // type for object externals
 pub type SharedAny = Arc<Mutex<Box<dyn Any + Send + Sync>>>;
 
+// what went wrong. bodu code sees it as err.kind
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorKind {
+    Type, // "type": a value of the wrong type
+    Argument, // "argument": the wrong number of arguments
+    Value, // "value": a value of the right type that can't be used
+    Io, // "io": files, the terminal, etc.
+    Parse, // "parse": bodu code, json, numbers, regexes, etc.
+    Import, // "import": packages and native libraries
+    Internal, // "internal": internal data of a value was messed with
+    Limit, // "limit": the code went over a limit of the global state
+}
+
+impl ErrorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Type => "type",
+            ErrorKind::Argument => "argument",
+            ErrorKind::Value => "value",
+            ErrorKind::Io => "io",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Import => "import",
+            ErrorKind::Internal => "internal",
+            ErrorKind::Limit => "limit",
+        }
+    }
+}
+
+// the internals keys at the top are markers. u64::MAX is the type of the std objects (array, buffer, readline) and of
+// cbodu libraries, so the vm markers need keys of their own
+
+// the internals key of the value thrown by exit(), catch lets it through so the program can unwind
+pub const EXIT_TAG: u64 = u64::MAX - 1;
+
+// the internals key that marks an object as an error object
+pub const ERROR_TAG: u64 = u64::MAX - 2;
+
 // makes an error, could be a macro
-pub fn make_err(v: &str) -> Container {
-    make_container(Value::String(v.to_string()))
+pub fn make_err(kind: ErrorKind, v: &str) -> Container {
+    make_error(kind.name(), v, make_container(Value::Null))
+}
+
+// error objects have a kind, a message, a cause (null if there's none) and a stack (null until the error is caught)
+pub fn make_error(kind: &str, message: &str, cause: Container) -> Container {
+    let mut obj = op::make_object_base();
+    obj.internals.insert(ERROR_TAG, make_container(Value::Boolean(true)));
+    obj.props.insert("kind".to_string(), ObjectProp::Value(make_container(Value::String(kind.to_string()))));
+    obj.props.insert("message".to_string(), ObjectProp::Value(make_container(Value::String(message.to_string()))));
+    obj.props.insert("cause".to_string(), ObjectProp::Value(cause));
+    obj.props.insert("stack".to_string(), ObjectProp::Value(make_container(Value::Null)));
+    make_container(Value::Object(obj))
+}
+
+// the exit code is in GlobalData.exitcode, set_exit_code can still change it while the program unwinds
+pub fn make_exit() -> Container {
+    let mut obj = op::make_object_base();
+    obj.internals.insert(EXIT_TAG, make_container(Value::Boolean(true)));
+    make_container(Value::Object(obj))
 }
 
 // used for operator functions
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
 pub enum Operator {
     Plus, // 0: +
     Minus, // 1: -
@@ -188,4 +555,52 @@ pub enum Operator {
     Tuple, // 12: ,
     Pipe, // 13: |>
     IsntNull, // 14: ?
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{Source, Span};
+
+    fn source() -> std::sync::Arc<Source> {
+        Source::new("a.bodu".to_string(), "let x = 1;\nprint(x.y.z);\n".to_string())
+    }
+
+    #[test]
+    fn report_has_the_location_and_the_line() {
+        let span = Span {
+            start: 11,
+            end: 24,
+            line: 2,
+            col: 1,
+        };
+        assert_eq!(source().report(Some(span), "oops"), "a.bodu:2:1: oops\n  |\n2 | print(x.y.z);\n  | ^^^^^^^^^^^^^");
+        assert_eq!(source().report(None, "oops"), "a.bodu: oops");
+    }
+
+    #[test]
+    fn excerpt_carets_stop_at_the_end_of_the_line() {
+        let span = Span {
+            start: 8,
+            end: 24,
+            line: 1,
+            col: 9,
+        };
+        assert_eq!(source().excerpt(span), "  |\n1 | let x = 1;\n  |         ^^");
+    }
+
+    #[test]
+    fn excerpt_of_an_empty_span_is_one_caret() {
+        let span = Span {
+            start: 4,
+            end: 4,
+            line: 1,
+            col: 5,
+        };
+        assert_eq!(source().excerpt(span), "  |\n1 | let x = 1;\n  |     ^");
+    }
+
+    // a made up span with line and col 0 points at the start instead of panicking
+    #[test]
+    fn excerpt_of_a_default_span() {
+        assert_eq!(source().excerpt(Span::default()), "  |\n0 | let x = 1;\n  | ^");
+    }
+}
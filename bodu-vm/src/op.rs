@@ -3,7 +3,7 @@ use std::{collections::HashMap, pin::Pin, sync::Arc};
 use rand::Rng;
 use tokio::sync::Mutex;
 
-use crate::{make_container, make_err, opfn, Container, Function, Gi, Instruction, Label, Object, ObjectProp, Operator, State, StateContainer, Value, VarIndex};
+use crate::{make_container, make_err, opfn, Container, DebugInfo, ErrorKind, ErrorTrace, Frame, Function, Gi, Instruction, Label, Limits, Object, ObjectProp, Operator, State, StateContainer, Step, Task, Value, VarIndex, VmHook, ERROR_TAG, EXIT_TAG, TASK};
 
 pub async fn resolve_bind(state: StateContainer, v: Container) -> Result<Container, Container> {
     let b = v.lock().await.clone();
@@ -55,7 +55,7 @@ pub async fn add(state: StateContainer, a: Container, b: Container) -> Result<Co
         (_, Value::Object(obj)) => {
             call_metaprop(state.clone(), obj, vec![x, y], "add".to_string()).await
         },
-        _ => Err(make_err("can't add a with b")),
+        _ => Err(make_err(ErrorKind::Type, "can't add a with b")),
     }
 }
 
@@ -77,7 +77,7 @@ pub async fn call(state: StateContainer, f: Container, args: Vec<Container>) ->
         Value::Object(obj) => {
             call_metaprop(state.clone(), obj, vec![g].iter().chain(args.iter()).map(|h| h.clone()).collect::<Vec<Container>>(), "call".to_string()).await
         },
-        _ => Err(make_err("can't call f")),
+        _ => Err(make_err(ErrorKind::Type, "can't call f")),
     }
 }
 
@@ -90,6 +90,7 @@ pub async fn to_string_base(state: StateContainer, v: Container) -> Result<Strin
         Value::Float(v) => Ok(v.to_string()),
         Value::Null => Ok("null".to_string()),
         Value::Boolean(v) => Ok(v.to_string()),
+        Value::Object(obj) if is_error_object(&obj) => error_to_string(state.clone(), x).await,
         Value::Object(obj) => {
             let tmp = call_metaprop(state.clone(), obj, vec![x], "to_string".to_string()).await?;
             Box::pin(to_string_base(state.clone(), tmp)).await
@@ -101,10 +102,64 @@ pub async fn to_string_base(state: StateContainer, v: Container) -> Result<Strin
             }
             Ok("(".to_string()+&s.join(", ")+")")
         },
-        _ => Err(make_err("can't convert v to string")),
+        _ => Err(make_err(ErrorKind::Type, "can't convert v to string")),
     }
 }
 
+// kind error: message, and the cause on the next line
+async fn error_to_string(state: StateContainer, e: Container) -> Result<String, Container> {
+    let kind = Box::pin(to_string_base(state.clone(), get_base(state.clone(), e.clone(), "kind".to_string()).await?)).await?;
+    let message = Box::pin(to_string_base(state.clone(), get_base(state.clone(), e.clone(), "message".to_string()).await?)).await?;
+    let cause = get_base(state.clone(), e.clone(), "cause".to_string()).await?;
+    let mut s = format!("{} error: {}", kind, message);
+    if let Value::Null = cause.lock().await.clone() {
+        return Ok(s)
+    }
+    s.push_str("\ncaused by: ");
+    s.push_str(&Box::pin(to_string_base(state.clone(), cause)).await?);
+    Ok(s)
+}
+
+pub async fn is_error(v: Container) -> bool {
+    match &*v.lock().await {
+        Value::Object(obj) => is_error_object(obj),
+        _ => false,
+    }
+}
+
+fn is_error_object(obj: &Object) -> bool {
+    obj.internals.contains_key(&ERROR_TAG)
+}
+
+pub async fn is_exit(v: Container) -> bool {
+    match &*v.lock().await {
+        Value::Object(obj) => obj.internals.contains_key(&EXIT_TAG),
+        _ => false,
+    }
+}
+
+// sets the stack of an error object that was just caught, if it doesn't have one yet
+async fn capture_stack(state: StateContainer, e: Container) -> Result<(), Container> {
+    if !is_error(e.clone()).await {
+        return Ok(())
+    }
+    if let Value::Null = get_base(state.clone(), e.clone(), "stack".to_string()).await?.lock().await.clone() {
+        let frames = {
+            let gd = state.lock().await.globaldata.clone();
+            match gd {
+                Some(gd) => match &gd.lock().await.errtrace {
+                    Some(trace) if Arc::ptr_eq(&trace.error, &e) => trace.frames.clone(),
+                    _ => return Ok(()),
+                },
+                None => return Ok(()),
+            }
+        };
+        let stack = frames.iter().map(|f| f.describe()).collect::<Vec<_>>().join("\n");
+        set_base(state.clone(), e, "stack".to_string(), make_container(Value::String(stack))).await?;
+    }
+    Ok(())
+}
+
 pub async fn to_string(state: StateContainer, v: Container) -> Result<Container, Container> {
     Ok(make_container(Value::String(to_string_base(state, v).await?)))
 }
@@ -118,6 +173,7 @@ pub async fn get_base(state: StateContainer, obj: Container, prop: String) -> Re
             match p {
                 None => match call_metaprop(state.clone(), obj, vec![o, make_container(Value::String(prop.clone()))], "get".to_string()).await {
                     Ok(v) => Ok(v),
+                    Err(e) if is_exit(e.clone()).await => Err(e),
                     Err(_) => Ok(make_container(Value::Null)),
                 },
                 Some(prop) => {
@@ -130,7 +186,7 @@ pub async fn get_base(state: StateContainer, obj: Container, prop: String) -> Re
                 },
             }
         },
-        _ => Err(make_err("can't get property prop on object obj")),
+        _ => Err(make_err(ErrorKind::Type, "can't get property prop on object obj")),
     }
 }
 
@@ -208,7 +264,7 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
         },
         (Value::Function(_), Value::Number(n)) => {
             if n < 1 {
-                return Err(make_err("can't multiply a with b"))
+                return Err(make_err(ErrorKind::Type, "can't multiply a with b"))
             }
             let g = {
                 let mut internals = HashMap::new();
@@ -219,7 +275,7 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
                     call: |state, args, gi| {
                         Box::pin(async move {
                             let arg = if args.len() == 0 {
-                                return Err(make_err("less than 1 argument"));
+                                return Err(make_err(ErrorKind::Argument, "less than 1 argument"));
                             } else {
                                 args[0].clone()
                             };
@@ -228,10 +284,10 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
                                 Value::Number(n) => {
                                     n
                                 },
-                                _ => return Err(make_err("data corrupted")),
+                                _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                             };
                             if n < 1 {
-                                return Err(make_err("data corrupted"))
+                                return Err(make_err(ErrorKind::Internal, "data corrupted"))
                             }
                             let mut r = make_container(Value::Null);
                             let mut i = n;
@@ -250,7 +306,7 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
         },
         (Value::Number(n), Value::Function(_)) => {
             if n < 1 {
-                return Err(make_err("can't multiply a with b"))
+                return Err(make_err(ErrorKind::Type, "can't multiply a with b"))
             }
             let g = {
                 let mut internals = HashMap::new();
@@ -261,7 +317,7 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
                     call: |state, args, gi| {
                         Box::pin(async move {
                             let arg = if args.len() == 0 {
-                                return Err(make_err("less than 1 argument"));
+                                return Err(make_err(ErrorKind::Argument, "less than 1 argument"));
                             } else {
                                 args[0].clone()
                             };
@@ -270,10 +326,10 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
                                 Value::Number(n) => {
                                     n
                                 },
-                                _ => return Err(make_err("data corrupted")),
+                                _ => return Err(make_err(ErrorKind::Internal, "data corrupted")),
                             };
                             if n < 1 {
-                                return Err(make_err("data corrupted"))
+                                return Err(make_err(ErrorKind::Internal, "data corrupted"))
                             }
                             let mut r = make_container(Value::Null);
                             let mut i = n;
@@ -293,7 +349,7 @@ pub async fn multiply(state: StateContainer, a: Container, b: Container) -> Resu
         (Value::Boolean(a), Value::Boolean(b)) => Ok(make_container(Value::Boolean(a|b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "multiply".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "multiply".to_string()).await,
-        _ => Err(make_err("can't multiply a with b")),
+        _ => Err(make_err(ErrorKind::Type, "can't multiply a with b")),
     }
 }
 
@@ -306,7 +362,7 @@ pub async fn negate(state: StateContainer, v: Container) -> Result<Container, Co
         Value::String(s) => Ok(make_container(Value::String(s.chars().rev().collect()))),
         Value::Boolean(b) => Ok(make_container(Value::Boolean(!b))),
         Value::Object(obj) => call_metaprop(state.clone(), obj, vec![x], "negate".to_string()).await,
-        _ => Err(make_err("can't negate v")),
+        _ => Err(make_err(ErrorKind::Type, "can't negate v")),
     }
 }
 
@@ -345,7 +401,7 @@ pub async fn subtract(state: StateContainer, a: Container, b: Container) -> Resu
         },
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "subtract".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "subtract".to_string()).await,
-        _ => Err(make_err("can't subtract a and b")),
+        _ => Err(make_err(ErrorKind::Type, "can't subtract a and b")),
     }
 }
 
@@ -358,7 +414,7 @@ pub async fn has_base(state: StateContainer, obj: Container, prop: String) -> Re
                 None => false,
             })
         },
-        _ => Err(make_err("can't see if obj has property prop")),
+        _ => Err(make_err(ErrorKind::Type, "can't see if obj has property prop")),
     }
 }
 
@@ -378,7 +434,7 @@ pub async fn set_base(state: StateContainer, obj: Container, prop: String, v: Co
             },
         }
     } else {
-        Err(make_err("can't set property of non-object"))
+        Err(make_err(ErrorKind::Type, "can't set property of non-object"))
     };
     let a = a?;
     if let Some(setter) = a {
@@ -457,7 +513,7 @@ pub async fn divide(state: StateContainer, a: Container, b: Container) -> Result
     match (a, b) {
         (Value::Number(a), Value::Number(b)) => {
             if b == 0 {
-                Err(make_err("cannot divide by 0"))
+                Err(make_err(ErrorKind::Value, "cannot divide by 0"))
             } else {
                 Ok(make_container(Value::Number(a/b)))
             }
@@ -489,7 +545,7 @@ pub async fn divide(state: StateContainer, a: Container, b: Container) -> Result
         },
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "divide".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "divide".to_string()).await,
-        _ => Err(make_err("cannot divide a by b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot divide a by b")),
     }
 }
 
@@ -505,7 +561,7 @@ pub async fn remainder(state: StateContainer, a: Container, b: Container) -> Res
         (Value::Float(a), Value::Float(b)) => Ok(make_container(Value::Float(a%b))),
         (Value::Number(n), Value::Function(_)) => {
             if n < 1 {
-                return Err(make_container(Value::String("cannot take the remainder of a by b".to_string())))
+                return Err(make_err(ErrorKind::Type, "cannot take the remainder of a by b"))
             }
             let g = {
                 let mut internals = HashMap::new();
@@ -518,7 +574,7 @@ pub async fn remainder(state: StateContainer, a: Container, b: Container) -> Res
                             let f = gi(0).unwrap();
                             let n = match gi(1).unwrap().lock().await.clone() {
                                 Value::Number(n) => n,
-                                _ => return Err(make_container(Value::String("data corrupted".to_string())))
+                                _ => return Err(make_err(ErrorKind::Internal, "data corrupted"))
                             };
                             for _ in 0..n {
                                 call(state.clone(), f.clone(), args.clone()).await?;
@@ -534,7 +590,7 @@ pub async fn remainder(state: StateContainer, a: Container, b: Container) -> Res
         },
         (Value::Function(_), Value::Number(n)) => {
             if n < 1 {
-                return Err(make_container(Value::String("cannot take the remainder of a by b".to_string())))
+                return Err(make_err(ErrorKind::Type, "cannot take the remainder of a by b"))
             }
             let g = {
                 let mut internals = HashMap::new();
@@ -547,7 +603,7 @@ pub async fn remainder(state: StateContainer, a: Container, b: Container) -> Res
                             let f = gi(0).unwrap();
                             let n = match gi(1).unwrap().lock().await.clone() {
                                 Value::Number(n) => n,
-                                _ => return Err(make_container(Value::String("data corrupted".to_string())))
+                                _ => return Err(make_err(ErrorKind::Internal, "data corrupted"))
                             };
                             for _ in 0..n {
                                 call(state.clone(), f.clone(), args.clone()).await?;
@@ -563,7 +619,7 @@ pub async fn remainder(state: StateContainer, a: Container, b: Container) -> Res
         },
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "remainder".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "remainder".to_string()).await,
-        _ => Err(make_container(Value::String("cannot take the remainder of a by b".to_string()))),
+        _ => Err(make_err(ErrorKind::Type, "cannot take the remainder of a by b")),
     }
 }
 
@@ -575,7 +631,7 @@ pub async fn to_number_base(state: StateContainer, v: Container) -> Result<i64,
         Value::Null => Ok(0),
         Value::String(s) => s.parse().map_err(|_| make_container(Value::String("cannot convert v to number".to_string()))),
         Value::Boolean(b) => Ok(if b {1} else {0}),
-        _ => Err(make_container(Value::String("cannot convert v to number".to_string())))
+        _ => Err(make_err(ErrorKind::Type, "cannot convert v to number"))
     }
 }
 
@@ -591,7 +647,7 @@ pub async fn to_float_base(state: StateContainer, v: Container) -> Result<f64, C
         Value::Null => Ok(0.0),
         Value::String(s) => s.parse().map_err(|_| make_container(Value::String("cannot convert v to float".to_string()))),
         Value::Boolean(b) => Ok(if b {1.0} else {0.0}),
-        _ => Err(make_container(Value::String("cannot convert v to float".to_string()))),
+        _ => Err(make_err(ErrorKind::Type, "cannot convert v to float")),
     }
 }
 
@@ -626,7 +682,7 @@ pub async fn not(state: StateContainer, v: Container) -> Result<Container, Conta
         Value::Float(n) => Ok(make_container(Value::Number(!(n as i64)))),
         Value::Boolean(b) => Ok(make_container(Value::Boolean(!b))),
         Value::Object(obj) => call_metaprop(state.clone(), obj, vec![x], "not".to_string()).await,
-        _ => Err(make_err("can't not v")),
+        _ => Err(make_err(ErrorKind::Type, "can't not v")),
     }
 }
 
@@ -642,7 +698,7 @@ pub async fn gt(state: StateContainer, a: Container, b: Container) -> Result<Con
         (Value::Float(a), Value::Float(b)) => Ok(make_container(Value::Boolean(a > b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "gt".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "gt".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -658,7 +714,7 @@ pub async fn ge(state: StateContainer, a: Container, b: Container) -> Result<Con
         (Value::Float(a), Value::Float(b)) => Ok(make_container(Value::Boolean(a >= b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "ge".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "ge".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -674,7 +730,7 @@ pub async fn lt(state: StateContainer, a: Container, b: Container) -> Result<Con
         (Value::Float(a), Value::Float(b)) => Ok(make_container(Value::Boolean(a < b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "lt".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "lt".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -690,7 +746,7 @@ pub async fn le(state: StateContainer, a: Container, b: Container) -> Result<Con
         (Value::Float(a), Value::Float(b)) => Ok(make_container(Value::Boolean(a <= b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "le".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "le".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -707,7 +763,7 @@ pub async fn and(state: StateContainer, a: Container, b: Container) -> Result<Co
         (Value::Boolean(a), Value::Boolean(b)) => Ok(make_container(Value::Boolean(a & b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "and".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "and".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -724,7 +780,7 @@ pub async fn or(state: StateContainer, a: Container, b: Container) -> Result<Con
         (Value::Boolean(a), Value::Boolean(b)) => Ok(make_container(Value::Boolean(a | b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "or".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "or".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -741,7 +797,7 @@ pub async fn xor(state: StateContainer, a: Container, b: Container) -> Result<Co
         (Value::Boolean(a), Value::Boolean(b)) => Ok(make_container(Value::Boolean(a ^ b))),
         (Value::Object(obj), _) => call_metaprop(state.clone(), obj, vec![x, y], "xor".to_string()).await,
         (_, Value::Object(obj)) => call_metaprop(state.clone(), obj, vec![x, y], "xor".to_string()).await,
-        _ => Err(make_err("cannot compare a and b")),
+        _ => Err(make_err(ErrorKind::Type, "cannot compare a and b")),
     }
 }
 
@@ -763,28 +819,70 @@ pub async fn isnt_null(state: StateContainer, v: Container) -> Result<Container,
     })
 }
 
+// every bodu call polls the futures of the call inside it, so deep recursion goes deep in the rust stack too. when there's
+// little stack left the call is polled on a new piece of it, so how deep the code can go is up to the depth limit instead
+// of the stack size of the thread it runs on. the call is also left out of tokio's budget: when a task yields, all of its
+// calls are polled again from the outermost one, and the new pieces of stack are made again
+struct GrowStack<F>(Pin<Box<F>>);
+
+// the most stack a call can use before the next one checks, calls are much bigger without optimizations
+const RED_ZONE: usize = 1024 * 1024;
+const NEW_STACK: usize = 16 * 1024 * 1024;
+
+impl<F: std::future::Future> std::future::Future for GrowStack<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<F::Output> {
+        stacker::maybe_grow(RED_ZONE, NEW_STACK, || self.0.as_mut().poll(cx))
+    }
+}
+
 fn make_function_call(state: StateContainer, args: Vec<Container>, gi: Gi) -> Pin<Box<dyn std::future::Future<Output = Result<Container, Container>> + Send>> {
-    Box::pin(async move {
+    Box::pin(GrowStack(Box::pin(tokio::task::unconstrained(async move {
         let state = state.clone();
         let instrs = gi(0).unwrap();
         let instrs = match instrs.lock().await.clone() {
             Value::Object(obj) => Some(obj),
             _ => None,
         };
-        let instrs = instrs.unwrap().externals;
-        let instrs = instrs[&0].lock().await.downcast_ref::<Vec<Instruction>>().unwrap().clone();
+        let externals = instrs.unwrap().externals;
+        let instrs = externals[&0].lock().await.downcast_ref::<Vec<Instruction>>().unwrap().clone();
+        let dbg = match externals.get(&1) {
+            Some(dbg) => dbg.lock().await.downcast_ref::<Arc<DebugInfo>>().cloned(),
+            None => None,
+        };
         let mut tmps: HashMap<u64, Container> = HashMap::new();
-        let r = interpret_instructions(state.clone(), &args, &mut tmps, &instrs, None).await?;
-        match r {
-            (Some(r), _) => Ok(r),
-            (None, _) => Ok(make_container(Value::Null)),
+        let (hook, limits, task) = watchers(state.clone()).await;
+        if let Some(limits) = &limits && let Some(task) = &task {
+            limits.enter(task.depth())?;
         }
-    })
+        if let Some(hook) = &hook {
+            hook.enter(state.clone(), dbg.clone()).await?;
+        }
+        if let Some(task) = &task {
+            task.enter(&dbg);
+        }
+        let r = interpret_instructions(state.clone(), &args, &mut tmps, &instrs, None, dbg).await;
+        if let Some(task) = &task {
+            task.leave();
+        }
+        let r = r.map(|r| match r {
+            (Some(r), _) => r,
+            (None, _) => make_container(Value::Null),
+        });
+        if let Some(hook) = &hook {
+            hook.leave(&r).await?;
+        }
+        r
+    }))))
 }
 
-pub async fn make_function(state: StateContainer, instrs: Vec<Instruction>, s: Option<StateContainer>) -> Result<Arc<Mutex<Value>>, Arc<Mutex<Value>>> {
+pub async fn make_function(state: StateContainer, instrs: Vec<Instruction>, s: Option<StateContainer>, dbg: Option<Arc<DebugInfo>>) -> Result<Arc<Mutex<Value>>, Arc<Mutex<Value>>> {
     let mut obj = make_object_base();
     obj.externals.insert(0, Arc::new(Mutex::new(Box::new(instrs.clone()))));
+    if let Some(dbg) = dbg {
+        obj.externals.insert(1, Arc::new(Mutex::new(Box::new(dbg))));
+    }
     let mut internals = HashMap::new();
     internals.insert(0, make_container(Value::Object(obj)));
     let s = match s {
@@ -868,7 +966,7 @@ pub async fn get_var(state: StateContainer, args: &Vec<Container>, tmps: &HashMa
 
 pub async fn set_var(state: StateContainer, tmps: &mut HashMap<u64, Container>, vari: VarIndex, v: Container) -> Result<(), Container> {
     match vari {
-        VarIndex::Arg(_) => Err(make_container(Value::String("can't set argument".to_string()))),
+        VarIndex::Arg(_) => Err(make_err(ErrorKind::Type, "can't set argument")),
         VarIndex::Ident(ident) => set_to_state(ident.clone(), v.clone(), state.clone()).await,
         VarIndex::Temp(u) => {
             tmps.insert(u, v.clone());
@@ -883,7 +981,7 @@ pub async fn decl(state: StateContainer, vari: VarIndex) -> Result<(), Container
     };
     match vari {
         VarIndex::Ident(ident) => set_base(state.clone(), obj.clone(), ident.clone(), make_container(Value::Null)).await,
-        _ => Err(make_container(Value::String("can't declare non-identifier".to_string()))),
+        _ => Err(make_err(ErrorKind::Type, "can't declare non-identifier")),
     }
 }
 
@@ -915,7 +1013,135 @@ macro_rules! make_fn {
     }};
 }
 
-pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>, tmps: &mut HashMap<u64, Container>, instrs: &Vec<Instruction>, pipeshort: Option<Container>) -> Result<(Option<Container>, Option<Label>), Container> {
+// runs the defers of a list of instructions, last deferred first
+// the defers are taken, so they never run twice
+async fn run_defers(state: StateContainer, args: &Vec<Container>, tmps: &mut HashMap<u64, Container>, defers: &mut Vec<Instruction>, dbg: &mut Option<DebugInfo>) -> Result<(), Container> {
+    let defers = std::mem::take(defers);
+    let instrs = defers.iter().rev().cloned().collect();
+    let dbg = dbg.as_mut().map(|dbg| {
+        let mut r = DebugInfo::new(dbg.source.clone(), dbg.name.clone());
+        r.spans = std::mem::take(&mut dbg.spans).into_iter().rev().collect();
+        r.children = std::mem::take(&mut dbg.children).into_iter().map(|(i, c)| (defers.len() - 1 - i, c)).collect();
+        Arc::new(r)
+    });
+    Box::pin(interpret_instructions(state.clone(), args, tmps, &instrs, None, dbg)).await?;
+    Ok(())
+}
+
+// a new error gets the stack of its task as its trace, while the functions it was thrown in are still on it.
+// returns true for a new error
+async fn note_error(state: StateContainer, e: &Container, task: &Option<Arc<Task>>) -> bool {
+    let gd = state.lock().await.globaldata.clone();
+    if let Some(gd) = gd {
+        let gd = &mut *gd.lock().await;
+        if let Some(trace) = &gd.errtrace && Arc::ptr_eq(&trace.error, e) {
+            return false
+        }
+        gd.errtrace = Some(ErrorTrace {
+            error: e.clone(),
+            frames: task.as_ref().map(|task| task.frames()).unwrap_or_default(),
+        });
+    }
+    true
+}
+
+pub async fn hook(state: StateContainer) -> Option<Arc<dyn VmHook>> {
+    watchers(state).await.0
+}
+
+// the functions being called in the task the code runs in, the innermost first
+pub async fn stack_trace(state: StateContainer) -> Vec<Frame> {
+    match watchers(state).await.2 {
+        Some(task) => task.frames(),
+        None => Vec::new(),
+    }
+}
+
+// for native code that waits for bodu code it started, the bodu code runs in the same task as the native code
+pub fn spawn_here<F>(f: F) -> tokio::task::JoinHandle<F::Output> where F: std::future::Future + Send + 'static, F::Output: Send + 'static {
+    match TASK.try_with(|task| task.clone()) {
+        Ok(task) => tokio::spawn(TASK.scope(task, f)),
+        Err(_) => tokio::spawn(f),
+    }
+}
+
+// the hook, the limits and the task the code runs in, they're kept up to date as the code runs
+async fn watchers(state: StateContainer) -> (Option<Arc<dyn VmHook>>, Option<Arc<Limits>>, Option<Arc<Task>>) {
+    let task = TASK.try_with(|task| task.clone()).ok();
+    let gd = state.lock().await.globaldata.clone();
+    match gd {
+        Some(gd) => {
+            let gd = gd.lock().await;
+            (gd.hook.clone(), gd.limits.clone(), task.or_else(|| Some(gd.task.clone())))
+        },
+        None => (None, None, task),
+    }
+}
+
+async fn take_trace(state: StateContainer) -> Option<ErrorTrace> {
+    let gd = state.lock().await.globaldata.clone();
+    match gd {
+        Some(gd) => gd.lock().await.errtrace.take(),
+        None => None,
+    }
+}
+
+async fn put_trace(state: StateContainer, trace: ErrorTrace) {
+    let gd = state.lock().await.globaldata.clone();
+    if let Some(gd) = gd {
+        gd.lock().await.errtrace = Some(trace);
+    }
+}
+
+pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>, tmps: &mut HashMap<u64, Container>, instrs: &Vec<Instruction>, pipeshort: Option<Container>, dbg: Option<Arc<DebugInfo>>) -> Result<(Option<Container>, Option<Label>), Container> {
+    let (hook, limits, task) = watchers(state.clone()).await;
+    let mut run = Run {
+        defers_dbg: dbg.as_ref().map(|dbg| DebugInfo::new(dbg.source.clone(), dbg.name.clone())),
+        dbg,
+        hook,
+        limits,
+        task,
+        pos: 0,
+        defers: Vec::new(),
+    };
+    let r = run_instructions(state.clone(), args, tmps, instrs, pipeshort, &mut run).await;
+    let Run { dbg, hook, task, pos, mut defers, mut defers_dbg, .. } = run;
+    if let Err(e) = &r {
+        let new = note_error(state.clone(), e, &task).await;
+        if new && let Some(hook) = &hook && let Some(instr) = instrs.get(pos) && !is_exit(e.clone()).await {
+            let caught = task.as_ref().is_some_and(|task| task.is_catching());
+            let step = Step {
+                state: state.clone(),
+                args,
+                tmps,
+                dbg: &dbg,
+                pos,
+                instr,
+            };
+            let _ = hook.throw(step, e.clone(), caught).await;
+        }
+        // exit() runs the pending defers on its way out
+        if !defers.is_empty() && is_exit(e.clone()).await {
+            run_defers(state.clone(), args, tmps, &mut defers, &mut defers_dbg).await?;
+        }
+    }
+    r
+}
+
+// what a list of instructions runs with besides its variables, interpret_instructions still needs it after an error
+struct Run {
+    dbg: Option<Arc<DebugInfo>>,
+    hook: Option<Arc<dyn VmHook>>,
+    limits: Option<Arc<Limits>>,
+    task: Option<Arc<Task>>,
+    pos: usize, // the index of the instruction being run
+    defers: Vec<Instruction>,
+    defers_dbg: Option<DebugInfo>,
+}
+
+async fn run_instructions(state: StateContainer, args: &Vec<Container>, tmps: &mut HashMap<u64, Container>, instrs: &Vec<Instruction>, pipeshort: Option<Container>, run: &mut Run) -> Result<(Option<Container>, Option<Label>), Container> {
+    let Run { dbg, hook, limits, task, pos, defers, defers_dbg } = run;
+    let (dbg, hook, limits, task) = (&*dbg, &*hook, &*limits, &*task);
     let mut ulabels: HashMap<u64, usize> = HashMap::new();
     let mut slabels: HashMap<String, usize> = HashMap::new();
     let mut i = 0;
@@ -931,23 +1157,39 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
         }
         i += 1;
     }
-    let mut defers: Vec<Instruction> = Vec::new();
     let mut pipeshort = match pipeshort {
         Some(v) => v.clone(),
         None => make_container(Value::Null),
     };
     i = 0;
     while i < instrs.len() {
+        *pos = i;
+        if let Some(task) = task {
+            task.at(dbg, i);
+        }
+        if let Some(limits) = limits {
+            limits.step()?;
+        }
+        if let Some(hook) = hook {
+            hook.step(Step {
+                state: state.clone(),
+                args,
+                tmps,
+                dbg,
+                pos: i,
+                instr: &instrs[i],
+            }).await?;
+        }
         match instrs[i].clone() {
             Instruction::Return(vi) => {
                 if defers.len() > 0 {
-                    Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                    run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                 }
                 return Ok((Some(get_var(state.clone(), args, tmps, vi.clone()).await?), None))
             },
             Instruction::Throw(vi) => {
                 if defers.len() > 0 {
-                    Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                    run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                 }
                 return Err(get_var(state.clone(), args, tmps, vi.clone()).await?)
             },
@@ -1017,7 +1259,7 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                     Some(u) => i = *u,
                     None => {
                         if defers.len() > 0 {
-                            Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                            run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                         }
                         return Ok((None, Some(l.clone())))
                     },
@@ -1046,7 +1288,7 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                         Some(u) => i = *u,
                         None => {
                             if defers.len() > 0 {
-                                Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                                run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                             }
                             return Ok((None, Some(l.clone())))
                         },
@@ -1055,11 +1297,11 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
             },
             Instruction::Block(instvec) => {
                 let s = new_state(state.clone()).await;
-                let r = Box::pin(interpret_instructions(s, args, tmps, &instvec, Some(pipeshort.clone()))).await?;
+                let r = Box::pin(interpret_instructions(s, args, tmps, &instvec, Some(pipeshort.clone()), child(dbg, i))).await?;
                 match r {
                     (Some(v), l) => {
                         if defers.len() > 0 {
-                            Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                            run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                         }
                         return Ok((Some(v), l))
                     },
@@ -1072,7 +1314,7 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                             Some(u) => i = *u,
                             None => {
                                 if defers.len() > 0 {
-                                    Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                                    run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                                 }
                                 return Ok((None, Some(l.clone())))
                             },
@@ -1123,9 +1365,20 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
             },
             Instruction::Catch(erri, err, instvec) => {
                 let s = new_state(state.clone()).await;
-                let r = Box::pin(interpret_instructions(s, args, tmps, &instvec, Some(pipeshort.clone()))).await;
+                if let Some(task) = task {
+                    task.start_catch();
+                }
+                let r = Box::pin(interpret_instructions(s, args, tmps, &instvec, Some(pipeshort.clone()), child(dbg, i))).await;
+                if let Some(task) = task {
+                    task.end_catch();
+                }
                 match r {
+                    Err(e) if is_exit(e.clone()).await => return Err(e),
                     Err(e) => {
+                        if let Some(limits) = limits {
+                            limits.caught();
+                        }
+                        capture_stack(state.clone(), e.clone()).await?;
                         set_var(state.clone(), tmps, erri, make_container(Value::Boolean(true))).await?;
                         set_var(state.clone(), tmps, err, e).await?;
                     },
@@ -1142,7 +1395,7 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                                     Some(u) => i = *u,
                                     None => {
                                         if defers.len() > 0 {
-                                            Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+                                            run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
                                         }
                                         return Ok((None, Some(l.clone())))
                                     },
@@ -1158,6 +1411,15 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                 set_var(state.clone(), tmps, r.clone(), op).await?;
             },
             Instruction::Defer(v) => {
+                if let Some(defers_dbg) = defers_dbg {
+                    let c = child(dbg, i);
+                    for j in 0..v.len() {
+                        if let Some(c) = &c && let Some(cc) = c.child(j) {
+                            defers_dbg.children.insert(defers.len() + j, cc);
+                        }
+                        defers_dbg.spans.push(c.as_ref().and_then(|c| c.span(j)));
+                    }
+                }
                 defers.extend(v.iter().map(|v| v.clone()));
             },
             Instruction::Boolean(res, op) => {
@@ -1177,7 +1439,7 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                 set_var(state.clone(), tmps, res.clone(), op).await?;
             },
             Instruction::MakeFunction(res, body) => {
-                let f = make_function(state.clone(), body, None).await?;
+                let f = make_function(state.clone(), body, None, child(dbg, i)).await?;
                 set_var(state.clone(), tmps, res, f).await?;
             },
             Instruction::Not(res, op) => {
@@ -1287,12 +1549,12 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                 let r = call(state.clone(), it, vec![]).await?;
                 let r = detuple(state.clone(), r).await?;
                 let b = match r.get(0) {
-                    None => return Err(make_err("invalid iterator used in loop")),
+                    None => return Err(make_err(ErrorKind::Type, "invalid iterator used in loop")),
                     Some(v) => to_boolean_base(state.clone(), v.clone()).await?,
                 };
                 if b {
                     let rv = match r.get(1) {
-                        None => return Err(make_err("invalid iterator used in loop")),
+                        None => return Err(make_err(ErrorKind::Type, "invalid iterator used in loop")),
                         Some(v) => v.clone(),
                     };
                     set_var(state.clone(), tmps, r1, make_container(Value::Boolean(true))).await?;
@@ -1325,11 +1587,59 @@ pub async fn interpret_instructions(state: StateContainer, args: &Vec<Container>
                 let r = isnt_null(state.clone(), op.clone()).await?;
                 set_var(state.clone(), tmps, res.clone(), r.clone()).await?;
             },
+            Instruction::Finally(instvec, finally) => {
+                let c = child(dbg, i);
+                let s = new_state(state.clone()).await;
+                let r = Box::pin(interpret_instructions(s, args, tmps, &instvec, Some(pipeshort.clone()), c.as_ref().and_then(|c| c.child(0)))).await;
+                // the finally block may throw and catch its own errors, the trace of an error that's still going is put back after it
+                let trace = match &r {
+                    Err(_) => take_trace(state.clone()).await,
+                    Ok(_) => None,
+                };
+                let s = new_state(state.clone()).await;
+                let f = Box::pin(interpret_instructions(s, args, tmps, &finally, Some(pipeshort.clone()), c.as_ref().and_then(|c| c.child(1)))).await?;
+                if let Some(trace) = trace {
+                    put_trace(state.clone(), trace).await;
+                }
+                // a return, break, continue or goto in the finally block replaces whatever the block did
+                let r = match f {
+                    (None, None) => r?,
+                    f => f,
+                };
+                match r {
+                    (Some(v), l) => {
+                        if !defers.is_empty() {
+                            run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
+                        }
+                        return Ok((Some(v), l))
+                    },
+                    (None, Some(l)) => {
+                        let opt = match l.clone() {
+                            Label::Unnamed(u) => ulabels.get(&u),
+                            Label::Named(s) => slabels.get(&s),
+                        };
+                        match opt {
+                            Some(u) => i = *u,
+                            None => {
+                                if !defers.is_empty() {
+                                    run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
+                                }
+                                return Ok((None, Some(l.clone())))
+                            },
+                        };
+                    },
+                    _ => {},
+                };
+            },
         }
         i += 1;
     }
     if defers.len() > 0 {
-        Box::pin(interpret_instructions(state.clone(), args, tmps, &defers.clone().into_iter().rev().collect(), None)).await?;
+        run_defers(state.clone(), args, tmps, defers, defers_dbg).await?;
     }
     Ok((None, None))
+}
+
+fn child(dbg: &Option<Arc<DebugInfo>>, i: usize) -> Option<Arc<DebugInfo>> {
+    dbg.as_ref().and_then(|dbg| dbg.child(i))
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
-use crate::{make_err, op::{add, and, call, divide, eql, ge, get, gt, isnt_null, le, lt, make_tuple, multiply, negate, neql, not, or, orthat, remainder, subtract, to_boolean_base, xor}, Container, Gi, StateContainer};
+use crate::{make_err, op::{add, and, call, divide, eql, ge, get, gt, isnt_null, le, lt, make_tuple, multiply, negate, neql, not, or, orthat, remainder, subtract, to_boolean_base, xor}, Container, ErrorKind, Gi, StateContainer};
 
 pub async fn plus(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[+] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[+] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -14,7 +14,7 @@ pub async fn plus(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn minus(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("[-] requires either 1 or 2 arguemnts"));
+        return Err(make_err(ErrorKind::Argument, "[-] requires either 1 or 2 arguemnts"));
     }
     if args.len() == 1 {
         negate(state, args[0].clone()).await
@@ -25,7 +25,7 @@ pub async fn minus(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn times(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[*] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[*] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -37,21 +37,21 @@ pub async fn times(state: StateContainer, args: Vec<Container>, _: Gi) -> Result
 
 pub async fn divide_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[/] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[/] requires 2 arguments"));
     }
     divide(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn modulus(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[%] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[%] requires 2 arguments"));
     }
     remainder(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn orthat_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[??] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[??] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -63,7 +63,7 @@ pub async fn orthat_(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
 
 pub async fn ternary(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 3 {
-        return Err(make_err("[?:] requires 3 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[?:] requires 3 arguments"));
     }
     if to_boolean_base(state.clone(), args[0].clone()).await? {
         Ok(args[1].clone())
@@ -74,56 +74,56 @@ pub async fn ternary(state: StateContainer, args: Vec<Container>, _: Gi) -> Resu
 
 pub async fn equalto(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[==] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[==] requires 2 arguments"));
     }
     eql(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn not_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("[!] requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "[!] requires 1 argument"));
     }
     not(state, args[0].clone()).await
 }
 
 pub async fn notequalto(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[!=] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[!=] requires 2 arguments"));
     }
     neql(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn less(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[<] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[<] requires 2 arguments"));
     }
     lt(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn lessorequal(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[<=] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[<=] requires 2 arguments"));
     }
     le(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn greater(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[>] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[>] requires 2 arguments"));
     }
     gt(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn greaterorequal(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[>=] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[>=] requires 2 arguments"));
     }
     ge(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn and_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[&] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[&] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -135,7 +135,7 @@ pub async fn and_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn or_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[|] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[|] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -147,14 +147,14 @@ pub async fn or_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<C
 
 pub async fn xor_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[^] requires 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[^] requires 2 arguments"));
     }
     xor(state, args[0].clone(), args[1].clone()).await
 }
 
 pub async fn property(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[.] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[.] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -166,14 +166,14 @@ pub async fn property(state: StateContainer, args: Vec<Container>, _: Gi) -> Res
 
 pub async fn tuple(_: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("[,] requires at least 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "[,] requires at least 1 argument"));
     }
     Ok(make_tuple(args.clone()))
 }
 
 pub async fn pipe(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() < 2 {
-        return Err(make_err("[|>] requires at least 2 arguments"));
+        return Err(make_err(ErrorKind::Argument, "[|>] requires at least 2 arguments"));
     }
     let mut args = args.clone();
     let mut res = args.remove(0);
@@ -185,7 +185,7 @@ pub async fn pipe(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<
 
 pub async fn isnt_null_(state: StateContainer, args: Vec<Container>, _: Gi) -> Result<Container, Container> {
     if args.len() == 0 {
-        return Err(make_err("[?] requires 1 argument"));
+        return Err(make_err(ErrorKind::Argument, "[?] requires 1 argument"));
     }
     isnt_null(state, args[0].clone()).await
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, ffi::{c_char, c_void, CStr, CString}, sync::Arc};
 
-use bodu_vm::{make_container, op::{add, and, call, detuple, divide, eql, ge, get, gt, isnt_null, le, lt, make_object, make_object_base, make_tuple, multiply, negate, neql, not, or, orthat, remainder, set, subtract, to_boolean_base, to_float_base, to_number_base, to_string_base, xor}, Container, Function, ObjectProp, StateContainer, Value};
+use bodu_vm::{make_container, op::{add, and, call, detuple, divide, eql, ge, get, gt, isnt_null, le, lt, make_object, make_object_base, make_tuple, multiply, negate, neql, not, or, orthat, remainder, set, spawn_here, subtract, to_boolean_base, to_float_base, to_number_base, to_string_base, xor}, Container, Function, ObjectProp, StateContainer, Value};
 use tokio::sync::Mutex;
 
 pub mod op;
@@ -186,7 +186,7 @@ pub extern "C" fn cbodu_tostring(state: *mut CBoduState, i: u64) -> *const c_cha
     let (tx, rx) = std::sync::mpsc::channel();
     {
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(to_string_base(state.clone(), v).await).unwrap()
         });
     }
@@ -225,7 +225,7 @@ pub extern "C" fn cbodu_tonumber(state: *mut CBoduState, i: u64) -> i64 {
     let (tx, rx) = std::sync::mpsc::channel();
     {
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(to_number_base(state.clone(), v).await).unwrap()
         });
     }
@@ -251,7 +251,7 @@ pub extern "C" fn cbodu_tofloat(state: *mut CBoduState, i: u64) -> f64 {
     let (tx, rx) = std::sync::mpsc::channel();
     {
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(to_float_base(state.clone(), v).await).unwrap()
         });
     }
@@ -277,7 +277,7 @@ pub extern "C" fn cbodu_toboolean(state: *mut CBoduState, i: u64) -> u8 {
     let (tx, rx) = std::sync::mpsc::channel();
     {
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(to_boolean_base(state.clone(), v).await).unwrap()
         });
     }
@@ -322,7 +322,7 @@ pub extern "C" fn cbodu_get(state: *mut CBoduState, obj: u64, key: u64) -> u64 {
     let (tx, rx) = std::sync::mpsc::channel();
     {
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(get(state.clone(), obj, key).await).unwrap()
         });
     }
@@ -364,7 +364,7 @@ pub extern "C" fn cbodu_set(state: *mut CBoduState, obj: u64, key: u64, val: u64
     let (tx, rx) = std::sync::mpsc::channel();
     {
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(set(state.clone(), obj, key, val).await).unwrap()
         });
     }
@@ -397,7 +397,7 @@ pub extern "C" fn cbodu_obj_getmetaobj(state: *mut CBoduState, obj: u64) -> u64
     };
     let r = {
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let obj = &mut *obj.lock().await;
             match obj {
                 Value::Object(obj) => tx.send(Some(obj.metaobj.clone())).unwrap(),
@@ -437,7 +437,7 @@ pub extern "C" fn cbodu_obj_setmetaobj(state: *mut CBoduState, obj: u64, val: u6
     };
     {
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let obj = &mut *obj.lock().await;
             match obj {
                 Value::Object(obj) => {
@@ -479,7 +479,7 @@ pub extern "C" fn cbodu_obj_makegetset(state: *mut CBoduState, obj: u64, key: u6
     let r = {
         let (tx, rx) = std::sync::mpsc::channel();
         let state = state.state.clone();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let key = match to_string_base(state.clone(), key).await {
                 Err(v) => {
                     tx.send(Err(v)).unwrap();
@@ -518,7 +518,7 @@ pub extern "C" fn cbodu_obj_getinternal(state: *mut CBoduState, obj: u64, i: u64
     };
     let r = {
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let obj = &mut *obj.lock().await;
             match obj {
                 Value::Object(obj) => {
@@ -557,7 +557,7 @@ pub extern "C" fn cbodu_obj_setinternal(state: *mut CBoduState, obj: u64, i: u64
     };
     {
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let obj = &mut *obj.lock().await;
             match obj {
                 Value::Object(obj) => {
@@ -644,7 +644,7 @@ pub extern "C" fn cbodu_setinternal(state: *mut CBoduState, f: u64, i: u64, val:
     };
     {
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let f = &mut *f.lock().await;
             match f {
                 Value::Function(f) => {
@@ -678,7 +678,7 @@ pub extern "C" fn cbodu_add(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(add(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -712,7 +712,7 @@ pub extern "C" fn cbodu_subtract(state: *mut CBoduState, op1: u64, op2: u64) ->
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(subtract(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -746,7 +746,7 @@ pub extern "C" fn cbodu_multiply(state: *mut CBoduState, op1: u64, op2: u64) ->
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(multiply(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -780,7 +780,7 @@ pub extern "C" fn cbodu_divide(state: *mut CBoduState, op1: u64, op2: u64) -> u6
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(divide(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -814,7 +814,7 @@ pub extern "C" fn cbodu_remainder(state: *mut CBoduState, op1: u64, op2: u64) ->
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(remainder(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -844,7 +844,7 @@ pub extern "C" fn cbodu_negate(state: *mut CBoduState, op: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(negate(state.clone(), op).await).unwrap();
         });
         rx.recv().unwrap()
@@ -878,7 +878,7 @@ pub extern "C" fn cbodu_eql(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(eql(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -912,7 +912,7 @@ pub extern "C" fn cbodu_neql(state: *mut CBoduState, op1: u64, op2: u64) -> u64
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(neql(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -942,7 +942,7 @@ pub extern "C" fn cbodu_not(state: *mut CBoduState, op: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(not(state.clone(), op).await).unwrap();
         });
         rx.recv().unwrap()
@@ -976,7 +976,7 @@ pub extern "C" fn cbodu_gt(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(gt(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1010,7 +1010,7 @@ pub extern "C" fn cbodu_ge(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(ge(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1044,7 +1044,7 @@ pub extern "C" fn cbodu_lt(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(lt(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1078,7 +1078,7 @@ pub extern "C" fn cbodu_le(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(le(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1112,7 +1112,7 @@ pub extern "C" fn cbodu_and(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(and(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1146,7 +1146,7 @@ pub extern "C" fn cbodu_or(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(or(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1180,7 +1180,7 @@ pub extern "C" fn cbodu_xor(state: *mut CBoduState, op1: u64, op2: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(xor(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1295,7 +1295,7 @@ pub extern "C" fn cbodu_call(state: *mut CBoduState, f: u64, args: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(call(state.clone(), f, args).await)
         });
         rx.recv().unwrap()
@@ -1321,7 +1321,7 @@ pub extern "C" fn cbodu_debug(state: *mut CBoduState) -> u8 {
     let state = state.state.clone();
     let debug = {
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(state.lock().await.debug).unwrap();
         });
         rx.recv().unwrap()
@@ -1349,7 +1349,7 @@ pub extern "C" fn cbodu_orthat(state: *mut CBoduState, op1: u64, op2: u64) -> u6
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(orthat(state.clone(), op1, op2).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1379,7 +1379,7 @@ pub extern "C" fn cbodu_isntnull(state: *mut CBoduState, op: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(isnt_null(state.clone(), op).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1425,7 +1425,7 @@ pub extern "C" fn cbodu_detuple(state: *mut CBoduState, v: u64) -> u64 {
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             tx.send(detuple(state.clone(), v).await).unwrap();
         });
         rx.recv().unwrap()
@@ -1462,7 +1462,7 @@ pub extern "C" fn cbodu_getregister(state: *mut CBoduState, s: *const c_char) ->
     let r = {
         let state = state.state.clone();
         let (tx, rx) = std::sync::mpsc::channel();
-        tokio::spawn(async move {
+        spawn_here(async move {
             let gd = &mut *state.lock().await;
             let gd = &mut *gd.globaldata.as_mut().unwrap().lock().await;
             tx.send(match gd.register.get(&s) {
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, ffi::c_void, sync::Arc};
 
-use bodu_vm::{make_container, make_err, op::to_number_base, Container, Function, StateContainer, Value};
+use bodu_vm::{make_container, make_err, op::to_number_base, Container, ErrorKind, Function, StateContainer, Value};
 use libloading::{Library, Symbol};
 
 use crate::{CBoduFn, CBoduState};
@@ -9,7 +9,7 @@ pub async fn load_lib(state: StateContainer, name: String) -> Result<Container,
     let lib = unsafe {
         Library::new(name)
     };
-    let lib = lib.map_err(|_| make_err("loaded invalid library"))?;
+    let lib = lib.map_err(|_| make_err(ErrorKind::Import, "loaded invalid library"))?;
     let lid = {
         let globaldata = &mut *state.lock().await;
         let globaldata = &mut *globaldata.globaldata.as_mut().unwrap().lock().await;
@@ -34,7 +34,7 @@ pub async fn load_lib(state: StateContainer, name: String) -> Result<Container,
                         o.clone()
                     };
                     let f: Symbol<'_, CBoduFn> = unsafe {
-                        o.get(b"cbodu_main").map_err(|_| make_err("loaded invalid library"))?
+                        o.get(b"cbodu_main").map_err(|_| make_err(ErrorKind::Import, "loaded invalid library"))?
                     };
                     let mut s = CBoduState {
                         i: 0,
@@ -0,0 +1,186 @@
+use std::{collections::{BTreeMap, HashMap}, io::Write, path::PathBuf, sync::{Arc, Mutex}};
+
+use bodu_vm::{DebugInfo, HookFuture, StateContainer, Step, VmHook};
+
+// the instructions of a file, by the block they're in and their index in it. the blocks are numbered by their position
+// in the debug info, so a file parsed again (imported by many test files) adds to the same counts
+#[derive(Default, Debug)]
+struct FileCoverage {
+    blocks: HashMap<Vec<usize>, usize>,
+    instrs: HashMap<(usize, usize), (usize, u64)>, // the line and how many times it ran
+}
+
+#[derive(Default, Debug)]
+struct Inner {
+    files: BTreeMap<String, FileCoverage>,
+    blocks: HashMap<usize, (String, usize)>, // the address of a DebugInfo, its file and its block
+    sources: HashMap<usize, String>, // the address of a Source and its file
+    kept: Vec<Arc<DebugInfo>>, // so the addresses aren't reused
+}
+
+// records which instructions and lines ran, for every file whose code was called
+#[derive(Debug)]
+pub struct Coverage {
+    output: PathBuf, // where the lcov file is written
+    inner: Mutex<Inner>,
+}
+
+impl Coverage {
+    pub fn new(output: PathBuf) -> Coverage {
+        Coverage {
+            output,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+}
+
+impl Inner {
+    // the first time code of a source is called, all of its instructions are added with no runs, so the code that never
+    // runs is counted too. code from load and exec has names like <load>, every one of them is a file of its own
+    fn add_source(&mut self, dbg: &Arc<DebugInfo>) {
+        let key = Arc::as_ptr(&dbg.source) as usize;
+        if self.sources.contains_key(&key) {
+            return;
+        }
+        let mut name = dbg.source.name.clone();
+        if name.starts_with('<') {
+            let n = self.files.keys().filter(|f| f.starts_with(&dbg.source.name)).count();
+            if n > 0 {
+                name = format!("{} #{}", dbg.source.name, n + 1);
+            }
+        }
+        self.sources.insert(key, name.clone());
+        self.kept.push(dbg.clone());
+        self.add_block(&name, dbg, Vec::new());
+    }
+
+    fn add_block(&mut self, name: &str, dbg: &Arc<DebugInfo>, path: Vec<usize>) {
+        let file = self.files.entry(name.to_string()).or_default();
+        let len = file.blocks.len();
+        let block = *file.blocks.entry(path.clone()).or_insert(len);
+        for (i, span) in dbg.spans.iter().enumerate() {
+            if let Some(span) = span {
+                file.instrs.entry((block, i)).or_insert((span.line, 0));
+            }
+        }
+        self.blocks.insert(Arc::as_ptr(dbg) as usize, (name.to_string(), block));
+        let mut children = dbg.children.iter().collect::<Vec<_>>();
+        children.sort_by_key(|(i, _)| **i);
+        for (i, child) in children {
+            let mut path = path.clone();
+            path.push(*i);
+            self.add_block(name, child, path);
+        }
+    }
+}
+
+// 1-3, 7, 9-10
+fn ranges(lines: &[usize]) -> String {
+    let mut out: Vec<(usize, usize)> = Vec::new();
+    for line in lines {
+        match out.last_mut() {
+            Some((_, end)) if *end + 1 == *line => *end = *line,
+            _ => out.push((*line, *line)),
+        }
+    }
+    out.into_iter().map(|(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) }).collect::<Vec<_>>().join(", ")
+}
+
+fn percent(n: usize, total: usize) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", n as f64 * 100.0 / total as f64)
+}
+
+impl VmHook for Coverage {
+    fn step<'a>(&'a self, step: Step<'a>) -> HookFuture<'a> {
+        Box::pin(async move {
+            let dbg = match step.dbg {
+                Some(dbg) => dbg,
+                None => return Ok(()),
+            };
+            let inner = &mut *self.inner.lock().unwrap();
+            // the blocks of defers are made while the code runs, they count for the line only
+            let (name, block) = match inner.blocks.get(&(Arc::as_ptr(dbg) as usize)) {
+                Some(b) => b.clone(),
+                None => {
+                    let name = match inner.sources.get(&(Arc::as_ptr(&dbg.source) as usize)) {
+                        Some(name) => name.clone(),
+                        None => return Ok(()),
+                    };
+                    if let Some(span) = dbg.span(step.pos) {
+                        let file = inner.files.entry(name).or_default();
+                        if let Some(v) = file.instrs.values_mut().find(|(line, _)| *line == span.line) {
+                            v.1 += 1;
+                        }
+                    }
+                    return Ok(());
+                },
+            };
+            let file = inner.files.entry(name).or_default();
+            if let Some(v) = file.instrs.get_mut(&(block, step.pos)) {
+                v.1 += 1;
+            }
+            Ok(())
+        })
+    }
+
+    fn enter<'a>(&'a self, _state: StateContainer, dbg: Option<Arc<DebugInfo>>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if let Some(dbg) = dbg {
+                self.inner.lock().unwrap().add_source(&dbg);
+            }
+            Ok(())
+        })
+    }
+
+    // the summary goes to stderr and the lcov file to the output file. a line ran as many times as its instruction that ran the most
+    fn finish(&self) {
+        let inner = self.inner.lock().unwrap();
+        let mut out = std::io::stderr().lock();
+        let mut lcov = String::new();
+        let (mut lines_hit, mut lines_total, mut instrs_hit, mut instrs_total) = (0, 0, 0, 0);
+        let _ = writeln!(out, "\ncoverage:");
+        let _ = writeln!(out, "{:>16} {:>16}  file", "lines", "instrs");
+        for (name, file) in inner.files.iter() {
+            let mut lines: BTreeMap<usize, u64> = BTreeMap::new();
+            for (line, hits) in file.instrs.values() {
+                let v = lines.entry(*line).or_default();
+                *v = (*v).max(*hits);
+            }
+            let hit = lines.values().filter(|hits| **hits > 0).count();
+            let ran = file.instrs.values().filter(|(_, hits)| *hits > 0).count();
+            lines_hit += hit;
+            lines_total += lines.len();
+            instrs_hit += ran;
+            instrs_total += file.instrs.len();
+            let missed = lines.iter().filter(|(_, hits)| **hits == 0).map(|(line, _)| *line).collect::<Vec<_>>();
+            let _ = write!(out, "{:>9} {:>6} {:>9} {:>6}  {}", format!("{}/{}", hit, lines.len()), percent(hit, lines.len()), format!("{}/{}", ran, file.instrs.len()), percent(ran, file.instrs.len()), name);
+            if missed.is_empty() {
+                let _ = writeln!(out);
+            } else {
+                let _ = writeln!(out, ", never ran: {}", ranges(&missed));
+            }
+            // lcov needs a file it can open, code from load and exec isn't in one
+            if name.starts_with('<') {
+                continue;
+            }
+            let path = std::fs::canonicalize(name).unwrap_or_else(|_| PathBuf::from(name));
+            lcov.push_str(&format!("TN:\nSF:{}\n", path.display()));
+            for (line, hits) in lines.iter() {
+                lcov.push_str(&format!("DA:{},{}\n", line, hits));
+            }
+            lcov.push_str(&format!("LF:{}\nLH:{}\nend_of_record\n", lines.len(), hit));
+        }
+        let _ = writeln!(out, "{:>9} {:>6} {:>9} {:>6}  total", format!("{}/{}", lines_hit, lines_total), percent(lines_hit, lines_total), format!("{}/{}", instrs_hit, instrs_total), percent(instrs_hit, instrs_total));
+        match std::fs::write(&self.output, lcov) {
+            Ok(_) => {
+                let _ = writeln!(out, "\nlcov written to {}", self.output.display());
+            },
+            Err(e) => {
+                let _ = writeln!(out, "\nError while writing {}: {}", self.output.display(), e);
+            },
+        }
+    }
+}
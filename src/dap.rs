@@ -0,0 +1,585 @@
+use std::{collections::HashMap, fs::File, io::{Read, Write}, path::PathBuf, sync::{atomic::{AtomicBool, AtomicI64, Ordering}, Arc, Mutex}};
+
+use bodu_std::{init_global_state, new_global_state};
+use bodu_vm::{op::{call, is_exit, make_function}, Container, DebugInfo, HookFuture, ObjectProp, Source, StateContainer, Step, Value, VmHook};
+use serde_json::{json, Value as Json};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{debugger::{evaluate, same_file, show, Mode, Stepper}, default_limits, exit_code, graceful, lsp::read_message, parse, run_gdefers, runtime_error};
+
+// what a variablesReference points to, they are numbered from 1 and forgotten when the code runs again
+#[derive(Debug)]
+enum Handle {
+    Value(Container), // an object or an array, its properties or elements are the variables
+    List(Vec<(String, Container)>),
+}
+
+#[derive(Debug)]
+struct Inner {
+    breakpoints: HashMap<String, Vec<usize>>, // the lines of every file
+    break_uncaught: bool,
+    break_all: bool, // errors that are caught too
+    stepper: Stepper,
+    reason: Option<&'static str>, // why the next step stops, for pause and stopOnEntry
+    handles: Vec<Handle>,
+}
+
+// the debug adapter is the hook of the global state. the requests that need the code to be stopped are sent by the
+// thread reading stdin to the code, which answers them while it waits in pause
+#[derive(Debug)]
+struct Adapter {
+    client: Mutex<Option<File>>, // the stdout from before the program's output was captured, None writes to stdout
+    seq: AtomicI64,
+    inner: Mutex<Inner>, // never held across an await
+    paused: AtomicBool,
+    evaluating: AtomicBool,
+    requests: tokio::sync::Mutex<mpsc::UnboundedReceiver<Json>>,
+}
+
+const THREAD_ID: i64 = 1;
+
+pub async fn dap(debug: bool) {
+    let (client, captured) = capture();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let adapter = Arc::new(Adapter {
+        client: Mutex::new(client),
+        seq: AtomicI64::new(1),
+        inner: Mutex::new(Inner {
+            breakpoints: HashMap::new(),
+            break_uncaught: true,
+            break_all: false,
+            stepper: Stepper::new(Mode::Continue),
+            reason: None,
+            handles: Vec::new(),
+        }),
+        paused: AtomicBool::new(false),
+        evaluating: AtomicBool::new(false),
+        requests: tokio::sync::Mutex::new(rx),
+    });
+    let mut saved = Vec::new();
+    let mut threads = Vec::new();
+    for c in captured {
+        let adapter = adapter.clone();
+        saved.push((c.fd, c.saved));
+        threads.push(std::thread::spawn(move || forward(adapter, c.pipe, c.category)));
+    }
+    let (launch_tx, launch_rx) = oneshot::channel();
+    {
+        let adapter = adapter.clone();
+        std::thread::spawn(move || adapter.read_requests(tx, launch_tx));
+    }
+    let config = match launch_rx.await {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+    let code = launch(adapter.clone(), config, debug).await;
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    restore(&saved);
+    for t in threads {
+        let _ = t.join();
+    }
+    adapter.event("exited", json!({"exitCode": code}));
+    adapter.event("terminated", json!({}));
+    // the client ends the session with disconnect
+    std::future::pending::<()>().await;
+}
+
+// runs the program of the launch request, returns the exit code
+async fn launch(adapter: Arc<Adapter>, config: Json, debug: bool) -> u8 {
+    let program = match config["program"].as_str() {
+        Some(p) => p.to_string(),
+        None => {
+            adapter.output("stderr", "the launch request requires a program\n");
+            return 1;
+        },
+    };
+    let args = config["args"].as_array().map(|a| a.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect::<Vec<_>>()).unwrap_or_default();
+    if config["stopOnEntry"].as_bool().unwrap_or(false) {
+        let inner = &mut *adapter.inner.lock().unwrap();
+        inner.stepper.mode = Mode::StepInto;
+        inner.reason = Some("entry");
+    }
+    let contents = match std::fs::read_to_string(&program) {
+        Ok(c) => c,
+        Err(e) => {
+            adapter.output("stderr", &format!("Error while reading {}: {}\n", program, e));
+            return 1;
+        },
+    };
+    let (instrs, dbg) = match parse(Source::new(program.clone(), contents), false) {
+        Ok(code) => code,
+        Err(e) => {
+            adapter.output("stderr", &format!("{}\n", e));
+            return 1;
+        },
+    };
+    let curdir = std::fs::canonicalize(&program).ok().and_then(|p| p.parent().map(|p| p.to_path_buf())).unwrap_or_else(|| std::env::current_dir().unwrap());
+    let state = new_global_state(debug, curdir).await;
+    init_global_state(state.clone(), args).await;
+    {
+        let st = state.lock().await;
+        let gd = &mut *st.globaldata.as_ref().unwrap().lock().await;
+        gd.hook = Some(adapter.clone());
+        gd.limits = Some(default_limits());
+    }
+    let f = make_function(state.clone(), instrs, None, Some(Arc::new(dbg))).await.unwrap();
+    match call(state.clone(), f, vec![]).await {
+        Ok(_) => graceful(state.clone()).await,
+        Err(e) if is_exit(e.clone()).await => {
+            run_gdefers(state.clone()).await;
+            exit_code(state.clone()).await
+        },
+        Err(e) => {
+            adapter.output("stderr", &format!("{}\n", runtime_error(state.clone(), e).await));
+            1
+        },
+    }
+}
+
+// stdout or stderr going into a pipe, saved is where it went before
+struct Captured {
+    fd: i32,
+    saved: File,
+    pipe: File,
+    category: &'static str,
+}
+
+// the program's stdout and stderr are sent to the client as output events, since stdout is where the messages go.
+// returns the stdout to write the messages to and what was captured
+#[cfg(unix)]
+fn capture() -> (Option<File>, Vec<Captured>) {
+    use std::os::fd::FromRawFd;
+    let client = unsafe { libc::dup(1) };
+    if client < 0 {
+        return (None, Vec::new());
+    }
+    let mut captured = Vec::new();
+    for (fd, category) in [(1, "stdout"), (2, "stderr")] {
+        let mut fds = [0; 2];
+        unsafe {
+            let saved = libc::dup(fd);
+            if saved < 0 || libc::pipe(fds.as_mut_ptr()) < 0 {
+                continue;
+            }
+            libc::dup2(fds[1], fd);
+            libc::close(fds[1]);
+            captured.push(Captured {
+                fd,
+                saved: File::from_raw_fd(saved),
+                pipe: File::from_raw_fd(fds[0]),
+                category,
+            });
+        }
+    }
+    (Some(unsafe { File::from_raw_fd(client) }), captured)
+}
+
+#[cfg(not(unix))]
+fn capture() -> (Option<File>, Vec<Captured>) {
+    (None, Vec::new())
+}
+
+// puts stdout and stderr back, which closes the pipes so the threads forwarding the output end
+#[cfg(unix)]
+fn restore(saved: &[(i32, File)]) {
+    use std::os::fd::AsRawFd;
+    for (fd, file) in saved {
+        unsafe {
+            libc::dup2(file.as_raw_fd(), *fd);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn restore(_: &[(i32, File)]) {}
+
+fn forward(adapter: Arc<Adapter>, mut file: File, category: &'static str) {
+    let mut buf = [0; 4096];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => adapter.output(category, &String::from_utf8_lossy(&buf[..n])),
+        }
+    }
+}
+
+// the elements of an array, None if v isn't one
+async fn array_items(v: &Container) -> Option<Vec<Container>> {
+    let o = match &*v.lock().await {
+        Value::Object(o) => o.clone(),
+        _ => return None,
+    };
+    match o.internals.get(&u64::MAX) {
+        Some(tag) if matches!(&*tag.lock().await, Value::String(s) if s == "array") => {},
+        _ => return None,
+    }
+    let items = o.externals.get(&0)?.clone();
+    let items = items.lock().await;
+    items.downcast_ref::<Vec<Container>>().cloned()
+}
+
+// what's inside a value in the variables view, getters aren't called
+async fn children(v: &Container) -> Vec<(String, Option<Container>)> {
+    if let Some(items) = array_items(v).await {
+        return items.into_iter().enumerate().map(|(i, v)| (i.to_string(), Some(v))).collect();
+    }
+    let props = match &*v.lock().await {
+        Value::Object(o) => o.props.clone(),
+        _ => return Vec::new(),
+    };
+    let mut props = props.into_iter().map(|(name, prop)| match prop {
+        ObjectProp::Value(v) => (name, Some(v)),
+        ObjectProp::GetSet(_, _) => (name, None),
+    }).collect::<Vec<_>>();
+    props.sort_by(|a, b| a.0.cmp(&b.0));
+    props
+}
+
+impl Adapter {
+    fn send(&self, mut message: Json) {
+        message["seq"] = json!(self.seq.fetch_add(1, Ordering::SeqCst));
+        let body = message.to_string();
+        let message = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+        match &mut *self.client.lock().unwrap() {
+            Some(client) => {
+                let _ = client.write_all(message.as_bytes());
+                let _ = client.flush();
+            },
+            None => {
+                let mut out = std::io::stdout().lock();
+                let _ = out.write_all(message.as_bytes());
+                let _ = out.flush();
+            },
+        }
+    }
+
+    fn respond(&self, request: &Json, body: Json) {
+        self.send(json!({"type": "response", "request_seq": request["seq"], "success": true, "command": request["command"], "body": body}));
+    }
+
+    fn fail(&self, request: &Json, message: &str) {
+        self.send(json!({"type": "response", "request_seq": request["seq"], "success": false, "command": request["command"], "message": message}));
+    }
+
+    fn event(&self, event: &str, body: Json) {
+        self.send(json!({"type": "event", "event": event, "body": body}));
+    }
+
+    fn output(&self, category: &str, output: &str) {
+        self.event("output", json!({"category": category, "output": output}));
+    }
+
+    fn ignoring(&self) -> bool {
+        self.evaluating.load(Ordering::SeqCst)
+    }
+
+    // runs in a thread of its own. the program starts once it was launched and configured
+    fn read_requests(&self, tx: mpsc::UnboundedSender<Json>, launch_tx: oneshot::Sender<Json>) {
+        let mut input = std::io::stdin().lock();
+        let mut launch_tx = Some(launch_tx);
+        let mut config = None;
+        let mut configured = false;
+        while let Some(request) = read_message(&mut input) {
+            let args = &request["arguments"];
+            match request["command"].as_str().unwrap_or_default() {
+                "initialize" => {
+                    self.respond(&request, json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                        "exceptionBreakpointFilters": [
+                            {"filter": "uncaught", "label": "Uncaught errors", "default": true},
+                            {"filter": "all", "label": "All errors", "default": false},
+                        ],
+                    }));
+                    self.event("initialized", json!({}));
+                },
+                "launch" => {
+                    config = Some(args.clone());
+                    self.respond(&request, json!({}));
+                },
+                "configurationDone" => {
+                    configured = true;
+                    self.respond(&request, json!({}));
+                },
+                "setBreakpoints" => {
+                    let path = args["source"]["path"].as_str().unwrap_or_default().to_string();
+                    let lines = args["breakpoints"].as_array().map(|b| b.iter().filter_map(|b| b["line"].as_u64().map(|l| l as usize)).collect::<Vec<_>>()).unwrap_or_default();
+                    let breakpoints = lines.iter().map(|line| json!({"verified": true, "line": line})).collect::<Vec<_>>();
+                    self.inner.lock().unwrap().breakpoints.insert(path, lines);
+                    self.respond(&request, json!({"breakpoints": breakpoints}));
+                },
+                "setExceptionBreakpoints" => {
+                    let filters = args["filters"].as_array().cloned().unwrap_or_default();
+                    {
+                        let inner = &mut *self.inner.lock().unwrap();
+                        inner.break_uncaught = filters.iter().any(|f| f == "uncaught");
+                        inner.break_all = filters.iter().any(|f| f == "all");
+                    }
+                    self.respond(&request, json!({}));
+                },
+                "threads" => self.respond(&request, json!({"threads": [{"id": THREAD_ID, "name": "main"}]})),
+                "pause" => {
+                    {
+                        let inner = &mut *self.inner.lock().unwrap();
+                        inner.stepper.mode = Mode::StepInto;
+                        inner.reason = Some("pause");
+                    }
+                    self.respond(&request, json!({}));
+                },
+                "disconnect" | "terminate" => {
+                    self.respond(&request, json!({}));
+                    std::process::exit(0);
+                },
+                "continue" | "next" | "stepIn" | "stepOut" | "stackTrace" | "scopes" | "variables" | "evaluate" => {
+                    if self.paused.load(Ordering::SeqCst) {
+                        let _ = tx.send(request);
+                    } else if request["command"] == "continue" {
+                        self.respond(&request, json!({"allThreadsContinued": true}));
+                    } else {
+                        self.fail(&request, "the program is running");
+                    }
+                },
+                command => self.fail(&request, &format!("{} isn't supported", command)),
+            }
+            if configured && let Some(config) = config.take() && let Some(launch_tx) = launch_tx.take() {
+                let _ = launch_tx.send(config);
+            }
+        }
+        std::process::exit(0);
+    }
+
+    // tells the client the code stopped and answers its requests until one of them runs the code
+    async fn pause(&self, step: &Step<'_>, reason: &str, text: Option<String>) {
+        self.inner.lock().unwrap().handles.clear();
+        self.paused.store(true, Ordering::SeqCst);
+        let mut body = json!({"reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true});
+        if let Some(text) = text {
+            body["text"] = json!(text);
+        }
+        self.event("stopped", body);
+        let mut requests = self.requests.lock().await;
+        loop {
+            let request = match requests.recv().await {
+                Some(r) => r,
+                None => return,
+            };
+            let args = &request["arguments"];
+            let mode: fn(usize) -> Mode = match request["command"].as_str().unwrap_or_default() {
+                "continue" => |_| Mode::Continue,
+                "next" => Mode::StepOver,
+                "stepIn" => |_| Mode::StepInto,
+                "stepOut" => Mode::StepOut,
+                "stackTrace" => {
+                    let frames = self.stack_frames();
+                    self.respond(&request, json!({"stackFrames": frames, "totalFrames": frames.len()}));
+                    continue;
+                },
+                "scopes" => {
+                    match self.scopes(step, args["frameId"].as_u64().unwrap_or(0) as usize).await {
+                        Some(scopes) => self.respond(&request, json!({"scopes": scopes})),
+                        None => self.fail(&request, "there's no such frame"),
+                    }
+                    continue;
+                },
+                "variables" => {
+                    match self.variables(args["variablesReference"].as_u64().unwrap_or(0) as usize).await {
+                        Some(variables) => self.respond(&request, json!({"variables": variables})),
+                        None => self.fail(&request, "there's no such variable"),
+                    }
+                    continue;
+                },
+                "evaluate" => {
+                    let frame = args["frameId"].as_u64().map(|id| id as usize);
+                    let state = {
+                        let inner = self.inner.lock().unwrap();
+                        match frame {
+                            Some(id) => inner.stepper.frames.get(id.wrapping_sub(1)).map(|f| f.state.clone()),
+                            None => inner.stepper.frames.last().map(|f| f.state.clone()),
+                        }
+                    };
+                    let state = state.unwrap_or_else(|| step.state.clone());
+                    let code = args["expression"].as_str().unwrap_or_default();
+                    self.evaluating.store(true, Ordering::SeqCst);
+                    let r = evaluate(state.clone(), code).await;
+                    self.evaluating.store(false, Ordering::SeqCst);
+                    match r {
+                        Ok(v) => {
+                            let (result, reference) = self.variable(state.clone(), v).await;
+                            self.respond(&request, json!({"result": result, "variablesReference": reference}));
+                        },
+                        Err(e) => self.fail(&request, &show(state.clone(), e).await),
+                    }
+                    continue;
+                },
+                command => {
+                    self.fail(&request, &format!("{} isn't supported", command));
+                    continue;
+                },
+            };
+            self.inner.lock().unwrap().stepper.resume(mode);
+            self.paused.store(false, Ordering::SeqCst);
+            self.respond(&request, json!({"allThreadsContinued": true}));
+            return;
+        }
+    }
+
+    // the innermost frame first, frame ids are the position from the outermost frame plus one
+    fn stack_frames(&self) -> Vec<Json> {
+        let inner = self.inner.lock().unwrap();
+        inner.stepper.frames.iter().enumerate().rev().map(|(i, frame)| {
+            let mut v = json!({"id": i + 1, "name": frame.name, "line": 0, "column": 0});
+            if let Some((source, span)) = &frame.location {
+                let path = std::fs::canonicalize(&source.name).unwrap_or_else(|_| PathBuf::from(&source.name));
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| source.name.clone());
+                v["line"] = json!(span.line);
+                v["column"] = json!(span.col);
+                v["source"] = json!({"name": name, "path": path.display().to_string()});
+            }
+            v
+        }).collect()
+    }
+
+    fn handle(&self, handle: Handle) -> usize {
+        let inner = &mut *self.inner.lock().unwrap();
+        inner.handles.push(handle);
+        inner.handles.len()
+    }
+
+    // the chain of states of the frame, from its innermost block to the global scope. the arguments are only known for the frame that stopped
+    async fn scopes(&self, step: &Step<'_>, id: usize) -> Option<Vec<Json>> {
+        let (mut state, innermost) = {
+            let inner = self.inner.lock().unwrap();
+            let frame = inner.stepper.frames.get(id.checked_sub(1)?)?;
+            (frame.state.clone(), id == inner.stepper.frames.len())
+        };
+        let mut scopes = Vec::new();
+        if innermost && !step.args.is_empty() {
+            let args = step.args.iter().enumerate().map(|(i, v)| (i.to_string(), v.clone())).collect();
+            scopes.push(json!({"name": "Arguments", "presentationHint": "arguments", "variablesReference": self.handle(Handle::List(args)), "expensive": false}));
+        }
+        let mut level = 0;
+        loop {
+            let (scope, parent) = {
+                let st = state.lock().await;
+                (st.scope.clone(), st.parent.clone())
+            };
+            let reference = self.handle(Handle::Value(scope));
+            match parent {
+                Some(parent) => {
+                    let name = if level == 0 { "Locals".to_string() } else { format!("Scope {}", level) };
+                    let mut v = json!({"name": name, "variablesReference": reference, "expensive": false});
+                    if level == 0 {
+                        v["presentationHint"] = json!("locals");
+                    }
+                    scopes.push(v);
+                    state = parent;
+                    level += 1;
+                },
+                None => {
+                    scopes.push(json!({"name": "Globals", "variablesReference": reference, "expensive": true}));
+                    return Some(scopes);
+                },
+            }
+        }
+    }
+
+    async fn variables(&self, reference: usize) -> Option<Vec<Json>> {
+        let (items, state) = {
+            let inner = self.inner.lock().unwrap();
+            let state = inner.stepper.frames.last()?.state.clone();
+            match inner.handles.get(reference.checked_sub(1)?)? {
+                Handle::Value(v) => (Err(v.clone()), state),
+                Handle::List(list) => (Ok(list.iter().map(|(name, v)| (name.clone(), Some(v.clone()))).collect::<Vec<_>>()), state),
+            }
+        };
+        let items = match items {
+            Ok(items) => items,
+            Err(v) => children(&v).await,
+        };
+        let mut variables = Vec::new();
+        for (name, v) in items {
+            let (value, reference) = match v {
+                Some(v) => self.variable(state.clone(), v).await,
+                None => ("<getter>".to_string(), 0),
+            };
+            variables.push(json!({"name": name, "value": value, "variablesReference": reference}));
+        }
+        Some(variables)
+    }
+
+    // the text of a value and the reference to what's inside it, 0 if there's nothing
+    async fn variable(&self, state: StateContainer, v: Container) -> (String, usize) {
+        let reference = if children(&v).await.is_empty() { 0 } else { self.handle(Handle::Value(v.clone())) };
+        let value = match &*v.lock().await {
+            Value::Object(o) if reference != 0 && o.internals.is_empty() => Some("{...}".to_string()),
+            _ => None,
+        };
+        let value = match value {
+            Some(value) => value,
+            None => show(state, v).await,
+        };
+        (value, reference)
+    }
+
+    fn stops_at(&self, step: &Step<'_>) -> Option<&'static str> {
+        let inner = &mut *self.inner.lock().unwrap();
+        let breakpoints = &inner.breakpoints;
+        let reason = inner.stepper.step(step, |source, line| breakpoints.iter().any(|(file, lines)| lines.contains(&line) && same_file(&source.name, file)))?;
+        match (reason, inner.reason.take()) {
+            ("step", Some(reason)) => Some(reason),
+            (reason, _) => Some(reason),
+        }
+    }
+}
+
+impl VmHook for Adapter {
+    fn step<'a>(&'a self, step: Step<'a>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            if let Some(reason) = self.stops_at(&step) {
+                self.pause(&step, reason, None).await;
+            }
+            Ok(())
+        })
+    }
+
+    fn enter<'a>(&'a self, state: StateContainer, dbg: Option<Arc<DebugInfo>>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            self.inner.lock().unwrap().stepper.enter(state, dbg);
+            Ok(())
+        })
+    }
+
+    fn leave<'a>(&'a self, _result: &'a Result<Container, Container>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            self.inner.lock().unwrap().stepper.leave();
+            Ok(())
+        })
+    }
+
+    fn throw<'a>(&'a self, step: Step<'a>, error: Container, caught: bool) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            let stops = {
+                let inner = self.inner.lock().unwrap();
+                if caught { inner.break_all } else { inner.break_uncaught }
+            };
+            if stops {
+                let text = show(step.state.clone(), error).await;
+                self.pause(&step, "exception", Some(text)).await;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,450 @@
+use std::{io::{BufRead, Write}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
+
+use bodu_vm::{make_container, make_err, op::{call, resolve_bind, to_string_base}, Container, DebugInfo, ErrorKind, HookFuture, ObjectProp, Source, Span, StateContainer, Step, Value, VmHook};
+
+#[derive(Clone, Copy, Debug)]
+pub enum Mode {
+    Continue,
+    StepInto,
+    StepOver(usize), // stops when the depth is this or less
+    StepOut(usize), // stops when the depth is less than this
+}
+
+#[derive(Debug)]
+pub struct Frame {
+    pub name: String,
+    pub state: StateContainer, // the state of the innermost block the function was in last
+    pub location: Option<(Arc<Source>, Span)>,
+    returned: bool, // a function it called just returned, so the line it's on can stop the code again
+}
+
+// the bodu functions being called and how far the code runs before stopping, what the debuggers have in common
+#[derive(Debug)]
+pub struct Stepper {
+    pub mode: Mode,
+    pub frames: Vec<Frame>,
+}
+
+impl Stepper {
+    pub fn new(mode: Mode) -> Stepper {
+        Stepper {
+            mode,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn enter(&mut self, state: StateContainer, dbg: Option<Arc<DebugInfo>>) {
+        let name = dbg.and_then(|dbg| dbg.name.clone()).unwrap_or_else(|| "<anonymous>".to_string());
+        self.frames.push(Frame {
+            name,
+            state,
+            location: None,
+            returned: false,
+        });
+    }
+
+    pub fn leave(&mut self) {
+        self.frames.pop();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.returned = true;
+        }
+    }
+
+    // called before every instruction, only the first instruction of a line can stop the code. returns why it stops
+    pub fn step(&mut self, step: &Step<'_>, breakpoint: impl Fn(&Source, usize) -> bool) -> Option<&'static str> {
+        let (source, span) = step.location()?;
+        let depth = self.frames.len();
+        let frame = self.frames.last_mut()?;
+        let same_line = !frame.returned && frame.location.as_ref().is_some_and(|(s, sp)| Arc::ptr_eq(s, &source) && sp.line == span.line);
+        frame.returned = false;
+        frame.location = Some((source.clone(), span));
+        frame.state = step.state.clone();
+        if same_line {
+            return None;
+        }
+        if breakpoint(&source, span.line) {
+            return Some("breakpoint");
+        }
+        match self.mode {
+            Mode::Continue => None,
+            Mode::StepInto => Some("step"),
+            Mode::StepOver(d) => (depth <= d).then_some("step"),
+            Mode::StepOut(d) => (depth < d).then_some("step"),
+        }
+    }
+
+    pub fn resume(&mut self, mode: impl FnOnce(usize) -> Mode) {
+        self.mode = mode(self.frames.len());
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    breakpoints: Vec<(String, usize)>, // file and line
+    stepper: Stepper,
+}
+
+// a debugger on the terminal, it's the hook of the global state. the frames are the bodu functions being called,
+// code running in async functions isn't told apart from the rest
+#[derive(Debug)]
+pub struct Debugger {
+    file: String,
+    inner: Mutex<Inner>, // never held across an await
+    evaluating: AtomicBool, // the code of p runs without stopping
+    detached: AtomicBool, // stdin was closed, the code runs until the end
+}
+
+const HELP: &str = "commands:
+  c, continue          run until a breakpoint or an uncaught error
+  s, step              run until the next line, going into function calls
+  n, next              run until the next line in this function
+  o, out               run until this function returns
+  b, break [FILE:]LINE set a breakpoint, without a line the breakpoints are listed
+  d, delete [FILE:]LINE remove a breakpoint, without a line all of them are removed
+  bt, backtrace        show the functions being called
+  l, list              show the code around the current line
+  scope [all]          show the variables of every scope, the global one only with all
+  args                 show the arguments of the function
+  temps                show the temporaries of the function
+  p, print EXPR        evaluate an expression or run a statement where the code stopped
+  q, quit              stop the program
+  h, help              show this";
+
+impl Debugger {
+    pub fn new(file: String, breakpoints: Vec<String>) -> Debugger {
+        let mut v = Vec::new();
+        for b in breakpoints {
+            match parse_location(&b, &file) {
+                Some(b) => v.push(b),
+                None => eprintln!("invalid breakpoint {}, it should be LINE or FILE:LINE", b),
+            }
+        }
+        Debugger {
+            file,
+            inner: Mutex::new(Inner {
+                breakpoints: v,
+                stepper: Stepper::new(Mode::StepInto),
+            }),
+            evaluating: AtomicBool::new(false),
+            detached: AtomicBool::new(false),
+        }
+    }
+
+    fn ignoring(&self) -> bool {
+        self.evaluating.load(Ordering::SeqCst) || self.detached.load(Ordering::SeqCst)
+    }
+
+    fn current_file(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        inner.stepper.frames.last().and_then(|f| f.location.as_ref().map(|(source, _)| source.name.clone())).unwrap_or_else(|| self.file.clone())
+    }
+
+    // shows where the code stopped and asks for commands until one of them runs the code
+    async fn pause(&self, step: &Step<'_>, reason: &str) {
+        if let Some((source, span)) = step.location() {
+            println!("stopped at {} ({})", source.location(span), reason);
+            println!("{}", listing(&source, span.line, 0));
+        } else {
+            println!("stopped ({})", reason);
+        }
+        let mut last = String::new();
+        loop {
+            print!("(bodu debug) ");
+            std::io::stdout().flush().unwrap();
+            let mut line = String::new();
+            let read = tokio::task::block_in_place(|| std::io::stdin().lock().read_line(&mut line));
+            if matches!(read, Ok(0) | Err(_)) {
+                println!();
+                self.detached.store(true, Ordering::SeqCst);
+                return;
+            }
+            let mut line = line.trim().to_string();
+            if line.is_empty() {
+                line = last.clone();
+            }
+            last = line.clone();
+            let (cmd, arg) = match line.split_once(char::is_whitespace) {
+                Some((cmd, arg)) => (cmd, arg.trim()),
+                None => (line.as_str(), ""),
+            };
+            match cmd {
+                "" => {},
+                "c" | "continue" => return self.resume(|_| Mode::Continue),
+                "s" | "step" => return self.resume(|_| Mode::StepInto),
+                "n" | "next" => return self.resume(Mode::StepOver),
+                "o" | "out" => return self.resume(Mode::StepOut),
+                "b" | "break" => self.set_breakpoint(arg),
+                "d" | "delete" => self.delete_breakpoint(arg),
+                "bt" | "backtrace" => self.backtrace(),
+                "l" | "list" => match step.location() {
+                    Some((source, span)) => println!("{}", listing(&source, span.line, 5)),
+                    None => println!("there's no source for this code"),
+                },
+                "scope" => show_scopes(step.state.clone(), arg == "all").await,
+                "args" => {
+                    if step.args.is_empty() {
+                        println!("no arguments");
+                    }
+                    for (i, v) in step.args.iter().enumerate() {
+                        println!("  {} = {}", i, show(step.state.clone(), v.clone()).await);
+                    }
+                },
+                "temps" => {
+                    let mut tmps = step.tmps.iter().collect::<Vec<_>>();
+                    tmps.sort_by_key(|(k, _)| **k);
+                    if tmps.is_empty() {
+                        println!("no temporaries");
+                    }
+                    for (k, v) in tmps {
+                        println!("  %{} = {}", k, show(step.state.clone(), v.clone()).await);
+                    }
+                },
+                "p" | "print" => {
+                    if arg.is_empty() {
+                        println!("p requires an expression");
+                    } else {
+                        println!("{}", self.evaluate(step.state.clone(), arg).await);
+                    }
+                },
+                "q" | "quit" => std::process::exit(0),
+                "h" | "help" => println!("{}", HELP),
+                _ => println!("unknown command {}, h shows the commands", cmd),
+            }
+        }
+    }
+
+    fn resume(&self, mode: impl FnOnce(usize) -> Mode) {
+        self.inner.lock().unwrap().stepper.resume(mode);
+    }
+
+    fn set_breakpoint(&self, arg: &str) {
+        if arg.is_empty() {
+            let inner = self.inner.lock().unwrap();
+            if inner.breakpoints.is_empty() {
+                println!("no breakpoints");
+            }
+            for (file, line) in inner.breakpoints.iter() {
+                println!("  {}:{}", file, line);
+            }
+            return;
+        }
+        match parse_location(arg, &self.current_file()) {
+            Some(b) => {
+                println!("breakpoint at {}:{}", b.0, b.1);
+                let inner = &mut *self.inner.lock().unwrap();
+                if !inner.breakpoints.contains(&b) {
+                    inner.breakpoints.push(b);
+                }
+            },
+            None => println!("invalid breakpoint {}, it should be LINE or FILE:LINE", arg),
+        }
+    }
+
+    fn delete_breakpoint(&self, arg: &str) {
+        if arg.is_empty() {
+            self.inner.lock().unwrap().breakpoints.clear();
+            println!("all breakpoints removed");
+            return;
+        }
+        match parse_location(arg, &self.current_file()) {
+            Some(b) => {
+                let inner = &mut *self.inner.lock().unwrap();
+                let len = inner.breakpoints.len();
+                inner.breakpoints.retain(|v| *v != b);
+                if inner.breakpoints.len() == len {
+                    println!("there's no breakpoint at {}:{}", b.0, b.1);
+                }
+            },
+            None => println!("invalid breakpoint {}, it should be LINE or FILE:LINE", arg),
+        }
+    }
+
+    fn backtrace(&self) {
+        let inner = self.inner.lock().unwrap();
+        for (i, frame) in inner.stepper.frames.iter().rev().enumerate() {
+            match &frame.location {
+                Some((source, span)) => println!("  #{} {} at {}", i, frame.name, source.location(*span)),
+                None => println!("  #{} {}", i, frame.name),
+            }
+        }
+    }
+
+    async fn evaluate(&self, state: StateContainer, code: &str) -> String {
+        self.evaluating.store(true, Ordering::SeqCst);
+        let r = evaluate(state.clone(), code).await;
+        self.evaluating.store(false, Ordering::SeqCst);
+        match r {
+            Ok(v) => show(state.clone(), v).await,
+            Err(e) => format!("error: {}", show(state.clone(), e).await),
+        }
+    }
+}
+
+impl VmHook for Debugger {
+    fn step<'a>(&'a self, step: Step<'a>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            let reason = {
+                let inner = &mut *self.inner.lock().unwrap();
+                let breakpoints = &inner.breakpoints;
+                inner.stepper.step(&step, |source, line| breakpoints.iter().any(|(file, l)| *l == line && same_file(&source.name, file)))
+            };
+            if let Some(reason) = reason {
+                self.pause(&step, reason).await;
+            }
+            Ok(())
+        })
+    }
+
+    fn enter<'a>(&'a self, state: StateContainer, dbg: Option<Arc<DebugInfo>>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            self.inner.lock().unwrap().stepper.enter(state, dbg);
+            Ok(())
+        })
+    }
+
+    fn leave<'a>(&'a self, _result: &'a Result<Container, Container>) -> HookFuture<'a> {
+        Box::pin(async move {
+            if self.ignoring() {
+                return Ok(());
+            }
+            self.inner.lock().unwrap().stepper.leave();
+            Ok(())
+        })
+    }
+
+    fn throw<'a>(&'a self, step: Step<'a>, error: Container, caught: bool) -> HookFuture<'a> {
+        Box::pin(async move {
+            if caught || self.ignoring() {
+                return Ok(());
+            }
+            println!("uncaught error: {}", show(step.state.clone(), error).await);
+            self.pause(&step, "uncaught error").await;
+            Ok(())
+        })
+    }
+}
+
+// runs the code with load_here in a state, as an expression first and as statements if it doesn't parse
+pub async fn evaluate(state: StateContainer, code: &str) -> Result<Container, Container> {
+    let (load_here, errtrace) = {
+        let st = state.lock().await;
+        let gd = st.globaldata.as_ref().unwrap().lock().await;
+        (gd.register.get("load_here").cloned(), gd.errtrace.clone())
+    };
+    let load_here = match load_here {
+        Some(f) => f,
+        None => return Err(make_err(ErrorKind::Internal, "load_here isn't available")),
+    };
+    let r = async {
+        let expr = make_container(Value::String(format!("out ({});", code)));
+        let f = match call(state.clone(), load_here.clone(), vec![expr]).await {
+            Ok(f) => f,
+            Err(_) if code.ends_with(';') || code.ends_with('}') => call(state.clone(), load_here.clone(), vec![make_container(Value::String(code.to_string()))]).await?,
+            Err(_) => call(state.clone(), load_here.clone(), vec![make_container(Value::String(format!("{};", code)))]).await?,
+        };
+        call(state.clone(), f, vec![]).await
+    }.await;
+    // the error thrown by the code isn't the one the program is going through
+    let st = state.lock().await;
+    st.globaldata.as_ref().unwrap().lock().await.errtrace = errtrace;
+    r
+}
+
+// LINE or FILE:LINE, a LINE alone is in the file given
+fn parse_location(s: &str, file: &str) -> Option<(String, usize)> {
+    let (file, line) = match s.rsplit_once(':') {
+        Some((file, line)) => (file.to_string(), line),
+        None => (file.to_string(), s),
+    };
+    let line = line.trim().parse::<usize>().ok().filter(|line| *line > 0)?;
+    Some((file, line))
+}
+
+// a breakpoint in foo.bodu is in dir/foo.bodu too
+pub fn same_file(name: &str, file: &str) -> bool {
+    name == file || name.ends_with(&format!("/{}", file)) || file.ends_with(&format!("/{}", name))
+}
+
+// the lines around a line of the source, the line itself is marked
+fn listing(source: &Source, line: usize, around: usize) -> String {
+    let lines = source.contents.lines().collect::<Vec<_>>();
+    let start = line.saturating_sub(around).max(1);
+    let end = (line + around).min(lines.len());
+    let mut out = Vec::new();
+    for i in start..=end {
+        let mark = if i == line { ">" } else { " " };
+        out.push(format!("{} {:>4} | {}", mark, i, lines[i - 1]));
+    }
+    out.join("\n")
+}
+
+// the variables of every scope from the innermost one, the global scope is only shown with all
+async fn show_scopes(state: StateContainer, all: bool) {
+    let mut state = state;
+    let mut level = 0;
+    loop {
+        let (scope, parent) = {
+            let st = state.lock().await;
+            (st.scope.clone(), st.parent.clone())
+        };
+        let props = match &*scope.lock().await {
+            Value::Object(o) => o.props.clone(),
+            _ => Default::default(),
+        };
+        let mut props = props.into_iter().collect::<Vec<_>>();
+        props.sort_by(|a, b| a.0.cmp(&b.0));
+        match parent {
+            Some(parent) => {
+                println!("scope {}:", level);
+                if props.is_empty() {
+                    println!("  nothing");
+                }
+                for (name, prop) in props {
+                    match prop {
+                        ObjectProp::Value(v) => println!("  {} = {}", name, show(state.clone(), v).await),
+                        ObjectProp::GetSet(_, _) => println!("  {} = <getter>", name),
+                    }
+                }
+                state = parent;
+                level += 1;
+            },
+            None => {
+                if !all {
+                    println!("global: {} names, scope all shows them", props.len());
+                    return;
+                }
+                println!("global:");
+                for (name, prop) in props {
+                    match prop {
+                        ObjectProp::Value(v) => println!("  {} = {}", name, show(state.clone(), v).await),
+                        ObjectProp::GetSet(_, _) => println!("  {} = <getter>", name),
+                    }
+                }
+                return;
+            },
+        }
+    }
+}
+
+// strings are quoted so they aren't mistaken for other values
+pub async fn show(state: StateContainer, v: Container) -> String {
+    let v = match resolve_bind(state.clone(), v).await {
+        Ok(v) => v,
+        Err(_) => return "<bind>".to_string(),
+    };
+    match &*v.lock().await {
+        Value::String(s) => return format!("{:?}", s),
+        Value::Function(_) => return "<function>".to_string(),
+        _ => {},
+    }
+    match to_string_base(state.clone(), v).await {
+        Ok(s) => s,
+        Err(_) => "<object>".to_string(),
+    }
+}
@@ -0,0 +1,640 @@
+use std::{collections::HashMap, io::{BufRead, Write}};
+
+use bodu_script::s1::{s1_trivia, INVALID_IDENT_CHARS, KEYWORDS, S1T};
+use bodu_std::{init_global_state, new_global_state};
+use bodu_vm::{ObjectProp, Source, Span, Value};
+use serde_json::{json, Value as Json};
+
+// the hover text of the globals comes from here
+const STDLIB: &str = include_str!("../STDLIB.md");
+
+// lsp symbol and completion kinds
+const SYMBOL_FUNCTION: u8 = 12;
+const SYMBOL_KEY: u8 = 20;
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_MODULE: u8 = 9;
+const COMPLETION_KEYWORD: u8 = 14;
+
+struct Server {
+    docs: HashMap<String, String>, // the open documents by uri
+    globals: Vec<(String, Vec<String>)>, // the names init_global_state makes and, for objects, their properties
+    stdlib: HashMap<String, String>,
+    shut_down: bool,
+}
+
+// talks the language server protocol over stdin and stdout
+pub async fn lsp() {
+    let mut server = Server {
+        docs: HashMap::new(),
+        globals: globals().await,
+        stdlib: stdlib_docs(),
+        shut_down: false,
+    };
+    let mut input = std::io::stdin().lock();
+    while let Some(message) = read_message(&mut input) {
+        // responses to requests the server didn't make and messages that aren't JSON are ignored
+        let method = match message.get("method").and_then(|m| m.as_str()) {
+            Some(method) => method,
+            None => continue,
+        };
+        let result = server.handle(method, &message["params"]);
+        if let Some(id) = message.get("id") {
+            send(match result {
+                Some(result) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": result,
+                }),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": -32601,
+                        "message": format!("{} isn't supported", method),
+                    },
+                }),
+            });
+        }
+    }
+}
+
+// a message is a Content-Length header, an empty line and the JSON. None when stdin is closed
+pub fn read_message(input: &mut impl BufRead) -> Option<Json> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') && name.eq_ignore_ascii_case("content-length") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    Some(serde_json::from_slice(&body).unwrap_or(Json::Null))
+}
+
+fn send(message: Json) {
+    let body = message.to_string();
+    let mut out = std::io::stdout().lock();
+    let _ = write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = out.flush();
+}
+
+async fn globals() -> Vec<(String, Vec<String>)> {
+    let state = new_global_state(false, std::env::current_dir().unwrap()).await;
+    init_global_state(state.clone(), Vec::new()).await;
+    let scope = state.lock().await.scope.clone();
+    let props = match &*scope.lock().await {
+        Value::Object(obj) => obj.props.clone(),
+        _ => HashMap::new(),
+    };
+    let mut globals = Vec::new();
+    for (name, prop) in props {
+        let mut members = match prop {
+            ObjectProp::Value(v) => match &*v.lock().await {
+                Value::Object(obj) => obj.props.keys().cloned().collect(),
+                _ => Vec::new(),
+            },
+            ObjectProp::GetSet(_, _) => Vec::new(),
+        };
+        members.sort();
+        globals.push((name, members));
+    }
+    globals.sort();
+    globals
+}
+
+// the text under each heading of STDLIB.md, by the name in the heading: `print(...)` is print and `math.abs(x)` is math.abs
+fn stdlib_docs() -> HashMap<String, String> {
+    let mut docs: HashMap<String, String> = HashMap::new();
+    let mut current = None;
+    for line in STDLIB.lines() {
+        if let Some(heading) = line.strip_prefix("## `").or(line.strip_prefix("# `")) {
+            let signature = heading.trim_end_matches('`');
+            let name = signature.split('(').next().unwrap().to_string();
+            let doc = docs.entry(name.clone()).or_default();
+            if !doc.is_empty() {
+                doc.push('\n');
+            }
+            doc.push_str(&format!("```bodu\n{}\n```\n", signature));
+            current = Some(name);
+        } else if line.starts_with('#') {
+            current = None;
+        } else if let Some(name) = &current {
+            let doc = docs.get_mut(name).unwrap();
+            doc.push_str(line);
+            doc.push('\n');
+        }
+    }
+    for doc in docs.values_mut() {
+        *doc = doc.trim().to_string();
+    }
+    docs
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum DefKind {
+    Function,
+    Variable,
+    Parameter,
+    Label,
+}
+
+// a name declared in the document
+struct Def {
+    name: String,
+    kind: DefKind,
+    start: Span, // where the declaration starts
+    span: Span, // the name
+    token: usize, // the token of the name
+    block: usize, // the block it can be used in
+    body: Option<usize>, // the block of a function
+    end: Option<Span>, // the } of a function
+    detail: String,
+    doc: Option<String>,
+}
+
+// the declarations and blocks of a document, found from the s1 tokens so it works while the code doesn't parse
+struct Analysis {
+    tokens: Vec<(S1T, Span)>,
+    parents: Vec<Option<usize>>, // the block each block is in, block 0 is the whole document
+    token_blocks: Vec<usize>, // the block each token is in, a { is in the block it opens
+    defs: Vec<Def>,
+}
+
+impl Analysis {
+    fn new(text: &str) -> Analysis {
+        let (tokens, comments, _) = s1_trivia(text.to_string());
+        let mut a = Analysis {
+            tokens,
+            parents: vec![None],
+            token_blocks: Vec::new(),
+            defs: Vec::new(),
+        };
+        let mut stack = vec![0];
+        let mut pending: Vec<usize> = Vec::new(); // parameters and loop and catch variables, they belong to the next block
+        let mut bodies: Vec<usize> = Vec::new(); // functions waiting for their block
+        for i in 0..a.tokens.len() {
+            let block = *stack.last().unwrap();
+            match a.tokens[i].0 {
+                S1T::OpenBrace => {
+                    let b = a.parents.len();
+                    a.parents.push(Some(block));
+                    stack.push(b);
+                    for d in pending.drain(..) {
+                        a.defs[d].block = b;
+                    }
+                    for f in bodies.drain(..) {
+                        a.defs[f].body = Some(b);
+                    }
+                },
+                S1T::CloseBrace if stack.len() > 1 => {
+                    let b = stack.pop().unwrap();
+                    let span = a.tokens[i].1;
+                    if let Some(f) = a.defs.iter_mut().find(|d| d.body == Some(b)) {
+                        f.end = Some(span);
+                    }
+                },
+                _ => {},
+            }
+            a.token_blocks.push(*stack.last().unwrap());
+            let start = a.tokens[i].1;
+            let doc = || doc_comment(&a.tokens, &comments, start);
+            match &a.tokens[i].0 {
+                S1T::KeywordFn => {
+                    let name = a.ident(i + 1);
+                    let mut j = i + 1 + name.is_some() as usize;
+                    let mut params = Vec::new();
+                    if a.tokens.get(j).is_some_and(|t| t.0 == S1T::OpenParen) {
+                        j += 1;
+                        while let Some((t, _)) = a.tokens.get(j) && *t != S1T::CloseParen {
+                            if let Some(param) = a.ident(j) {
+                                params.push((param, j));
+                            }
+                            j += 1;
+                        }
+                    }
+                    let names = params.iter().map(|p| p.0.clone()).collect::<Vec<_>>().join(", ");
+                    let detail = format!("fn {}({})", name.clone().unwrap_or_default(), names);
+                    if let Some(name) = name {
+                        let doc = doc();
+                        bodies.push(a.defs.len());
+                        a.def(name, DefKind::Function, start, i + 1, block, detail.clone(), doc);
+                    }
+                    for (param, j) in params {
+                        pending.push(a.defs.len());
+                        a.def(param, DefKind::Parameter, start, j, block, detail.clone(), None);
+                    }
+                },
+                S1T::KeywordLet | S1T::KeywordBind => {
+                    let keyword = if a.tokens[i].0 == S1T::KeywordLet {
+                        "let"
+                    } else {
+                        "bind"
+                    };
+                    let doc = doc();
+                    if let Some(name) = a.ident(i + 1) {
+                        a.def(name.clone(), DefKind::Variable, start, i + 1, block, format!("{} {}", keyword, name), doc);
+                    } else if a.tokens.get(i + 1).is_some_and(|t| t.0 == S1T::OpenParen) {
+                        // let (a, b, c)
+                        let mut j = i + 2;
+                        while let Some((t, _)) = a.tokens.get(j) && *t != S1T::CloseParen {
+                            if let Some(name) = a.ident(j) {
+                                a.def(name.clone(), DefKind::Variable, start, j, block, format!("{} {}", keyword, name), doc.clone());
+                            }
+                            j += 1;
+                        }
+                    }
+                },
+                S1T::KeywordFor | S1T::KeywordCatch => {
+                    let keyword = if a.tokens[i].0 == S1T::KeywordFor {
+                        "for"
+                    } else {
+                        "catch"
+                    };
+                    if let Some(name) = a.ident(i + 1) {
+                        pending.push(a.defs.len());
+                        a.def(name.clone(), DefKind::Variable, start, i + 1, block, format!("{} {}", keyword, name), None);
+                    }
+                },
+                S1T::Label(name) if i == 0 || a.tokens[i - 1].0 != S1T::KeywordGoto => {
+                    let doc = doc();
+                    a.def(name.clone(), DefKind::Label, start, i, block, format!("#{}", name), doc);
+                },
+                _ => {},
+            }
+        }
+        a
+    }
+
+    fn ident(&self, i: usize) -> Option<String> {
+        match self.tokens.get(i) {
+            Some((S1T::Identifier(name), _)) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn def(&mut self, name: String, kind: DefKind, start: Span, token: usize, block: usize, detail: String, doc: Option<String>) {
+        self.defs.push(Def {
+            name,
+            kind,
+            start,
+            span: self.tokens[token].1,
+            token,
+            block,
+            body: None,
+            end: None,
+            detail,
+            doc,
+        });
+    }
+
+    // if what's declared in block can be used in inner
+    fn visible(&self, block: usize, inner: usize) -> bool {
+        let mut b = Some(inner);
+        while let Some(i) = b {
+            if i == block {
+                return true;
+            }
+            b = self.parents[i];
+        }
+        false
+    }
+
+    // the identifier or label at a byte offset, the end of a token counts so it works with the cursor right after a name
+    fn token_at(&self, offset: usize) -> Option<usize> {
+        self.tokens.iter().position(|(t, span)| span.start <= offset && offset <= span.end && matches!(t, S1T::Identifier(_) | S1T::Label(_)))
+    }
+
+    // the declaration a name refers to: the last one before it that can be seen from where it is, or the first one after it
+    fn resolve(&self, i: usize) -> Option<&Def> {
+        let (name, label) = match &self.tokens[i].0 {
+            S1T::Identifier(name) => (name, false),
+            S1T::Label(name) => (name, true),
+            _ => return None,
+        };
+        if i > 0 && self.tokens[i - 1].0 == S1T::Dot {
+            return None; // a property
+        }
+        let block = self.token_blocks[i];
+        let mut defs = self.defs.iter().filter(|d| d.name == *name && (d.kind == DefKind::Label) == label && self.visible(d.block, block));
+        let before = defs.clone().filter(|d| d.token <= i).max_by_key(|d| d.token);
+        before.or_else(|| defs.next())
+    }
+
+    // the function whose block the declaration is in, if there's one
+    fn parent(&self, d: &Def) -> Option<usize> {
+        let mut b = Some(d.block);
+        while let Some(i) = b {
+            if let Some(f) = self.defs.iter().position(|f| f.body == Some(i) && f.token != d.token) {
+                return Some(f);
+            }
+            b = self.parents[i];
+        }
+        None
+    }
+
+    fn symbols(&self, text: &str, parent: Option<usize>) -> Vec<Json> {
+        self.defs.iter().enumerate().filter(|(_, d)| matches!(d.kind, DefKind::Function | DefKind::Label) && self.parent(d) == parent).map(|(i, d)| {
+            let (kind, range) = match d.kind {
+                DefKind::Function => (SYMBOL_FUNCTION, d.start.to(d.end.unwrap_or(d.span))),
+                _ => (SYMBOL_KEY, d.span),
+            };
+            json!({
+                "name": d.name,
+                "detail": d.detail,
+                "kind": kind,
+                "range": range_of(text, range),
+                "selectionRange": range_of(text, d.span),
+                "children": self.symbols(text, Some(i)),
+            })
+        }).collect()
+    }
+}
+
+// the comments on the lines right before a declaration, without the comment markers
+fn doc_comment(tokens: &[(S1T, Span)], comments: &[(String, Span)], start: Span) -> Option<String> {
+    let mut line = start.line;
+    let mut lines = Vec::new();
+    for (text, span) in comments.iter().rev() {
+        let end = span.line + text.matches('\n').count();
+        if span.start >= start.start {
+            continue;
+        }
+        // a comment after code on the same line is about that code
+        if end + 1 != line || tokens.iter().any(|(_, t)| t.line == span.line && t.start < span.start) {
+            break;
+        }
+        let text = match text.strip_prefix("/*") {
+            Some(text) => text.trim_end_matches("*/").lines().map(|l| l.trim().trim_start_matches('*').trim()).collect::<Vec<_>>().join("\n"),
+            None => text.trim_start_matches('/').trim().to_string(),
+        };
+        lines.push(text.trim().to_string());
+        line = span.line;
+    }
+    lines.reverse();
+    let doc = lines.join("\n");
+    if doc.is_empty() {
+        None
+    } else {
+        Some(doc)
+    }
+}
+
+// lsp positions are lines and UTF-16 code units from 0
+fn position(text: &str, offset: usize) -> Json {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+fn range_of(text: &str, span: Span) -> Json {
+    json!({
+        "start": position(text, span.start),
+        "end": position(text, span.end),
+    })
+}
+
+fn offset(text: &str, position: &Json) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let mut start = 0;
+    for _ in 0..line {
+        match text[start..].find('\n') {
+            Some(i) => start += i + 1,
+            None => return text.len(),
+        }
+    }
+    let mut units = 0;
+    for (i, ch) in text[start..].char_indices() {
+        if units >= character || ch == '\n' {
+            return start + i;
+        }
+        units += ch.len_utf16();
+    }
+    text.len()
+}
+
+impl Server {
+    // the result of a request, None if the method isn't supported. notifications return None too, it isn't sent
+    fn handle(&mut self, method: &str, params: &Json) -> Option<Json> {
+        match method {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": 1, // the whole document is sent on every change
+                    "documentSymbolProvider": true,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": {
+                        "triggerCharacters": ["."],
+                    },
+                },
+                "serverInfo": {
+                    "name": "bodu",
+                },
+            })),
+            "shutdown" => {
+                self.shut_down = true;
+                Some(Json::Null)
+            },
+            "exit" => std::process::exit(if self.shut_down {
+                0
+            } else {
+                1
+            }),
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default().to_string();
+                self.update(uri, text);
+                None
+            },
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+                if let Some(text) = params["contentChanges"].as_array().and_then(|c| c.last()).and_then(|c| c["text"].as_str()) {
+                    self.update(uri, text.to_string());
+                }
+                None
+            },
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.docs.remove(uri);
+                publish(uri, Vec::new());
+                None
+            },
+            "textDocument/documentSymbol" => {
+                let text = self.doc(params);
+                Some(Json::Array(Analysis::new(&text).symbols(&text, None)))
+            },
+            "textDocument/definition" => {
+                let text = self.doc(params);
+                let a = Analysis::new(&text);
+                let def = a.token_at(offset(&text, &params["position"])).and_then(|i| a.resolve(i));
+                Some(match def {
+                    Some(d) => json!({
+                        "uri": params["textDocument"]["uri"],
+                        "range": range_of(&text, d.span),
+                    }),
+                    None => Json::Null,
+                })
+            },
+            "textDocument/hover" => {
+                let text = self.doc(params);
+                Some(self.hover(&text, offset(&text, &params["position"])))
+            },
+            "textDocument/completion" => {
+                let text = self.doc(params);
+                Some(self.completion(&text, offset(&text, &params["position"])))
+            },
+            _ => None,
+        }
+    }
+
+    fn doc(&self, params: &Json) -> String {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        self.docs.get(uri).cloned().unwrap_or_default()
+    }
+
+    // keeps the new text and sends the errors of every parser stage
+    fn update(&mut self, uri: String, text: String) {
+        let errors = bodu_script::check(Source::new(uri.clone(), text.clone()));
+        let diagnostics = errors.iter().map(|e| json!({
+            "range": range_of(&text, e.span.unwrap_or(Span {
+                start: 0,
+                end: 0,
+                line: 1,
+                col: 1,
+            })),
+            "severity": 1,
+            "source": "bodu",
+            "message": e.message,
+        })).collect();
+        publish(&uri, diagnostics);
+        self.docs.insert(uri, text);
+    }
+
+    fn hover(&self, text: &str, offset: usize) -> Json {
+        let a = Analysis::new(text);
+        let i = match a.token_at(offset) {
+            Some(i) => i,
+            None => return Json::Null,
+        };
+        let value = match a.resolve(i) {
+            Some(d) => {
+                let mut value = format!("```bodu\n{}\n```", d.detail);
+                if d.kind == DefKind::Parameter {
+                    value.push_str(&format!("\n\n`{}` is a parameter", d.name));
+                }
+                if let Some(doc) = &d.doc {
+                    value.push_str("\n\n");
+                    value.push_str(doc);
+                }
+                value
+            },
+            None => {
+                // a global, or a property of one like math.abs
+                let name = match (&a.tokens[i].0, i.checked_sub(2).map(|j| (&a.tokens[j].0, &a.tokens[j + 1].0))) {
+                    (S1T::Identifier(name), Some((S1T::Identifier(obj), S1T::Dot))) => format!("{}.{}", obj, name),
+                    (S1T::Identifier(name), _) => name.clone(),
+                    _ => return Json::Null,
+                };
+                match self.stdlib.get(&name) {
+                    Some(doc) => doc.clone(),
+                    None => return Json::Null,
+                }
+            },
+        };
+        json!({
+            "contents": {
+                "kind": "markdown",
+                "value": value,
+            },
+            "range": range_of(text, a.tokens[i].1),
+        })
+    }
+
+    // properties after a global and a dot, otherwise the globals, the keywords and the names declared where the cursor is
+    fn completion(&self, text: &str, offset: usize) -> Json {
+        let word_start = |end: usize| text[..end].char_indices().rev().find(|(_, ch)| ch.is_whitespace() || INVALID_IDENT_CHARS.contains(*ch)).map(|(i, ch)| i + ch.len_utf8()).unwrap_or(0);
+        let start = word_start(offset);
+        let mut items = Vec::new();
+        if text[..start].ends_with('.') {
+            let obj = &text[word_start(start - 1)..start - 1];
+            if let Some((_, members)) = self.globals.iter().find(|(name, _)| name == obj) {
+                for member in members {
+                    items.push(self.item(member, COMPLETION_FUNCTION, self.stdlib.get(&format!("{}.{}", obj, member))));
+                }
+            }
+            return Json::Array(items);
+        }
+        for (name, members) in self.globals.iter() {
+            let kind = if members.is_empty() {
+                COMPLETION_FUNCTION
+            } else {
+                COMPLETION_MODULE
+            };
+            items.push(self.item(name, kind, self.stdlib.get(name)));
+        }
+        for keyword in KEYWORDS {
+            items.push(self.item(keyword, COMPLETION_KEYWORD, None));
+        }
+        let a = Analysis::new(text);
+        // the block of the last token before the cursor
+        let block = a.tokens.iter().rposition(|(_, span)| span.end <= offset).map(|i| a.token_blocks[i]).unwrap_or(0);
+        let mut seen = Vec::new();
+        for d in a.defs.iter().rev() {
+            if d.kind == DefKind::Label || !a.visible(d.block, block) || seen.contains(&&d.name) {
+                continue;
+            }
+            seen.push(&d.name);
+            let kind = if d.kind == DefKind::Function {
+                COMPLETION_FUNCTION
+            } else {
+                COMPLETION_VARIABLE
+            };
+            let mut item = self.item(&d.name, kind, d.doc.as_ref());
+            item["detail"] = json!(d.detail);
+            items.push(item);
+        }
+        Json::Array(items)
+    }
+
+    fn item(&self, label: &str, kind: u8, doc: Option<&String>) -> Json {
+        let mut item = json!({
+            "label": label,
+            "kind": kind,
+        });
+        if let Some(doc) = doc {
+            item["documentation"] = json!({
+                "kind": "markdown",
+                "value": doc,
+            });
+        }
+        item
+    }
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) {
+    send(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": {
+            "uri": uri,
+            "diagnostics": diagnostics,
+        },
+    }));
+}
@@ -1,11 +1,22 @@
-use std::path::PathBuf;
+use std::{io::{Read, StdoutLock, Write}, path::PathBuf, sync::Arc, time::Duration};
 
-use bodu_compiler::compile_instrs;
+use bodu_compiler::{asm::assemble, compile_instrs, decompile, disasm::disassemble, is_bytecode};
 use clap::{Arg, ArgAction, Command};
-use rustyline::DefaultEditor;
-use bodu_script::{s1::s1, s2::s2, s3::s3, s4::s4};
+use bodu_script::{s1::s1, s2::s2, s3::s3, s4::{s4, s4_eval}, ParseError};
 use bodu_std::{init_global_state, new_global_state};
-use bodu_vm::{op::{call, make_function, new_state, to_string_base}, StateContainer};
+use bodu_vm::{op::{call, is_exit, make_function, to_string_base}, Container, DebugInfo, Instruction, Limits, Source, Span, StateContainer, Value, VmHook};
+use serde::Serialize;
+
+mod dap;
+mod coverage;
+mod debugger;
+mod lsp;
+mod profiler;
+mod repl;
+mod testing;
+
+// the default --max-depth
+const MAX_DEPTH: usize = 1000;
 
 #[tokio::main]
 async fn main() {
@@ -22,8 +33,56 @@ async fn main() {
             Command::new("run")
                 .arg(
                     Arg::new("file")
-                        .required(true)
-                ).about("run a bodu file")
+                        .required_unless_present("eval")
+                        .help("the file to run, - reads the script from stdin")
+                )
+                .arg(
+                    Arg::new("eval")
+                        .short('e')
+                        .long("eval")
+                        .conflicts_with("file")
+                        .help("run this code instead of a file and print its result")
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value("bodu.folded")
+                        .value_name("FILE")
+                        .help("profile the code: a table of the functions and lines goes to stderr and the collapsed stacks to FILE, bodu.folded by default")
+                )
+                .arg(
+                    Arg::new("coverage")
+                        .long("coverage")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value("lcov.info")
+                        .value_name("FILE")
+                        .conflicts_with("profile")
+                        .help("record which lines ran: a summary of every file goes to stderr and an lcov file to FILE, lcov.info by default")
+                )
+                .arg(
+                    Arg::new("max-steps")
+                        .long("max-steps")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("throw a limit error when the code ran N instructions")
+                )
+                .arg(
+                    Arg::new("max-depth")
+                        .long("max-depth")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .help(format!("throw a limit error when N function calls are inside each other, 0 turns it off [default: {}]", MAX_DEPTH))
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .value_parser(parse_seconds)
+                        .help("throw a limit error when the code ran for SECONDS, which can have a fraction")
+                ).about("run a bodu file or a compiled bytecode file")
                 .visible_alias("r")
                 .arg(
                     Arg::new("bodu_args")
@@ -32,6 +91,42 @@ async fn main() {
                     .action(ArgAction::Append)
                     .help("arguments passed to the bodu script")
                 )
+        ).subcommand(
+            Command::new("debug")
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                        .help("the bodu file to debug")
+                )
+                .arg(
+                    Arg::new("break")
+                        .short('b')
+                        .long("break")
+                        .action(ArgAction::Append)
+                        .help("set a breakpoint at LINE or FILE:LINE before the file runs")
+                )
+                .arg(
+                    Arg::new("bodu_args")
+                    .trailing_var_arg(true)
+                    .allow_hyphen_values(true)
+                    .action(ArgAction::Append)
+                    .help("arguments passed to the bodu script")
+                )
+                .about("run a bodu file in the debugger, it stops at the first line")
+        ).subcommand(
+            Command::new("eval")
+                .arg(
+                    Arg::new("code")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("bodu_args")
+                    .trailing_var_arg(true)
+                    .allow_hyphen_values(true)
+                    .action(ArgAction::Append)
+                    .help("arguments passed to the bodu code")
+                )
+                .about("run a snippet of bodu code and print its result")
         ).subcommand(
             Command::new("compile")
                 .arg(
@@ -42,8 +137,138 @@ async fn main() {
                     Arg::new("output")
                         .required(true)
                 )
+                .arg(
+                    Arg::new("debug-info")
+                        .short('g')
+                        .long("debug-info")
+                        .action(ArgAction::SetTrue)
+                        .help("keep the source and the locations of the code, so errors and the debugger can point at lines. without it compiled code has no locations")
+                )
                 .about("compile a bodu file to a bytecode file")
                 .visible_alias("c")
+        ).subcommand(
+            Command::new("check")
+                .arg(
+                    Arg::new("paths")
+                        .required(true)
+                        .action(ArgAction::Append)
+                        .help("bodu files, or directories to search for .bodu files")
+                )
+                .about("report every syntax error in bodu files without running them")
+        ).subcommand(
+            Command::new("fmt")
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .action(ArgAction::SetTrue)
+                        .help("don't change the files, fail if any of them isn't formatted")
+                )
+                .arg(
+                    Arg::new("paths")
+                        .required(true)
+                        .action(ArgAction::Append)
+                        .help("bodu files, or directories to search for .bodu files. - formats stdin to stdout")
+                )
+                .about("format bodu files in place")
+        ).subcommand(
+            Command::new("lint")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("print one problem per line or a JSON array")
+                )
+                .arg(
+                    Arg::new("config")
+                        .long("config")
+                        .help("a JSON file like {\"disabled\": [\"unused-variable\"]} with the rules to turn off")
+                )
+                .arg(
+                    Arg::new("allow")
+                        .short('A')
+                        .long("allow")
+                        .action(ArgAction::Append)
+                        .value_parser(bodu_script::lint::RULES.to_vec())
+                        .help("turn off a rule")
+                )
+                .arg(
+                    Arg::new("paths")
+                        .required(true)
+                        .action(ArgAction::Append)
+                        .help("bodu files, or directories to search for .bodu files")
+                )
+                .about("report likely mistakes in bodu files without running them")
+        ).subcommand(
+            Command::new("test")
+                .arg(
+                    Arg::new("filter")
+                        .short('f')
+                        .long("filter")
+                        .help("only run the tests with this in their name")
+                )
+                .arg(
+                    Arg::new("coverage")
+                        .long("coverage")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value("lcov.info")
+                        .value_name("FILE")
+                        .help("record which lines the tests ran: a summary of every file goes to stderr and an lcov file to FILE, lcov.info by default")
+                )
+                .arg(
+                    Arg::new("paths")
+                        .action(ArgAction::Append)
+                        .default_value(".")
+                        .help("test files, or directories to search for *_test.bodu files")
+                )
+                .about("run the tests in *_test.bodu files")
+        ).subcommand(
+            Command::new("dump")
+                .arg(
+                    Arg::new("stage")
+                        .long("stage")
+                        .required(true)
+                        .value_parser(["tokens", "s2", "ast", "instrs"])
+                        .help("the parser stage to print: tokens (S1), s2, ast (S3) or instrs (S4)")
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("print pretty text or JSON")
+                )
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                        .help("the bodu file, - reads it from stdin")
+                )
+                .about("print the output of a parser stage for a bodu file")
+        ).subcommand(
+            Command::new("disasm")
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                )
+                .about("print the instructions of a bodu file or a compiled bytecode file")
+        ).subcommand(
+            Command::new("asm")
+                .arg(
+                    Arg::new("input")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("output")
+                        .required(true)
+                )
+                .about("assemble a listing in the format printed by disasm into a bytecode file")
+        ).subcommand(
+            Command::new("lsp")
+            .about("start the bodu language server, it talks over stdin and stdout")
+        ).subcommand(
+            Command::new("dap")
+            .about("start the bodu debug adapter, it talks the Debug Adapter Protocol over stdin and stdout")
         ).subcommand(
             Command::new("repl")
             .about("start the bodu repl")
@@ -51,55 +276,223 @@ async fn main() {
             Command::new("version")
                 .visible_alias("--version")
                 .about("print version and exit")
-        ).subcommand_required(true);
+        ).subcommand_required(true)
+        .allow_external_subcommands(true)
+        .override_usage("bodu [OPTIONS] <COMMAND>\n       bodu [OPTIONS] <file> [bodu_args]...");
     let matches = cmd.clone().get_matches();
-    if let Some(_) = matches.subcommand_matches("version") {
+    if matches.subcommand_matches("version").is_some() {
         println!("Bodu 0.1.0");
+    } else if matches.subcommand_matches("lsp").is_some() {
+        lsp::lsp().await;
+    } else if let Some(matches) = matches.subcommand_matches("dap") {
+        dap::dap(matches.get_flag("debug")).await;
     } else if let Some(matches) = matches.subcommand_matches("repl") {
-        repl(matches.get_flag("debug")).await;
+        repl::repl(matches.get_flag("debug")).await;
     } else if let Some(matches) = matches.subcommand_matches("run") {
+        let args = if let Some(args) = matches.get_many::<String>("bodu_args") {
+            args.cloned().collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        let mut hook = matches.get_one::<String>("profile").map(|output| Arc::new(profiler::Profiler::new(PathBuf::from(output))) as Arc<dyn VmHook>);
+        if let Some(output) = matches.get_one::<String>("coverage") {
+            hook = Some(Arc::new(coverage::Coverage::new(PathBuf::from(output))));
+        }
+        let max_depth = match matches.get_one::<usize>("max-depth") {
+            Some(0) => None,
+            Some(n) => Some(*n),
+            None => Some(MAX_DEPTH),
+        };
+        let limits = Arc::new(Limits::new(matches.get_one::<u64>("max-steps").copied(), max_depth, matches.get_one::<Duration>("timeout").copied()));
+        if let Some(code) = matches.get_one::<String>("eval") {
+            eval(code.clone(), matches.get_flag("debug"), args, hook, limits).await;
+        } else {
+            let file = matches.get_one::<String>("file").unwrap();
+            interpret(file.clone(), matches.get_flag("debug"), args, hook, limits).await;
+        }
+    } else if let Some(matches) = matches.subcommand_matches("debug") {
         let file = matches.get_one::<String>("file").unwrap();
+        let breakpoints = matches.get_many::<String>("break").map(|v| v.cloned().collect::<Vec<_>>()).unwrap_or_default();
         let args = if let Some(args) = matches.get_many::<String>("bodu_args") {
-            args.map(|s| s.clone()).collect::<Vec<_>>()
+            args.cloned().collect::<Vec<_>>()
         } else {
             Vec::new()
         };
-        interpret(file.clone(), matches.get_flag("debug"), args).await;
+        debug(file.clone(), breakpoints, matches.get_flag("debug"), args).await;
+    } else if let Some(matches) = matches.subcommand_matches("eval") {
+        let code = matches.get_one::<String>("code").unwrap();
+        let args = if let Some(args) = matches.get_many::<String>("bodu_args") {
+            args.cloned().collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        eval(code.clone(), matches.get_flag("debug"), args, None, default_limits()).await;
     } else if let Some(matches) = matches.subcommand_matches("compile") {
         let input = matches.get_one::<String>("input").unwrap();
         let output = matches.get_one::<String>("output").unwrap();
-        compile(input.clone(), output.clone()).await;
+        compile(input.clone(), output.clone(), matches.get_flag("debug"), matches.get_flag("debug-info")).await;
+    } else if let Some(matches) = matches.subcommand_matches("check") {
+        let paths = matches.get_many::<String>("paths").unwrap().cloned().collect::<Vec<_>>();
+        check(paths).await;
+    } else if let Some(matches) = matches.subcommand_matches("fmt") {
+        let paths = matches.get_many::<String>("paths").unwrap().cloned().collect::<Vec<_>>();
+        fmt(paths, matches.get_flag("check"));
+    } else if let Some(matches) = matches.subcommand_matches("lint") {
+        let paths = matches.get_many::<String>("paths").unwrap().cloned().collect::<Vec<_>>();
+        let mut disabled = matches.get_many::<String>("allow").map(|v| v.cloned().collect::<Vec<_>>()).unwrap_or_default();
+        if let Some(config) = matches.get_one::<String>("config") {
+            disabled.extend(lint_config(config));
+        }
+        let json = matches.get_one::<String>("format").unwrap() == "json";
+        lint(paths, disabled, json).await;
+    } else if let Some(matches) = matches.subcommand_matches("test") {
+        let paths = matches.get_many::<String>("paths").unwrap().cloned().collect::<Vec<_>>();
+        let filter = matches.get_one::<String>("filter").cloned();
+        let hook = matches.get_one::<String>("coverage").map(|output| Arc::new(coverage::Coverage::new(PathBuf::from(output))) as Arc<dyn VmHook>);
+        testing::test(paths, filter, matches.get_flag("debug"), hook).await;
+    } else if let Some(matches) = matches.subcommand_matches("dump") {
+        let stage = matches.get_one::<String>("stage").unwrap();
+        let json = matches.get_one::<String>("format").unwrap() == "json";
+        let file = matches.get_one::<String>("file").unwrap();
+        dump(file.clone(), stage, json);
+    } else if let Some(matches) = matches.subcommand_matches("disasm") {
+        let file = matches.get_one::<String>("file").unwrap();
+        disasm(file.clone()).await;
+    } else if let Some(matches) = matches.subcommand_matches("asm") {
+        let input = matches.get_one::<String>("input").unwrap();
+        let output = matches.get_one::<String>("output").unwrap();
+        asm(input.clone(), output.clone(), matches.get_flag("debug")).await;
+    } else if let Some((file, ext)) = matches.subcommand() {
+        // bodu script.bodu args... is the same as bodu run script.bodu args..., so scripts with a shebang can be run directly
+        let args = if let Some(args) = ext.get_many::<std::ffi::OsString>("") {
+            args.map(|a| a.to_string_lossy().to_string()).collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        // a typo in a command ends up here too
+        if !std::path::Path::new(file).exists() {
+            eprintln!("Error: {} is not a command or a file, bodu help lists the commands", file);
+            std::process::exit(1);
+        }
+        interpret(file.to_string(), matches.get_flag("debug"), args, None, default_limits()).await;
     } else {
         cmd.print_help().unwrap();
     }
 }
 
-static D: bool = false; // change this if you need to debug the parser
-
-async fn interpret(file: String, debug: bool, args: Vec<String>) {
-    let contents = std::fs::read_to_string(file.clone()).unwrap();
-    let contents = s1(contents).unwrap();
-    if D {
-        println!("S1: {:#?}", contents);
-    }
-    let contents = s2(contents).unwrap();
-    if D {
-        println!("S2: {:#?}", contents);
+async fn interpret(file: String, debug: bool, args: Vec<String>, hook: Option<Arc<dyn VmHook>>, limits: Arc<Limits>) {
+    if file == "-" {
+        let mut contents = Vec::new();
+        if let Err(e) = std::io::stdin().read_to_end(&mut contents) {
+            eprintln!("Error while reading the script from stdin: {}", e);
+            std::process::exit(1);
+        }
+        execute("<stdin>".to_string(), contents, std::env::current_dir().unwrap(), debug, args, false, hook, limits).await;
     }
-    let contents = s3(contents).unwrap();
-    if D {
-        println!("S3: {:#?}", contents);
+    let contents = match std::fs::read(file.clone()) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", file, e);
+            std::process::exit(1);
+        },
+    };
+    let path = match std::fs::canonicalize(PathBuf::from(file.clone())) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", file, e);
+            std::process::exit(1);
+        },
+    };
+    execute(file, contents, path.parent().unwrap().to_path_buf(), debug, args, false, hook, limits).await;
+}
+
+async fn debug(file: String, breakpoints: Vec<String>, debug: bool, args: Vec<String>) {
+    let contents = match std::fs::read(file.clone()) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", file, e);
+            std::process::exit(1);
+        },
+    };
+    let path = match std::fs::canonicalize(PathBuf::from(file.clone())) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", file, e);
+            std::process::exit(1);
+        },
+    };
+    println!("bodu debugger, h shows the commands");
+    let hook = Arc::new(debugger::Debugger::new(file.clone(), breakpoints));
+    execute(file, contents, path.parent().unwrap().to_path_buf(), debug, args, false, Some(hook), default_limits()).await;
+}
+
+async fn eval(code: String, debug: bool, args: Vec<String>, hook: Option<Arc<dyn VmHook>>, limits: Arc<Limits>) {
+    execute("<eval>".to_string(), code.into_bytes(), std::env::current_dir().unwrap(), debug, args, true, hook, limits).await;
+}
+
+// runs a script or bytecode, curdir is where imports are relative to. with print_result the value of the last expression is printed, unless it's null.
+// the hook, if there's one, watches the code from the first instruction
+#[allow(clippy::too_many_arguments)]
+async fn execute(name: String, contents: Vec<u8>, curdir: PathBuf, debug: bool, args: Vec<String>, print_result: bool, hook: Option<Arc<dyn VmHook>>, limits: Arc<Limits>) -> ! {
+    let mut debug = debug;
+    let (instrs, dbg) = if is_bytecode(&contents) {
+        let bytecode = match decompile(&contents) {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("Error while loading bytecode from {}: {}", name, e);
+                std::process::exit(1);
+            },
+        };
+        debug |= bytecode.debug;
+        (bytecode.instrs, bytecode.dbg.map(Arc::new))
+    } else {
+        let contents = match String::from_utf8(contents) {
+            Ok(c) => c,
+            Err(_) => {
+                eprintln!("Error while reading {}: not valid UTF-8", name);
+                std::process::exit(1);
+            },
+        };
+        let parsed = if print_result {
+            parse_snippet(&name, &contents)
+        } else {
+            parse(Source::new(name, contents), false)
+        };
+        match parsed {
+            Ok((instrs, dbg)) => (instrs, Some(Arc::new(dbg))),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            },
+        }
+    };
+    let state = new_global_state(debug, curdir).await;
+    init_global_state(state.clone(), args).await;
+    {
+        let st = state.lock().await;
+        let gd = &mut *st.globaldata.as_ref().unwrap().lock().await;
+        gd.hook = hook.clone();
+        gd.limits = Some(limits);
     }
-    let instrs = s4(contents).unwrap();
-    if D {
-        println!("S4: {:#?}", instrs);
+    let f = make_function(state.clone(), instrs, None, dbg).await.unwrap();
+    match call(state.clone(), f, vec![]).await {
+        Ok(v) => {
+            if print_result && !matches!(*v.lock().await, Value::Null) {
+                match to_string_base(state.clone(), v).await {
+                    Ok(v) => println!("{}", v),
+                    Err(e) => {
+                        eprintln!("{}", runtime_error(state.clone(), e).await);
+                        quit(&hook, 1);
+                    },
+                }
+            }
+        },
+        Err(e) if is_exit(e.clone()).await => exit(state.clone(), &hook).await,
+        Err(e) => {
+            eprintln!("{}", runtime_error(state.clone(), e).await);
+            quit(&hook, 1);
+        },
     }
-    let path = PathBuf::from(file);
-    let path = std::fs::canonicalize(path).unwrap();
-    let state = new_global_state(debug, path.parent().unwrap().to_path_buf()).await;
-    init_global_state(state.clone(), args).await;
-    let f = make_function(state.clone(), instrs, None).await.unwrap();
-    call(state.clone(), f, vec![]).await.unwrap();
     {
         let state = state.clone();
         tokio::spawn(async move {
@@ -107,116 +500,474 @@ async fn interpret(file: String, debug: bool, args: Vec<String>) {
             run_gdefers(state.clone()).await;
         });
     }
-    graceful(state.clone()).await;
+    let code = graceful(state.clone()).await;
+    quit(&hook, code as i32);
 }
 
-async fn compile(input: String, output: String) {
-    let contents = std::fs::read_to_string(input).unwrap();
-    let contents = s1(contents).unwrap();
-    if D {
-        println!("S1: {:#?}", contents);
+// only the depth is limited when no limits are given, so runaway recursion throws instead of using up the memory
+fn default_limits() -> Arc<Limits> {
+    Arc::new(Limits::new(None, Some(MAX_DEPTH), None))
+}
+
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let secs = s.parse::<f64>().map_err(|e| e.to_string())?;
+    Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())
+}
+
+// the hook finishes, a profiler writes its report for example, before the process exits
+fn quit(hook: &Option<Arc<dyn VmHook>>, code: i32) -> ! {
+    if let Some(hook) = hook {
+        hook.finish();
     }
-    let contents = s2(contents).unwrap();
-    if D {
-        println!("S2: {:#?}", contents);
+    std::process::exit(code)
+}
+
+async fn compile(input: String, output: String, debug: bool, debug_info: bool) {
+    let contents = std::fs::read_to_string(input.clone()).unwrap();
+    let (instrs, dbg) = match parse(Source::new(input, contents), false) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        },
+    };
+    let contents = compile_instrs(instrs, debug, if debug_info {
+        Some(&dbg)
+    } else {
+        None
+    });
+    std::fs::write(output, contents).unwrap();
+}
+
+async fn check(paths: Vec<String>) {
+    let mut files = Vec::new();
+    let mut missing = 0; // paths that couldn't be read
+    for path in paths {
+        if let Err(e) = find_files(PathBuf::from(&path), &mut files) {
+            eprintln!("Error while reading {}: {}", path, e);
+            missing += 1;
+        }
     }
-    let contents = s3(contents).unwrap();
-    if D {
-        println!("S3: {:#?}", contents);
+    let mut errors = missing;
+    let mut failed = missing;
+    for file in files.iter() {
+        let n = check_file(file);
+        if n > 0 {
+            errors += n;
+            failed += 1;
+        }
     }
-    let instrs = s4(contents).unwrap();
-    if D {
-        println!("S4: {:#?}", instrs);
+    if failed > 0 {
+        eprintln!("{} error(s) in {} of {} file(s)", errors, failed, files.len() + missing);
+        std::process::exit(1);
     }
-    let contents = compile_instrs(instrs);
-    std::fs::write(output, contents).unwrap();
+    println!("no errors in {} file(s)", files.len());
 }
 
-async fn repl(debug: bool) {
-    println!("Welcome to the Bodu REPL!");
-    let state = new_global_state(debug, std::env::current_dir().unwrap()).await;
-    init_global_state(state.clone(), Vec::new()).await;
-    let s = new_state(state.clone()).await;
-    let mut rl = DefaultEditor::new().unwrap();
-    loop {
-        let line = rl.readline(">> ");
-        match line {
-            Ok(line) => {
-                rl.add_history_entry(line.as_str()).unwrap();
-                let line = match s1(line) {
-                    Ok(s) => s,
-                    Err(s) => {
-                        println!("Error while parsing (S1): {}", s);
-                        continue;
-                    },
-                };
-                if D {
-                    println!("S1: {:#?}", line);
-                }
-                let line = match s2(line) {
-                    Ok(s) => s,
-                    Err(s) => {
-                        println!("Error while parsing (S2): {}", s);
-                        continue;
-                    },
-                };
-                if D {
-                    println!("S2: {:#?}", line);
-                }
-                let line = match s3(line) {
-                    Ok(s) => s,
-                    Err(s) => {
-                        println!("Error while parsing (S3): {}", s);
-                        continue;
-                    },
-                };
-                if D {
-                    println!("S3: {:#?}", line);
-                }
-                let line = match s4(line) {
-                    Ok(s) => s,
-                    Err(s) => {
-                        println!("Error while parsing (S4): {}", s);
-                        continue;
-                    },
-                };
-                if D {
-                    println!("S4: {:#?}", line);
-                }
-                let f = match make_function(state.clone(), line, Some(s.clone())).await {
-                    Ok(f) => f,
-                    Err(e) => {
-                        let e = to_string_base(state.clone(), e).await.unwrap();
-                        println!("Error while compiling into function: {}", e);
-                        continue;
-                    },
-                };
-                match call(state.clone(), f, vec![]).await {
-                    Ok(_) => {},
-                    Err(e) => {
-                        let e = match to_string_base(state.clone(), e).await {
-                            Ok(e) => e,
-                            Err(_) => {
-                                println!("Error while converting error to string.");
-                                continue;
-                            },
-                        };
-                        println!("Runtime error: {}", e);
-                        continue;
-                    },
-                }
+// directories are searched recursively for .bodu files, files are always checked
+fn find_files(path: PathBuf, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        std::fs::metadata(&path)?;
+        files.push(path);
+        return Ok(());
+    }
+    let mut entries = std::fs::read_dir(&path)?.map(|e| e.map(|e| e.path())).collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            find_files(entry, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == "bodu") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+// prints the errors of a file and returns how many there are
+fn check_file(file: &PathBuf) -> usize {
+    let name = file.display().to_string();
+    let contents = match std::fs::read(file) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", name, e);
+            return 1;
+        },
+    };
+    if is_bytecode(&contents) {
+        return match decompile(&contents) {
+            Ok(_) => 0,
+            Err(e) => {
+                eprintln!("Error while loading bytecode from {}: {}", name, e);
+                1
             },
-            _ => break,
+        };
+    }
+    let contents = match String::from_utf8(contents) {
+        Ok(c) => c,
+        Err(_) => {
+            eprintln!("Error while reading {}: not valid UTF-8", name);
+            return 1;
+        },
+    };
+    let source = Source::new(name, contents);
+    let errors = bodu_script::check(source.clone());
+    for e in errors.iter() {
+        eprintln!("{}", e.report(&source));
+    }
+    errors.len()
+}
+
+fn fmt(paths: Vec<String>, check: bool) {
+    let mut files = Vec::new();
+    let mut failed = false;
+    for path in paths {
+        if path == "-" {
+            failed |= !fmt_stdin();
+            continue;
+        }
+        if let Err(e) = find_files(PathBuf::from(&path), &mut files) {
+            eprintln!("Error while reading {}: {}", path, e);
+            failed = true;
         }
     }
-    {
-        let state = state.clone();
-        tokio::spawn(async move {
-            tokio::signal::ctrl_c().await.unwrap();
-            run_gdefers(state.clone()).await;
+    for file in files.iter() {
+        failed |= !fmt_file(file, check);
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn fmt_stdin() -> bool {
+    let mut contents = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut contents) {
+        eprintln!("Error while reading stdin: {}", e);
+        return false;
+    }
+    match bodu_script::format::format(&contents) {
+        Ok(formatted) => {
+            print!("{}", formatted);
+            true
+        },
+        Err(e) => {
+            eprintln!("Error while formatting: {}", e.report(&Source::new("<stdin>".to_string(), contents)));
+            false
+        },
+    }
+}
+
+// returns false if the file couldn't be formatted, or with check if it isn't formatted
+fn fmt_file(file: &PathBuf, check: bool) -> bool {
+    let name = file.display().to_string();
+    let contents = match std::fs::read_to_string(file) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", name, e);
+            return false;
+        },
+    };
+    let formatted = match bodu_script::format::format(&contents) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error while formatting: {}", e.report(&Source::new(name, contents)));
+            return false;
+        },
+    };
+    if formatted == contents {
+        return true;
+    }
+    if check {
+        eprintln!("{} isn't formatted", name);
+        return false;
+    }
+    if let Err(e) = std::fs::write(file, formatted) {
+        eprintln!("Error while writing {}: {}", name, e);
+        return false;
+    }
+    true
+}
+
+// the rules turned off by a config file
+fn lint_config(file: &str) -> Vec<String> {
+    let fail = |e: String| -> ! {
+        eprintln!("Error while reading {}: {}", file, e);
+        std::process::exit(1);
+    };
+    let contents = std::fs::read_to_string(file).unwrap_or_else(|e| fail(e.to_string()));
+    let config: serde_json::Value = serde_json::from_str(&contents).unwrap_or_else(|e| fail(e.to_string()));
+    let disabled = match config.get("disabled") {
+        Some(serde_json::Value::Array(v)) => v,
+        Some(_) => fail("disabled must be an array of rule names".to_string()),
+        None => return Vec::new(),
+    };
+    disabled.iter().map(|rule| match rule.as_str() {
+        Some(rule) if bodu_script::lint::RULES.contains(&rule) => rule.to_string(),
+        _ => fail(format!("{} isn't a rule, the rules are {}", rule, bodu_script::lint::RULES.join(", "))),
+    }).collect()
+}
+
+async fn lint(paths: Vec<String>, disabled: Vec<String>, json: bool) {
+    let mut files = Vec::new();
+    let mut failed = false;
+    for path in paths {
+        if let Err(e) = find_files(PathBuf::from(&path), &mut files) {
+            eprintln!("Error while reading {}: {}", path, e);
+            failed = true;
+        }
+    }
+    // assigning to a name the standard library already has doesn't make a new global
+    let globals = {
+        let state = new_global_state(false, std::env::current_dir().unwrap()).await;
+        init_global_state(state.clone(), Vec::new()).await;
+        let scope = state.lock().await.scope.clone();
+        match &*scope.lock().await {
+            Value::Object(obj) => obj.props.keys().cloned().collect::<Vec<_>>(),
+            _ => Vec::new(),
+        }
+    };
+    let mut found = Vec::new();
+    for file in files.iter() {
+        let name = file.display().to_string();
+        let contents = match std::fs::read_to_string(file) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Error while reading {}: {}", name, e);
+                failed = true;
+                continue;
+            },
+        };
+        let ast = s1(contents.clone()).and_then(s2).and_then(s3);
+        let ast = match ast {
+            Ok(ast) => ast,
+            Err(e) => {
+                eprintln!("Error while parsing: {}", e.report(&Source::new(name, contents)));
+                failed = true;
+                continue;
+            },
+        };
+        for lint in bodu_script::lint::lint(&ast, &globals) {
+            if !disabled.iter().any(|rule| rule == lint.rule) {
+                found.push((name.clone(), lint));
+            }
+        }
+    }
+    write_out(|out| {
+        if json {
+            let found = found.iter().map(|(file, lint)| serde_json::json!({
+                "file": file,
+                "rule": lint.rule,
+                "message": lint.message,
+                "span": lint.span,
+            })).collect::<Vec<_>>();
+            writeln!(out, "{}", serde_json::to_string_pretty(&found).unwrap())?;
+        } else {
+            for (file, lint) in found.iter() {
+                match lint.span {
+                    Some(span) => writeln!(out, "{}:{}:{}: {}: {}", file, span.line, span.col, lint.rule, lint.message)?,
+                    None => writeln!(out, "{}: {}: {}", file, lint.rule, lint.message)?,
+                }
+            }
+        }
+        Ok(())
+    });
+    if failed || !found.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+// runs every stage up to the one asked for, the earlier ones are needed to get there, and prints that one
+fn dump(file: String, stage: &str, json: bool) {
+    let contents = if file == "-" {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents).map(|_| contents)
+    } else {
+        std::fs::read_to_string(&file)
+    };
+    let contents = match contents {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", file, e);
+            std::process::exit(1);
+        },
+    };
+    let source = Source::new(if file == "-" {
+        "<stdin>".to_string()
+    } else {
+        file
+    }, contents);
+    let fail = |stage: &str, e: ParseError| -> ! {
+        eprintln!("Error while parsing ({}): {}", stage, e.report(&source));
+        std::process::exit(1);
+    };
+    let tokens = s1(source.contents.clone()).unwrap_or_else(|e| fail("S1", e));
+    if stage == "tokens" {
+        print_tokens(&tokens, json);
+        return;
+    }
+    let tokens = s2(tokens).unwrap_or_else(|e| fail("S2", e));
+    if stage == "s2" {
+        print_tokens(&tokens, json);
+        return;
+    }
+    let ast = s3(tokens).unwrap_or_else(|e| fail("S3", e));
+    if stage == "ast" {
+        write_out(|out| if json {
+            writeln!(out, "{}", serde_json::to_string_pretty(&ast).unwrap())
+        } else {
+            writeln!(out, "{:#?}", ast)
         });
+        return;
     }
-    graceful(state.clone()).await;
+    let (instrs, _) = s4(ast, source.clone()).unwrap_or_else(|e| fail("S4", e));
+    write_out(|out| if json {
+        writeln!(out, "{}", serde_json::to_string_pretty(&instrs).unwrap())
+    } else {
+        write!(out, "{}", disassemble(&instrs))
+    });
+}
+
+// everything is written through one lock of stdout. when the reader goes away (bodu dump x | head) the output stops quietly
+fn write_out(f: impl FnOnce(&mut StdoutLock) -> std::io::Result<()>) {
+    let mut out = std::io::stdout().lock();
+    match f(&mut out).and_then(|_| out.flush()) {
+        Ok(()) => {},
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {},
+        Err(e) => {
+            eprintln!("Error while writing the output: {}", e);
+            std::process::exit(1);
+        },
+    }
+}
+
+// one token per line, or a JSON array of {"token", "span"}
+fn print_tokens<T: Serialize + std::fmt::Debug>(tokens: &[(T, Span)], json: bool) {
+    write_out(|out| {
+        if json {
+            let tokens = tokens.iter().map(|(t, span)| serde_json::json!({
+                "token": t,
+                "span": span,
+            })).collect::<Vec<_>>();
+            writeln!(out, "{}", serde_json::to_string_pretty(&tokens).unwrap())?;
+        } else {
+            for (t, span) in tokens {
+                writeln!(out, "{}:{}\t{:?}", span.line, span.col, t)?;
+            }
+        }
+        Ok(())
+    });
+}
+
+async fn disasm(file: String) {
+    let contents = match std::fs::read(&file) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", file, e);
+            std::process::exit(1);
+        },
+    };
+    if is_bytecode(&contents) {
+        let bytecode = match decompile(&contents) {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("Error while loading bytecode from {}: {}", file, e);
+                std::process::exit(1);
+            },
+        };
+        println!("; bytecode format {}, compiled by bodu {} in {} mode{}", bytecode.version, bytecode.bodu_version, if bytecode.debug {
+            "debug"
+        } else {
+            "release"
+        }, if bytecode.dbg.is_some() {
+            ", with debug info"
+        } else {
+            ""
+        });
+        print!("{}", disassemble(&bytecode.instrs));
+    } else {
+        let contents = match String::from_utf8(contents) {
+            Ok(c) => c,
+            Err(_) => {
+                eprintln!("Error while reading {}: not valid UTF-8", file);
+                std::process::exit(1);
+            },
+        };
+        match parse(Source::new(file, contents), false) {
+            Ok((instrs, _)) => print!("{}", disassemble(&instrs)),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            },
+        }
+    }
+}
+
+async fn asm(input: String, output: String, debug: bool) {
+    let contents = match std::fs::read_to_string(&input) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", input, e);
+            std::process::exit(1);
+        },
+    };
+    let instrs = match assemble(&contents) {
+        Ok(instrs) => instrs,
+        Err(e) => {
+            eprintln!("Error while assembling {}: {}", input, e);
+            std::process::exit(1);
+        },
+    };
+    let contents = compile_instrs(instrs, debug, None);
+    if let Err(e) = std::fs::write(&output, contents) {
+        eprintln!("Error while writing {}: {}", output, e);
+        std::process::exit(1);
+    }
+}
+
+// with eval the value of the last expression is returned
+fn parse(source: Arc<Source>, eval: bool) -> Result<(Vec<Instruction>, DebugInfo), String> {
+    let contents = s1(source.contents.clone()).map_err(|e| format!("Error while parsing (S1): {}", e.report(&source)))?;
+    let contents = s2(contents).map_err(|e| format!("Error while parsing (S2): {}", e.report(&source)))?;
+    let contents = s3(contents).map_err(|e| format!("Error while parsing (S3): {}", e.report(&source)))?;
+    let s4 = if eval {
+        s4_eval
+    } else {
+        s4
+    };
+    s4(contents, source.clone()).map_err(|e| format!("Error while parsing (S4): {}", e.report(&source)))
+}
+
+// code from bodu eval, run -e or the repl. the value of the last expression is returned, and the ; at the end can be left out
+fn parse_snippet(name: &str, code: &str) -> Result<(Vec<Instruction>, DebugInfo), String> {
+    match parse(Source::new(name.to_string(), code.to_string()), true) {
+        Ok(v) => Ok(v),
+        Err(e) => parse(Source::new(name.to_string(), format!("{};", code.trim_end())), true).map_err(|_| e),
+    }
+}
+
+// the error message and, if it's known, where the error was thrown and its stack trace
+async fn runtime_error(state: StateContainer, e: Container) -> String {
+    let trace = {
+        let state = &mut *state.lock().await;
+        let gd = &mut *state.globaldata.as_mut().unwrap().lock().await;
+        gd.errtrace.take()
+    };
+    let msg = match to_string_base(state.clone(), e.clone()).await {
+        Ok(msg) => msg,
+        Err(_) => "(couldn't convert the error to a string)".to_string(),
+    };
+    let trace = match trace {
+        Some(trace) if Arc::ptr_eq(&e, &trace.error) => trace,
+        _ => return format!("Runtime error: {}", msg),
+    };
+    let mut s = match trace.frames.first().and_then(|f| f.location.clone()) {
+        Some((source, span)) => format!("Runtime error: {}", source.report(Some(span), &msg)),
+        None => format!("Runtime error: {}", msg),
+    };
+    s.push_str("\nstack trace (most recent call first):");
+    for frame in trace.frames {
+        s.push_str(&format!("\n    at {}", frame.describe()));
+    }
+    s
 }
 
 async fn graceful(state: StateContainer) -> u8 {
@@ -224,18 +975,26 @@ async fn graceful(state: StateContainer) -> u8 {
         let t = {
             let threads = &mut *state.lock().await;
             let threads = &mut *threads.globaldata.as_mut().unwrap().lock().await;
-            if threads.threads.len() == 0 {
+            if threads.threads.is_empty() {
                 break;
             }
             threads.threads.remove(0)
         };
         t.await.unwrap()
     }
-    {
-        let threads = &mut *state.lock().await;
-        let threads = &mut *threads.globaldata.as_mut().unwrap().lock().await;
-        threads.exitcode
-    }
+    exit_code(state).await
+}
+
+async fn exit_code(state: StateContainer) -> u8 {
+    let threads = &mut *state.lock().await;
+    let threads = &mut *threads.globaldata.as_mut().unwrap().lock().await;
+    threads.exitcode
+}
+
+// exit() was called: its defers already ran while it unwound, the gdefers run now
+async fn exit(state: StateContainer, hook: &Option<Arc<dyn VmHook>>) -> ! {
+    run_gdefers(state.clone()).await;
+    quit(hook, exit_code(state).await as i32)
 }
 
 async fn run_gdefers(state: StateContainer) {
@@ -0,0 +1,210 @@
+use std::{collections::HashMap, io::Write, path::PathBuf, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use bodu_vm::{Container, DebugInfo, HookFuture, StateContainer, Step, VmHook};
+
+#[derive(Default, Debug)]
+struct FnStats {
+    calls: u64,
+    inclusive: Duration,
+    exclusive: Duration,
+    instrs: u64,
+}
+
+#[derive(Default, Debug)]
+struct LineStats {
+    hits: u64, // how many times the code got to the line from another one
+    inclusive: Duration,
+    exclusive: Duration,
+    instrs: u64,
+}
+
+// a function being called. the time of the line it's on is counted from line_start, children is the time spent in the functions it called
+#[derive(Debug)]
+struct Active {
+    function: Arc<str>,
+    start: Instant,
+    children: Duration,
+    line: Option<(Arc<str>, usize)>,
+    line_start: Instant,
+    line_children: Duration,
+}
+
+#[derive(Default, Debug)]
+struct Inner {
+    stack: Vec<Active>,
+    functions: HashMap<Arc<str>, FnStats>,
+    lines: HashMap<(Arc<str>, usize), LineStats>,
+    stacks: HashMap<String, Duration>, // the exclusive time of every stack, the functions are joined with ;
+}
+
+// counts the calls, time and instructions of every bodu function and line. the time of native functions goes to the
+// bodu code that called them, and code running in async functions isn't told apart from the rest
+#[derive(Debug)]
+pub struct Profiler {
+    output: PathBuf, // where the collapsed stacks are written
+    inner: Mutex<Inner>,
+}
+
+// how many lines the report shows
+const LINES: usize = 30;
+
+impl Profiler {
+    pub fn new(output: PathBuf) -> Profiler {
+        Profiler {
+            output,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+}
+
+// the time of the line being run goes to it. outer are the calls active was called by, if one of them is on the same
+// line (a recursive call) the time is already in the inclusive time of that line
+fn end_line(lines: &mut HashMap<(Arc<str>, usize), LineStats>, outer: &[Active], active: &mut Active, now: Instant) {
+    if let Some(line) = active.line.take() {
+        let elapsed = now - active.line_start;
+        let recursive = outer.iter().any(|a| a.line.as_ref() == Some(&line));
+        let stats = lines.entry(line).or_default();
+        if !recursive {
+            stats.inclusive += elapsed;
+        }
+        stats.exclusive += elapsed.saturating_sub(active.line_children);
+    }
+    active.line_start = now;
+    active.line_children = Duration::ZERO;
+}
+
+// the function returned, active was just taken from the stack
+fn close(inner: &mut Inner, mut active: Active, now: Instant) {
+    end_line(&mut inner.lines, &inner.stack, &mut active, now);
+    let elapsed = now - active.start;
+    let exclusive = elapsed.saturating_sub(active.children);
+    // a recursive call is already in the inclusive time of the outer call
+    let recursive = inner.stack.iter().any(|a| a.function == active.function);
+    let stats = inner.functions.entry(active.function.clone()).or_default();
+    if !recursive {
+        stats.inclusive += elapsed;
+    }
+    stats.exclusive += exclusive;
+    let mut path = inner.stack.iter().map(|a| a.function.as_ref()).collect::<Vec<_>>();
+    path.push(&active.function);
+    let path = path.join(";");
+    *inner.stacks.entry(path).or_default() += exclusive;
+    if let Some(parent) = inner.stack.last_mut() {
+        parent.children += elapsed;
+        parent.line_children += elapsed;
+    }
+}
+
+// name (file:line), the line is where the function starts
+fn function_name(dbg: &Option<Arc<DebugInfo>>) -> Arc<str> {
+    match dbg {
+        Some(dbg) => {
+            let name = dbg.name.clone().unwrap_or_else(|| "<anonymous>".to_string());
+            match dbg.spans.iter().flatten().next() {
+                Some(span) => format!("{} ({}:{})", name, dbg.source.name, span.line).into(),
+                None => format!("{} ({})", name, dbg.source.name).into(),
+            }
+        },
+        None => "<unknown>".into(),
+    }
+}
+
+fn ms(d: Duration) -> String {
+    format!("{:.3}ms", d.as_secs_f64() * 1000.0)
+}
+
+impl VmHook for Profiler {
+    fn step<'a>(&'a self, step: Step<'a>) -> HookFuture<'a> {
+        Box::pin(async move {
+            let now = Instant::now();
+            let line = step.location().map(|(source, span)| (source.name.as_str().into(), span.line));
+            let inner = &mut *self.inner.lock().unwrap();
+            let (active, outer) = match inner.stack.split_last_mut() {
+                Some(a) => a,
+                None => return Ok(()),
+            };
+            inner.functions.entry(active.function.clone()).or_default().instrs += 1;
+            let same = active.line == line;
+            end_line(&mut inner.lines, outer, active, now);
+            if let Some(line) = line {
+                let stats = inner.lines.entry(line.clone()).or_default();
+                stats.instrs += 1;
+                if !same {
+                    stats.hits += 1;
+                }
+                active.line = Some(line);
+            }
+            Ok(())
+        })
+    }
+
+    fn enter<'a>(&'a self, _state: StateContainer, dbg: Option<Arc<DebugInfo>>) -> HookFuture<'a> {
+        Box::pin(async move {
+            let function = function_name(&dbg);
+            let now = Instant::now();
+            let inner = &mut *self.inner.lock().unwrap();
+            inner.functions.entry(function.clone()).or_default().calls += 1;
+            inner.stack.push(Active {
+                function,
+                start: now,
+                children: Duration::ZERO,
+                line: None,
+                line_start: now,
+                line_children: Duration::ZERO,
+            });
+            Ok(())
+        })
+    }
+
+    fn leave<'a>(&'a self, _result: &'a Result<Container, Container>) -> HookFuture<'a> {
+        Box::pin(async move {
+            let now = Instant::now();
+            let inner = &mut *self.inner.lock().unwrap();
+            if let Some(active) = inner.stack.pop() {
+                close(inner, active, now);
+            }
+            Ok(())
+        })
+    }
+
+    // the table goes to stderr, so it isn't mixed with what the program printed, and the stacks to the output file
+    fn finish(&self) {
+        let inner = &mut *self.inner.lock().unwrap();
+        let now = Instant::now();
+        // exit() can leave functions that never returned
+        while let Some(active) = inner.stack.pop() {
+            close(inner, active, now);
+        }
+        let mut out = std::io::stderr().lock();
+        let mut functions = inner.functions.iter().collect::<Vec<_>>();
+        functions.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then_with(|| a.0.cmp(b.0)));
+        let _ = writeln!(out, "\nfunctions, by exclusive time:");
+        let _ = writeln!(out, "{:>10} {:>12} {:>12} {:>12}  function", "calls", "inclusive", "exclusive", "instrs");
+        for (name, stats) in functions {
+            let _ = writeln!(out, "{:>10} {:>12} {:>12} {:>12}  {}", stats.calls, ms(stats.inclusive), ms(stats.exclusive), stats.instrs, name);
+        }
+        let mut lines = inner.lines.iter().collect::<Vec<_>>();
+        lines.sort_by(|a, b| b.1.exclusive.cmp(&a.1.exclusive).then_with(|| a.0.cmp(b.0)));
+        let _ = writeln!(out, "\nlines, by exclusive time:");
+        let _ = writeln!(out, "{:>10} {:>12} {:>12} {:>12}  line", "hits", "inclusive", "exclusive", "instrs");
+        for ((file, line), stats) in lines.iter().take(LINES) {
+            let _ = writeln!(out, "{:>10} {:>12} {:>12} {:>12}  {}:{}", stats.hits, ms(stats.inclusive), ms(stats.exclusive), stats.instrs, file, line);
+        }
+        if lines.len() > LINES {
+            let _ = writeln!(out, "... and {} more lines", lines.len() - LINES);
+        }
+        // the collapsed stack format of flamegraph.pl and inferno, the numbers are microseconds
+        let mut stacks = inner.stacks.iter().map(|(path, time)| format!("{} {}", path, time.as_micros())).collect::<Vec<_>>();
+        stacks.sort();
+        let mut folded = stacks.join("\n");
+        folded.push('\n');
+        match std::fs::write(&self.output, folded) {
+            Ok(_) => {
+                let _ = writeln!(out, "\ncollapsed stacks written to {}", self.output.display());
+            },
+            Err(e) => {
+                let _ = writeln!(out, "\nError while writing {}: {}", self.output.display(), e);
+            },
+        }
+    }
+}
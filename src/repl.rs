@@ -0,0 +1,426 @@
+use std::{borrow::Cow, path::PathBuf, sync::Arc, time::Instant};
+
+use bodu_script::s1::{s1_recover, INVALID_IDENT_CHARS, KEYWORDS, S1T};
+use bodu_std::{init_global_state, new_global_state};
+use bodu_vm::{op::{call, is_exit, make_function, new_state, to_string_base}, Container, DebugInfo, Instruction, ObjectProp, Source, StateContainer, Value};
+use rustyline::{completion::Completer, error::ReadlineError, highlight::{CmdKind, Highlighter}, hint::Hinter, history::DefaultHistory, validate::Validator, Context, Editor, Helper};
+
+use crate::{default_limits, exit, graceful, parse, parse_snippet, run_gdefers, runtime_error};
+
+const HELP: &str = ":help          show this
+:load file     run a file in the repl
+:reset         forget everything defined so far
+:time code     run code and show how long it took
+:type code     show the type of the value of code
+:quit          leave the repl";
+
+// the global state and the scope the lines run in, :reset replaces both
+struct Repl {
+    debug: bool,
+    state: StateContainer,
+    scope: StateContainer,
+}
+
+impl Repl {
+    async fn new(debug: bool) -> Repl {
+        let state = new_global_state(debug, std::env::current_dir().unwrap()).await;
+        init_global_state(state.clone(), Vec::new()).await;
+        {
+            let st = state.lock().await;
+            st.globaldata.as_ref().unwrap().lock().await.limits = Some(default_limits());
+        }
+        let scope = new_state(state.clone()).await;
+        Repl {
+            debug,
+            state,
+            scope,
+        }
+    }
+
+    // runs parsed code in the repl scope, errors come back ready to be printed
+    async fn run(&self, code: (Vec<Instruction>, DebugInfo)) -> Result<Container, String> {
+        let f = match make_function(self.state.clone(), code.0, Some(self.scope.clone()), Some(Arc::new(code.1))).await {
+            Ok(f) => f,
+            Err(e) => {
+                let e = to_string_base(self.state.clone(), e).await.unwrap();
+                return Err(format!("Error while compiling into function: {}", e));
+            },
+        };
+        match call(self.state.clone(), f, vec![]).await {
+            Ok(v) => Ok(v),
+            Err(e) if is_exit(e.clone()).await => exit(self.state.clone(), &None).await,
+            Err(e) => Err(runtime_error(self.state.clone(), e).await),
+        }
+    }
+
+    // the value of the last expression of the code
+    async fn eval(&self, code: &str) -> Result<Container, String> {
+        self.run(parse_snippet("<repl>", code)?).await
+    }
+
+    async fn show(&self, v: Container) -> String {
+        match to_string_base(self.state.clone(), v).await {
+            Ok(s) => s,
+            Err(e) => runtime_error(self.state.clone(), e).await,
+        }
+    }
+
+    // what to print for the code: its value unless it's null, or the error
+    async fn echo(&self, code: &str) -> Option<String> {
+        match self.eval(code).await {
+            Ok(v) => {
+                if matches!(*v.lock().await, Value::Null) {
+                    return None;
+                }
+                Some(self.show(v).await)
+            },
+            Err(e) => Some(e),
+        }
+    }
+
+    // what to print for the command, None when the repl should stop
+    async fn command(&mut self, line: &str) -> Option<Vec<String>> {
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (line, ""),
+        };
+        let mut out = Vec::new();
+        match name {
+            ":help" | ":h" => out.push(HELP.to_string()),
+            ":quit" | ":q" => return None,
+            ":reset" => *self = Repl::new(self.debug).await,
+            ":load" => {
+                let r = match std::fs::read_to_string(arg) {
+                    Ok(contents) => match parse(Source::new(arg.to_string(), contents), false) {
+                        Ok(code) => self.run(code).await,
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(format!("Error while reading {}: {}", arg, e)),
+                };
+                if let Err(e) = r {
+                    out.push(e);
+                }
+            },
+            ":time" => {
+                let start = Instant::now();
+                out.extend(self.echo(arg).await);
+                out.push(format!("took {:?}", start.elapsed()));
+            },
+            ":type" => {
+                match self.eval(arg).await {
+                    Ok(v) => {
+                        let type_ = self.builtin("type").await;
+                        match call(self.state.clone(), type_, vec![v]).await {
+                            Ok(t) => out.push(self.show(t).await),
+                            Err(e) => out.push(runtime_error(self.state.clone(), e).await),
+                        }
+                    },
+                    Err(e) => out.push(e),
+                }
+            },
+            _ => out.push(format!("unknown command {}, :help shows the commands", name)),
+        }
+        Some(out)
+    }
+
+    // a function of the standard library, even if the repl code shadowed it
+    async fn builtin(&self, name: &str) -> Container {
+        let gd = self.state.lock().await.globaldata.clone().unwrap();
+        let gd = gd.lock().await;
+        gd.register.get(name).unwrap().clone()
+    }
+}
+
+// completes names from the repl scope and properties after a dot, and colors the line with the s1 tokens
+struct ReplHelper {
+    scope: StateContainer,
+}
+
+impl ReplHelper {
+    // the names of every scope from the repl scope to the global scope.
+    // the states and values are only looked at if nothing else has them locked, the completion can't wait for them
+    fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut state = Some(self.scope.clone());
+        while let Some(s) = state {
+            let s = match s.try_lock() {
+                Ok(s) => s.clone(),
+                Err(_) => break,
+            };
+            names.extend(props(&s.scope));
+            state = s.parent.clone();
+        }
+        names
+    }
+
+    fn lookup(&self, name: &str) -> Option<Container> {
+        let mut state = Some(self.scope.clone());
+        while let Some(s) = state {
+            let s = s.try_lock().ok()?.clone();
+            if let Some(v) = prop(&s.scope, name) {
+                return Some(v);
+            }
+            state = s.parent.clone();
+        }
+        None
+    }
+
+    // where the completed part of the line starts and what it can be replaced with
+    fn completions(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        // the word before the cursor, dots included
+        let start = line[..pos].char_indices().rev().find(|(_, ch)| ch.is_whitespace() || (*ch != '.' && INVALID_IDENT_CHARS.contains(*ch))).map(|(i, ch)| i + ch.len_utf8()).unwrap_or(0);
+        let word = &line[start..pos];
+        let (names, prefix) = match word.rsplit_once('.') {
+            Some((path, prefix)) => {
+                let mut path = path.split('.');
+                let mut v = self.lookup(path.next().unwrap());
+                for name in path {
+                    v = v.and_then(|v| prop(&v, name));
+                }
+                (v.map(|v| props(&v)).unwrap_or_default(), prefix)
+            },
+            None => {
+                let mut names = self.names();
+                names.extend(KEYWORDS.iter().map(|k| k.to_string()));
+                (names, word)
+            },
+        };
+        let mut names = names.into_iter().filter(|name| name.starts_with(prefix)).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        (pos - prefix.len(), names)
+    }
+}
+
+fn props(v: &Container) -> Vec<String> {
+    match v.try_lock() {
+        Ok(v) => match &*v {
+            Value::Object(obj) => obj.props.keys().cloned().collect(),
+            _ => Vec::new(),
+        },
+        Err(_) => Vec::new(),
+    }
+}
+
+// getters aren't called
+fn prop(v: &Container, name: &str) -> Option<Container> {
+    match &*v.try_lock().ok()? {
+        Value::Object(obj) => match obj.props.get(name)? {
+            ObjectProp::Value(v) => Some(v.clone()),
+            ObjectProp::GetSet(_, _) => None,
+        },
+        _ => None,
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.completions(line, pos))
+    }
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        let (tokens, _) = s1_recover(line.to_string());
+        let mut s = String::new();
+        let mut last = 0;
+        for (t, span) in tokens {
+            let color = match t {
+                t if t.is_keyword() => "\x1b[35m", // magenta
+                S1T::String(_) => "\x1b[32m", // green
+                S1T::IntLiteral(_, _) | S1T::FloatLiteral(_, _) => "\x1b[33m", // yellow
+                _ => continue,
+            };
+            s.push_str(&line[last..span.start]);
+            s.push_str(color);
+            s.push_str(&line[span.start..span.end]);
+            s.push_str("\x1b[0m");
+            last = span.end;
+        }
+        s.push_str(&line[last..]);
+        Cow::Owned(s)
+    }
+
+    fn highlight_char(&self, _: &str, _: usize, kind: CmdKind) -> bool {
+        kind != CmdKind::MoveCursor
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+// more lines are needed while a bracket is open or a string isn't finished
+fn unfinished(code: &str) -> bool {
+    let (tokens, errors) = s1_recover(code.to_string());
+    if errors.iter().any(|e| e.message == "unfinished string literal") {
+        return true;
+    }
+    let mut depth = 0;
+    for (t, _) in tokens {
+        match t {
+            S1T::OpenBrace | S1T::OpenParen | S1T::OpenBrack => depth += 1,
+            S1T::CloseBrace | S1T::CloseParen | S1T::CloseBrack => depth -= 1,
+            _ => {},
+        }
+    }
+    depth > 0
+}
+
+// $BODU_HISTORY, or .bodu_history in the home directory
+fn history_file() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("BODU_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".bodu_history"))
+}
+
+pub async fn repl(debug: bool) {
+    println!("Welcome to the Bodu REPL! Type :help for the commands.");
+    let mut repl = Repl::new(debug).await;
+    let mut rl: Editor<ReplHelper, DefaultHistory> = Editor::new().unwrap();
+    rl.set_helper(Some(ReplHelper {
+        scope: repl.scope.clone(),
+    }));
+    let history = history_file();
+    if let Some(history) = &history {
+        let _ = rl.load_history(history); // there's no history the first time
+    }
+    let mut code = String::new();
+    loop {
+        let prompt = if code.is_empty() {
+            ">> "
+        } else {
+            ".. "
+        };
+        let line = match rl.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                code.clear(); // Ctrl+C drops what was typed so far
+                continue;
+            },
+            Err(_) => break,
+        };
+        if code.is_empty() && line.trim_start().starts_with(':') {
+            let _ = rl.add_history_entry(line.trim());
+            match repl.command(line.trim()).await {
+                Some(out) => {
+                    for line in out {
+                        println!("{}", line);
+                    }
+                },
+                None => break,
+            }
+            if let Some(helper) = rl.helper_mut() {
+                helper.scope = repl.scope.clone(); // :reset made a new one
+            }
+            continue;
+        }
+        code.push_str(&line);
+        code.push('\n');
+        if unfinished(&code) {
+            continue;
+        }
+        let code = std::mem::take(&mut code);
+        if code.trim().is_empty() {
+            continue;
+        }
+        let _ = rl.add_history_entry(code.trim_end());
+        if let Some(out) = repl.echo(&code).await {
+            println!("{}", out);
+        }
+    }
+    if let Some(history) = &history && let Err(e) = rl.save_history(history) {
+        eprintln!("Error while saving the history to {}: {}", history.display(), e);
+    }
+    {
+        let state = repl.state.clone();
+        tokio::spawn(async move {
+            tokio::signal::ctrl_c().await.unwrap();
+            run_gdefers(state.clone()).await;
+        });
+    }
+    let code = graceful(repl.state.clone()).await;
+    std::process::exit(code as i32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unfinished, Repl, ReplHelper};
+
+    #[test]
+    fn unfinished_while_a_bracket_is_open() {
+        assert!(unfinished("fn f() {\n"));
+        assert!(unfinished("print(1,\n"));
+        assert!(unfinished("if x { loop { break; }\n"));
+        assert!(!unfinished("fn f() { return 1; }\n"));
+        assert!(!unfinished("print(1);\n"));
+        // closing too much isn't waiting for more, the error is shown
+        assert!(!unfinished("}\n"));
+    }
+
+    #[test]
+    fn unfinished_while_a_string_is_open() {
+        assert!(unfinished("print(\"a\n"));
+        assert!(!unfinished("print(\"a\");\n"));
+        // brackets in strings don't count
+        assert!(!unfinished("print(\"{\");\n"));
+    }
+
+    #[tokio::test]
+    async fn type_shows_the_type_of_the_value() {
+        let mut repl = Repl::new(false).await;
+        assert_eq!(repl.command(":type 1 + 1").await, Some(vec!["number".to_string()]));
+        assert_eq!(repl.command(":type \"a\"").await, Some(vec!["string".to_string()]));
+        // even when the code has its own type
+        repl.echo("let type = 5;").await;
+        assert_eq!(repl.command(":type type").await, Some(vec!["number".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn time_shows_the_value_and_how_long_it_took() {
+        let mut repl = Repl::new(false).await;
+        let out = repl.command(":time 6 * 7").await.unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0], "42");
+        assert!(out[1].starts_with("took "), "{}", out[1]);
+        // nothing is shown for null
+        assert_eq!(repl.command(":time null").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn commands() {
+        let mut repl = Repl::new(false).await;
+        assert_eq!(repl.command(":quit").await, None);
+        assert_eq!(repl.command(":nope").await, Some(vec!["unknown command :nope, :help shows the commands".to_string()]));
+        repl.echo("let x = 1;").await;
+        assert_eq!(repl.echo("x").await, Some("1".to_string()));
+        assert_eq!(repl.command(":reset").await, Some(Vec::new()));
+        // x is forgotten, so it's null
+        assert_eq!(repl.echo("x").await, None);
+    }
+
+    #[tokio::test]
+    async fn completes_members_after_a_dot() {
+        let repl = Repl::new(false).await;
+        let helper = ReplHelper {
+            scope: repl.scope.clone(),
+        };
+        let (start, names) = helper.completions("print(math.fl", 13);
+        assert_eq!(start, 11);
+        assert_eq!(names, ["floor"]);
+        let (start, names) = helper.completions("math.", 5);
+        assert_eq!(start, 5);
+        assert!(names.contains(&"sqrt".to_string()) && names.contains(&"floor".to_string()), "{:?}", names);
+        // names and keywords without a dot
+        let (start, names) = helper.completions("pri", 3);
+        assert_eq!((start, names), (0, vec!["print".to_string()]));
+        assert!(helper.completions("whi", 3).1.contains(&"while".to_string()));
+    }
+}
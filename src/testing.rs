@@ -0,0 +1,216 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, pin::Pin, sync::Arc, time::{Duration, Instant}};
+
+use bodu_std::{init_global_state, new_global_state};
+use bodu_vm::{make_container, make_err, op::{call, is_exit, make_function, new_state, set_base, to_string_base}, Container, DebugInfo, ErrorKind, Function, Gi, Instruction, ObjectProp, Source, StateContainer, Value, VmHook};
+
+use crate::{default_limits, find_files, graceful, parse, run_gdefers, runtime_error};
+
+#[derive(Default)]
+struct Summary {
+    passed: usize,
+    failed: usize,
+    filtered: usize,
+}
+
+// runs the tests of every *_test.bodu file, directories are searched recursively and files are always run.
+// the hook watches every file, it finishes after the summary
+pub async fn test(paths: Vec<String>, filter: Option<String>, debug: bool, hook: Option<Arc<dyn VmHook>>) {
+    let start = Instant::now();
+    let mut files = Vec::new();
+    let mut summary = Summary::default();
+    for path in paths {
+        let path = PathBuf::from(&path);
+        let found = if path.is_dir() {
+            let mut found = Vec::new();
+            let r = find_files(path.clone(), &mut found);
+            found.retain(|f| f.file_name().is_some_and(|name| name.to_string_lossy().ends_with("_test.bodu")));
+            r.map(|_| found)
+        } else {
+            std::fs::metadata(&path).map(|_| vec![path.clone()])
+        };
+        match found {
+            Ok(found) => files.extend(found),
+            Err(e) => {
+                eprintln!("Error while reading {}: {}", path.display(), e);
+                summary.failed += 1;
+            },
+        }
+    }
+    for file in files.iter() {
+        test_file(file, filter.as_deref(), debug, &hook, &mut summary).await;
+    }
+    println!("\n{} passed, {} failed, {} filtered out ({})", summary.passed, summary.failed, summary.filtered, duration(start.elapsed()));
+    if let Some(hook) = &hook {
+        hook.finish();
+    }
+    if summary.failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn duration(d: Duration) -> String {
+    format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+}
+
+// every test runs in a global state of its own: the file runs in a new one before each test, so what a test changes
+// isn't seen by the next one. the tests are the ones registered with test(name, fn) in order, then the test_* functions
+// the file declared sorted by name
+async fn test_file(file: &PathBuf, filter: Option<&str>, debug: bool, hook: &Option<Arc<dyn VmHook>>, summary: &mut Summary) {
+    let name = file.display().to_string();
+    let contents = match std::fs::read_to_string(file) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error while reading {}: {}", name, e);
+            summary.failed += 1;
+            return;
+        },
+    };
+    let (instrs, dbg) = match parse(Source::new(name.clone(), contents), false) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            summary.failed += 1;
+            return;
+        },
+    };
+    let dbg = Arc::new(dbg);
+    let curdir = std::fs::canonicalize(file).ok().and_then(|p| p.parent().map(|p| p.to_path_buf())).unwrap_or_else(|| std::env::current_dir().unwrap());
+    println!("{}", name);
+    let (state, tests) = match load(&name, &instrs, &dbg, debug, &curdir, hook).await {
+        Some(loaded) => loaded,
+        None => {
+            summary.failed += 1;
+            return;
+        },
+    };
+    let names = tests.iter().map(|t| t.0.clone()).collect::<Vec<_>>();
+    // the state the tests were found in is used for the first test that runs
+    let mut fresh = Some((state, tests));
+    for (i, test) in names.iter().enumerate() {
+        if let Some(filter) = filter && !test.contains(filter) {
+            summary.filtered += 1;
+            continue;
+        }
+        let (state, tests) = match fresh.take() {
+            Some(loaded) => loaded,
+            None => match load(&name, &instrs, &dbg, debug, &curdir, hook).await {
+                Some(loaded) => loaded,
+                None => {
+                    summary.failed += 1;
+                    continue;
+                },
+            },
+        };
+        let start = Instant::now();
+        let r = match tests.get(i) {
+            Some((n, f)) if n == test => call(state.clone(), f.clone(), vec![]).await,
+            _ => Err(make_err(ErrorKind::Value, "the file registered other tests when it ran again")),
+        };
+        let took = duration(start.elapsed());
+        match r {
+            Ok(_) => {
+                println!("ok   {} ({})", test, took);
+                summary.passed += 1;
+            },
+            Err(e) => {
+                println!("FAIL {} ({})", test, took);
+                println!("{}", indent(&failure(state.clone(), e).await));
+                summary.failed += 1;
+            },
+        }
+        finish(state).await;
+    }
+    if let Some((state, _)) = fresh {
+        finish(state).await;
+    }
+}
+
+// runs the file in a new global state, that state and the names and functions of the tests the file has
+async fn load(name: &str, instrs: &[Instruction], dbg: &Arc<DebugInfo>, debug: bool, curdir: &Path, hook: &Option<Arc<dyn VmHook>>) -> Option<(StateContainer, Vec<(String, Container)>)> {
+    let state = new_global_state(debug, curdir.to_path_buf()).await;
+    init_global_state(state.clone(), Vec::new()).await;
+    {
+        let st = state.lock().await;
+        let gd = &mut *st.globaldata.as_ref().unwrap().lock().await;
+        gd.hook = hook.clone();
+        gd.limits = Some(default_limits());
+    }
+    let registered = make_container(Value::Tuple(Vec::new()));
+    {
+        let mut internals = HashMap::new();
+        internals.insert(0, registered.clone());
+        let test = make_container(Value::Function(Function {
+            internals,
+            call: register,
+            state: state.clone(),
+            caller_state: false,
+        }));
+        let global = state.lock().await.scope.clone();
+        set_base(state.clone(), global, "test".to_string(), test).await.unwrap();
+    }
+    let scope = new_state(state.clone()).await;
+    let f = make_function(state.clone(), instrs.to_vec(), Some(scope.clone()), Some(dbg.clone())).await.unwrap();
+    if let Err(e) = call(state.clone(), f, vec![]).await {
+        println!("FAIL {} couldn't run", name);
+        println!("{}", indent(&failure(state.clone(), e).await));
+        finish(state).await;
+        return None;
+    }
+    let mut tests = Vec::new();
+    let registered = match &*registered.lock().await {
+        Value::Tuple(v) => v.clone(),
+        _ => Vec::new(),
+    };
+    for t in registered {
+        if let Value::Tuple(v) = &*t.lock().await {
+            tests.push((to_string_base(state.clone(), v[0].clone()).await.unwrap_or_default(), v[1].clone()));
+        }
+    }
+    let mut declared = Vec::new();
+    let props = match &*scope.lock().await.scope.lock().await {
+        Value::Object(obj) => obj.props.clone(),
+        _ => HashMap::new(),
+    };
+    for (name, prop) in props {
+        if let ObjectProp::Value(v) = prop && name.starts_with("test_") && matches!(&*v.lock().await, Value::Function(_)) {
+            declared.push((name, v));
+        }
+    }
+    declared.sort_by(|a, b| a.0.cmp(&b.0));
+    tests.extend(declared);
+    Some((state, tests))
+}
+
+async fn finish(state: StateContainer) {
+    graceful(state.clone()).await;
+    run_gdefers(state).await;
+}
+
+async fn failure(state: StateContainer, e: Container) -> String {
+    if is_exit(e.clone()).await {
+        return "exit was called".to_string();
+    }
+    runtime_error(state, e).await
+}
+
+fn indent(s: &str) -> String {
+    s.lines().map(|l| format!("     {}", l)).collect::<Vec<_>>().join("\n")
+}
+
+// test(name, fn) adds a test to the tuple in the internals
+fn register(state: StateContainer, args: Vec<Container>, gi: Gi) -> Pin<Box<dyn std::future::Future<Output = Result<Container, Container>> + Send>> {
+    Box::pin(async move {
+        if args.len() < 2 {
+            return Err(make_err(ErrorKind::Argument, "test requires 2 arguments"));
+        }
+        if !matches!(&*args[1].lock().await, Value::Function(_)) {
+            return Err(make_err(ErrorKind::Type, "test requires a name and a function"));
+        }
+        let name = make_container(Value::String(to_string_base(state.clone(), args[0].clone()).await?));
+        let tests = gi(0).unwrap();
+        if let Value::Tuple(v) = &mut *tests.lock().await {
+            v.push(make_container(Value::Tuple(vec![name, args[1].clone()])));
+        }
+        Ok(make_container(Value::Null))
+    })
+}
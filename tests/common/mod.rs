@@ -0,0 +1,127 @@
+#![allow(dead_code)] // every test file uses a different part of this
+
+use std::{io::{BufRead, BufReader, Read, Write}, path::PathBuf, process::{Child, ChildStdin, Command, Stdio}, sync::mpsc::{channel, Receiver}, time::Duration};
+
+use serde_json::Value;
+
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    pub code: Option<i32>,
+}
+
+impl Output {
+    pub fn ok(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+// runs bodu with the arguments in dir, stdin is written to it and closed
+pub fn bodu_in(dir: &PathBuf, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bodu"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    Output {
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+        code: output.status.code(),
+    }
+}
+
+pub fn bodu(args: &[&str], stdin: &str) -> Output {
+    bodu_in(&std::env::current_dir().unwrap(), args, stdin)
+}
+
+// an empty directory for one test, with the files in it
+pub fn dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bodu-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (file, contents) in files {
+        std::fs::write(dir.join(file), contents).unwrap();
+    }
+    dir
+}
+
+// a running bodu lsp or bodu dap, the messages go both ways as JSON after a Content-Length header
+pub struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+}
+
+impl Session {
+    pub fn start(dir: &PathBuf, args: &[&str]) -> Session {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bodu"))
+            .args(args)
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let (tx, messages) = channel();
+        std::thread::spawn(move || {
+            loop {
+                let mut len = 0;
+                loop {
+                    let mut line = String::new();
+                    if stdout.read_line(&mut line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    match line.trim_end().strip_prefix("Content-Length: ") {
+                        Some(n) => len = n.parse().unwrap(),
+                        None if line.trim_end().is_empty() => break,
+                        None => {},
+                    }
+                }
+                let mut body = vec![0; len];
+                if stdout.read_exact(&mut body).is_err() || tx.send(serde_json::from_slice(&body).unwrap()).is_err() {
+                    return;
+                }
+            }
+        });
+        Session {
+            child,
+            stdin,
+            messages,
+        }
+    }
+
+    pub fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    // the next message, it fails if none comes in 10 seconds
+    pub fn next(&mut self) -> Value {
+        self.messages.recv_timeout(Duration::from_secs(10)).expect("bodu didn't send a message")
+    }
+
+    // skips the messages before the first one f is true for
+    pub fn until(&mut self, f: impl Fn(&Value) -> bool) -> Value {
+        loop {
+            let message = self.next();
+            if f(&message) {
+                return message;
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,47 @@
+mod common;
+
+// the lcov file has a record for the imported file too, with the lines that never ran at 0
+#[test]
+fn covers_imported_files() {
+    let dir = common::dir("coverage", &[
+        ("lib.bodu", "fn double(n) {\n    return n * 2;\n}\nfn unused() {\n    return 0;\n}\nreturn double;\n"),
+        ("main.bodu", "let double = import(\"lib.bodu\");\nprint(double(2));\n"),
+    ]);
+    let out = common::bodu_in(&dir, &["run", "--coverage", "main.bodu"], "");
+    assert!(out.ok(), "{}", out.stderr);
+    assert_eq!(out.stdout, "4\n");
+    assert!(out.stderr.contains("lib.bodu, never ran: 5\n"), "{}", out.stderr);
+    assert!(out.stderr.ends_with("lcov written to lcov.info\n"), "{}", out.stderr);
+    let lcov = std::fs::read_to_string(dir.join("lcov.info")).unwrap();
+    let path = |file: &str| dir.join(file).canonicalize().unwrap().display().to_string();
+    let expected = [
+        "TN:".to_string(),
+        format!("SF:{}", path("lib.bodu")),
+        "DA:1,1".to_string(),
+        "DA:2,1".to_string(),
+        "DA:4,1".to_string(),
+        "DA:5,0".to_string(),
+        "DA:7,1".to_string(),
+        "LF:5".to_string(),
+        "LH:4".to_string(),
+        "end_of_record".to_string(),
+        "TN:".to_string(),
+        format!("SF:{}", path("main.bodu")),
+        "DA:1,1".to_string(),
+        "DA:2,1".to_string(),
+        "LF:2".to_string(),
+        "LH:2".to_string(),
+        "end_of_record".to_string(),
+    ];
+    assert_eq!(lcov.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn writes_to_the_file_given() {
+    let dir = common::dir("coverage-file", &[("a.bodu", "print(1);\n")]);
+    let out = common::bodu_in(&dir, &["run", "--coverage=out.info", "a.bodu"], "");
+    assert!(out.ok(), "{}", out.stderr);
+    assert!(!dir.join("lcov.info").exists());
+    let lcov = std::fs::read_to_string(dir.join("out.info")).unwrap();
+    assert!(lcov.contains("DA:1,1\nLF:1\nLH:1\nend_of_record\n"), "{}", lcov);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use serde_json::{json, Value};
+
+const PROGRAM: &str = "fn f(a) {\n    let b = a * 2;\n    print(b);\n    return b;\n}\nlet r = f(21);\nprint(r);\n";
+
+struct Client {
+    session: common::Session,
+    seq: u64,
+}
+
+impl Client {
+    // sends a request and returns its response, the events before it are skipped
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let seq = self.seq;
+        self.session.send(json!({"seq": seq, "type": "request", "command": command, "arguments": arguments}));
+        let response = self.session.until(|m| m["type"] == "response" && m["request_seq"] == seq);
+        assert_eq!(response["success"], true, "{}", response);
+        response["body"].clone()
+    }
+
+    fn event(&mut self, event: &str) -> Value {
+        self.session.until(|m| m["type"] == "event" && m["event"] == event)["body"].clone()
+    }
+}
+
+#[test]
+fn stops_at_a_breakpoint_and_runs_to_the_end() {
+    let dir = common::dir("dap", &[("a.bodu", PROGRAM)]);
+    let program = dir.join("a.bodu").canonicalize().unwrap().display().to_string();
+    let mut client = Client {
+        session: common::Session::start(&dir, &["dap"]),
+        seq: 0,
+    };
+    let capabilities = client.request("initialize", json!({"adapterID": "bodu"}));
+    assert_eq!(capabilities["supportsConfigurationDoneRequest"], true);
+    client.event("initialized");
+    let breakpoints = client.request("setBreakpoints", json!({"source": {"path": program}, "breakpoints": [{"line": 3}]}));
+    assert_eq!(breakpoints["breakpoints"], json!([{"verified": true, "line": 3}]));
+    client.request("launch", json!({"program": program}));
+    client.request("configurationDone", json!({}));
+
+    let stopped = client.event("stopped");
+    assert_eq!(stopped["reason"], "breakpoint");
+    let frames = client.request("stackTrace", json!({"threadId": stopped["threadId"]}));
+    let frames = frames["stackFrames"].as_array().unwrap();
+    assert_eq!(frames.iter().map(|f| (f["name"].as_str().unwrap(), f["line"].as_u64().unwrap())).collect::<Vec<_>>(), [("f", 3), ("<top level>", 6)]);
+    assert_eq!(frames[0]["source"]["path"], program);
+
+    let scopes = client.request("scopes", json!({"frameId": frames[0]["id"]}));
+    let locals = scopes["scopes"].as_array().unwrap().iter().find(|s| s["name"] == "Locals").unwrap().clone();
+    let variables = client.request("variables", json!({"variablesReference": locals["variablesReference"]}));
+    let b = variables["variables"].as_array().unwrap().iter().find(|v| v["name"] == "b").unwrap().clone();
+    assert_eq!(b["value"], "42");
+
+    let evaluated = client.request("evaluate", json!({"expression": "b + 1", "frameId": frames[0]["id"]}));
+    assert_eq!(evaluated["result"], "43");
+
+    client.request("continue", json!({"threadId": stopped["threadId"]}));
+    assert_eq!(client.event("exited")["exitCode"], 0);
+    client.event("terminated");
+}
@@ -0,0 +1,33 @@
+mod common;
+
+// the commands are read from stdin like they were typed
+#[test]
+fn breaks_steps_and_prints() {
+    let dir = common::dir("debugger", &[("a.bodu", "fn f(a) {\n    let b = a * 2;\n    return b;\n}\nlet r = f(21);\nprint(r);\n")]);
+    let out = common::bodu_in(&dir, &["debug", "a.bodu"], "b 2\nc\nbt\ns\np b\np a + 1\nc\n");
+    assert!(out.ok(), "{}", out.stderr);
+    let expected = [
+        "bodu debugger, h shows the commands",
+        "stopped at a.bodu:1:1 (step)",
+        ">    1 | fn f(a) {",
+        "(bodu debug) breakpoint at a.bodu:2",
+        "(bodu debug) stopped at a.bodu:2:5 (breakpoint)",
+        ">    2 |     let b = a * 2;",
+        "(bodu debug)   #0 f at a.bodu:2:5",
+        "  #1 <top level> at a.bodu:5:1",
+        "(bodu debug) stopped at a.bodu:3:5 (step)",
+        ">    3 |     return b;",
+        "(bodu debug) 42",
+        "(bodu debug) 22",
+        "(bodu debug) 42",
+    ];
+    assert_eq!(out.stdout.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn quit_stops_the_program() {
+    let dir = common::dir("debugger-quit", &[("a.bodu", "print(1);\nprint(2);\n")]);
+    let out = common::bodu_in(&dir, &["debug", "a.bodu"], "n\nq\n");
+    assert!(out.ok(), "{}", out.stderr);
+    assert!(out.stdout.ends_with("(bodu debug) 1\nstopped at a.bodu:2:1 (step)\n>    2 | print(2);\n(bodu debug) "), "{}", out.stdout);
+}
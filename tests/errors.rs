@@ -0,0 +1,33 @@
+mod common;
+
+// errors say file:line:col and show the line with carets under the statement
+#[test]
+fn runtime_errors_have_the_location_and_an_excerpt() {
+    let dir = common::dir("runtime-error", &[("a.bodu", "let x = 1;\nfn f(a) {\n    return a.b.c;\n}\nf(null);\n")]);
+    let out = common::bodu_in(&dir, &["run", "a.bodu"], "");
+    assert_eq!(out.code, Some(1));
+    let lines = out.stderr.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("Runtime error: a.bodu:3:5: type error:"), "{}", out.stderr);
+    assert_eq!(lines[1..4], ["  |", "3 |     return a.b.c;", "  |     ^^^^^^^^^^^^^"]);
+    assert_eq!(lines[4..7], ["stack trace (most recent call first):", "    at f (a.bodu:3:5)", "    at <top level> (a.bodu:5:1)"]);
+}
+
+#[test]
+fn parse_errors_have_the_location_and_an_excerpt() {
+    let dir = common::dir("parse-error", &[("a.bodu", "let x = 1;\nlet y = (2;\n")]);
+    let out = common::bodu_in(&dir, &["run", "a.bodu"], "");
+    assert_eq!(out.code, Some(1));
+    assert_eq!(out.stderr, "Error while parsing (S3): a.bodu:2:11: couldn't parse\n  |\n2 | let y = (2;\n  |           ^\n");
+}
+
+// bytecode compiled with --debug-info keeps the locations
+#[test]
+fn compiled_errors_have_locations_with_debug_info() {
+    let dir = common::dir("compiled-error", &[("a.bodu", "print(1);\nthrow \"boom\";\n")]);
+    assert!(common::bodu_in(&dir, &["compile", "a.bodu", "plain.bc"], "").ok());
+    assert!(common::bodu_in(&dir, &["compile", "-g", "a.bodu", "debug.bc"], "").ok());
+    let out = common::bodu_in(&dir, &["run", "debug.bc"], "");
+    assert!(out.stderr.starts_with("Runtime error: a.bodu:2:1: boom\n  |\n2 | throw \"boom\";\n"), "{}", out.stderr);
+    let out = common::bodu_in(&dir, &["run", "plain.bc"], "");
+    assert!(out.stderr.starts_with("Runtime error: boom\n"), "{}", out.stderr);
+}
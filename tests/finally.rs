@@ -0,0 +1,151 @@
+mod common;
+
+// runs the code with bodu run -, what it printed and whether it exited with 0
+fn run(code: &str) -> (String, bool) {
+    let output = common::bodu(&["run", "-"], code);
+    let ok = output.ok();
+    (output.stdout, ok)
+}
+
+fn lines(out: &str) -> Vec<&str> {
+    out.lines().collect()
+}
+
+#[test]
+fn runs_after_the_try_block() {
+    let (out, ok) = run(r#"
+        try { print("try"); } finally { print("finally"); }
+        print("after");
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["try", "finally", "after"]);
+}
+
+#[test]
+fn runs_on_return() {
+    let (out, ok) = run(r#"
+        fn f() {
+            try {
+                return 1;
+            } finally {
+                print("finally");
+            }
+            return 2;
+        }
+        print(f());
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["finally", "1"]);
+}
+
+#[test]
+fn runs_on_return_through_nested_finally_blocks() {
+    let (out, ok) = run(r#"
+        fn f() {
+            try {
+                try {
+                    return "try";
+                } finally {
+                    print("inner");
+                }
+            } finally {
+                print("outer");
+            }
+        }
+        print(f());
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["inner", "outer", "try"]);
+}
+
+#[test]
+fn return_in_finally_replaces_the_return_value() {
+    let (out, ok) = run(r#"
+        fn f() {
+            try {
+                return "try";
+            } finally {
+                return "finally";
+            }
+        }
+        print(f());
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["finally"]);
+}
+
+#[test]
+fn runs_on_throw_and_the_error_keeps_going() {
+    let (out, ok) = run(r#"
+        fn f() {
+            try {
+                throw "boom";
+            } finally {
+                print("finally");
+            }
+            print("not reached");
+        }
+        try { f(); } catch e { print("caught", e); }
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["finally", "caught\tboom"]);
+}
+
+#[test]
+fn runs_on_an_uncaught_throw() {
+    let (out, ok) = run(r#"
+        try { throw "boom"; } finally { print("finally"); }
+        print("not reached");
+    "#);
+    assert!(!ok);
+    assert_eq!(lines(&out), ["finally"]);
+}
+
+#[test]
+fn runs_when_the_catch_block_throws() {
+    let (out, ok) = run(r#"
+        try {
+            try { throw "a"; } catch e { throw "b"; } finally { print("finally"); }
+        } catch e {
+            print("caught", e);
+        }
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["finally", "caught\tb"]);
+}
+
+#[test]
+fn runs_on_break_and_continue() {
+    let (out, ok) = run(r#"
+        let n = 0;
+        loop {
+            try {
+                n = n + 1;
+                if n == 3 { break; }
+                continue;
+            } finally {
+                print("finally", n);
+            }
+        }
+        print("after", n);
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["finally\t1", "finally\t2", "finally\t3", "after\t3"]);
+}
+
+#[test]
+fn runs_on_goto_and_out() {
+    let (out, ok) = run(r#"
+        try {
+            goto #after;
+        } finally {
+            print("finally goto");
+        }
+        print("not reached");
+        #after
+        let v = try { out 5; } finally { print("finally out"); };
+        print(v);
+    "#);
+    assert!(ok);
+    assert_eq!(lines(&out), ["finally goto", "finally out", "5"]);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+#[test]
+fn max_steps_stops_a_loop() {
+    let out = common::bodu(&["run", "--max-steps", "200", "-"], "loop {}\n");
+    assert_eq!(out.code, Some(1));
+    assert!(out.stderr.contains("limit error: the code ran more than 200 instructions"), "{}", out.stderr);
+}
+
+// the first catch gets to handle the limit error, after that it's thrown again
+#[test]
+fn max_steps_lets_one_catch_block_run() {
+    let out = common::bodu(&["run", "--max-steps", "200", "-"], r#"
+        try { loop {} } catch e { print(e.kind); }
+        try { loop {} } catch e { print("caught again"); }
+        print("not reached");
+    "#);
+    assert_eq!(out.code, Some(1));
+    assert_eq!(out.stdout, "limit\n");
+    assert!(out.stderr.contains("limit error: the code ran more than 200 instructions"), "{}", out.stderr);
+}
+
+#[test]
+fn runaway_recursion_can_be_caught() {
+    let out = common::bodu(&["run", "-"], r#"
+        fn f(n) { return f(n + 1); }
+        try { f(0); } catch e { print(e.kind, e.message); }
+        print("after");
+    "#);
+    assert!(out.ok(), "{}", out.stderr);
+    assert_eq!(out.stdout, "limit\tmore than 1000 function calls inside each other\nafter\n");
+}
+
+#[test]
+fn max_depth_can_be_lowered() {
+    let out = common::bodu(&["run", "--max-depth", "10", "-"], r#"
+        let depth = 0;
+        fn f() { depth = depth + 1; f(); }
+        try { f(); } catch e { print(e.kind, depth); }
+    "#);
+    assert!(out.ok(), "{}", out.stderr);
+    assert_eq!(out.stdout, "limit\t9\n");
+}
+
+#[test]
+fn timeout_stops_a_loop() {
+    let out = common::bodu(&["run", "--timeout", "0.2", "-"], "loop {}\n");
+    assert_eq!(out.code, Some(1));
+    assert!(out.stderr.contains("limit error: the code ran for more than 200ms"), "{}", out.stderr);
+}
+
+#[test]
+fn timeout_lets_one_catch_block_run() {
+    let out = common::bodu(&["run", "--timeout", "0.2", "-"], r#"
+        try { loop {} } catch e { print(e.kind, e.message); }
+    "#);
+    assert!(out.ok(), "{}", out.stderr);
+    assert_eq!(out.stdout, "limit\tthe code ran for more than 200ms\n");
+}
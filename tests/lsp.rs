@@ -0,0 +1,57 @@
+mod common;
+
+use serde_json::{json, Value};
+
+const URI: &str = "file:///a.bodu";
+const TEXT: &str = "fn add(a, b) {\n    return a + b;\n}\nprint(add(1, 2));\nlet x = (1;\n";
+
+fn request(session: &mut common::Session, id: u64, method: &str, params: Value) -> Value {
+    session.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}));
+    session.until(|m| m["id"] == id)["result"].clone()
+}
+
+fn at(line: u64, character: u64) -> Value {
+    json!({"textDocument": {"uri": URI}, "position": {"line": line, "character": character}})
+}
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Value {
+    json!({"start": {"line": start.0, "character": start.1}, "end": {"line": end.0, "character": end.1}})
+}
+
+#[test]
+fn answers_over_stdio() {
+    let mut session = common::Session::start(&std::env::temp_dir(), &["lsp"]);
+    let init = request(&mut session, 1, "initialize", json!({}));
+    assert_eq!(init["capabilities"]["hoverProvider"], true);
+    session.send(json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    session.send(json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+        "textDocument": {"uri": URI, "languageId": "bodu", "version": 1, "text": TEXT},
+    }}));
+
+    // the error is at the ; where the ) should be
+    let published = session.until(|m| m["method"] == "textDocument/publishDiagnostics");
+    assert_eq!(published["params"]["uri"], URI);
+    let diagnostics = published["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["range"], range((4, 10), (4, 11)));
+    assert_eq!(diagnostics[0]["severity"], 1);
+
+    let symbols = request(&mut session, 2, "textDocument/documentSymbol", json!({"textDocument": {"uri": URI}}));
+    let symbols = symbols.as_array().unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0]["name"], "add");
+    assert_eq!(symbols[0]["detail"], "fn add(a, b)");
+    assert_eq!(symbols[0]["kind"], 12);
+    assert_eq!(symbols[0]["range"], range((0, 0), (2, 1)));
+
+    // from the call of add to its name in the fn
+    let definition = request(&mut session, 3, "textDocument/definition", at(3, 7));
+    assert_eq!(definition, json!({"uri": URI, "range": range((0, 3), (0, 6))}));
+
+    let hover = request(&mut session, 4, "textDocument/hover", at(3, 1));
+    assert_eq!(hover["range"], range((3, 0), (3, 5)));
+    let text = hover["contents"]["value"].as_str().unwrap();
+    assert!(text.starts_with("```bodu\nprint(...)\n```\n\nTakes any number of arguments"), "{}", text);
+
+    assert_eq!(request(&mut session, 5, "shutdown", Value::Null), Value::Null);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+// every line of the collapsed stacks is frame;frame;... and a count, the outermost frame first
+#[test]
+fn writes_collapsed_stacks() {
+    let dir = common::dir("profile", &[("a.bodu", r#"fn inner(n) {
+    let i = 0;
+    loop {
+        if i == n { break; }
+        i = i + 1;
+    }
+    return i;
+}
+fn outer() { return inner(100); }
+print(outer());
+"#)]);
+    let out = common::bodu_in(&dir, &["run", "--profile", "a.bodu"], "");
+    assert!(out.ok(), "{}", out.stderr);
+    assert_eq!(out.stdout, "100\n");
+    assert!(out.stderr.contains("functions, by exclusive time:"), "{}", out.stderr);
+    assert!(out.stderr.ends_with("collapsed stacks written to bodu.folded\n"), "{}", out.stderr);
+    let folded = std::fs::read_to_string(dir.join("bodu.folded")).unwrap();
+    let mut stacks = Vec::new();
+    for line in folded.lines() {
+        let (stack, count) = line.rsplit_once(' ').unwrap();
+        assert!(count.parse::<u64>().is_ok(), "{}", line);
+        assert!(stack.split(';').all(|frame| !frame.is_empty()), "{}", line);
+        stacks.push(stack);
+    }
+    stacks.sort();
+    assert_eq!(stacks, ["<top level> (a.bodu:1)", "<top level> (a.bodu:1);outer (a.bodu:9)", "<top level> (a.bodu:1);outer (a.bodu:9);inner (a.bodu:1)"]);
+}
+
+#[test]
+fn writes_to_the_file_given() {
+    let dir = common::dir("profile-file", &[("a.bodu", "print(1);\n")]);
+    let out = common::bodu_in(&dir, &["run", "--profile=out.folded", "a.bodu"], "");
+    assert!(out.ok(), "{}", out.stderr);
+    assert!(!dir.join("bodu.folded").exists());
+    let folded = std::fs::read_to_string(dir.join("out.folded")).unwrap();
+    assert!(folded.starts_with("<top level> (a.bodu:1) "), "{}", folded);
+}
@@ -0,0 +1,118 @@
+mod common;
+
+#[test]
+fn runs_registered_tests_then_declared_ones() {
+    let dir = common::dir("test-order", &[("a_test.bodu", r#"
+        test("registered", fn() { assert.eq(1 + 1, 2); });
+        fn test_b() { assert.ne(1, 2); }
+        fn test_a() { assert.throws(fn() { throw "boom"; }); }
+        fn helper() { throw "not a test"; }
+    "#)]);
+    let out = common::bodu_in(&dir, &["test"], "");
+    assert!(out.ok(), "{}{}", out.stdout, out.stderr);
+    let lines = out.stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "./a_test.bodu");
+    assert!(lines[1].starts_with("ok   registered ("), "{}", out.stdout);
+    assert!(lines[2].starts_with("ok   test_a ("), "{}", out.stdout);
+    assert!(lines[3].starts_with("ok   test_b ("), "{}", out.stdout);
+    assert!(lines[5].starts_with("3 passed, 0 failed, 0 filtered out ("), "{}", out.stdout);
+}
+
+// a test doesn't see what the one before it changed
+#[test]
+fn every_test_gets_a_fresh_global_state() {
+    let dir = common::dir("test-fresh", &[("a_test.bodu", r#"
+        let count = 0;
+        fn test_a() { count = count + 1; assert.eq(count, 1); }
+        fn test_b() { count = count + 1; assert.eq(count, 1); }
+    "#)]);
+    let out = common::bodu_in(&dir, &["test"], "");
+    assert!(out.ok(), "{}{}", out.stdout, out.stderr);
+    assert!(out.stdout.contains("2 passed, 0 failed"), "{}", out.stdout);
+}
+
+#[test]
+fn failing_tests_make_it_exit_with_1() {
+    let dir = common::dir("test-fail", &[("a_test.bodu", r#"
+        fn test_fails() { assert.eq(1, 2, "numbers"); }
+        fn test_passes() {}
+    "#)]);
+    let out = common::bodu_in(&dir, &["test"], "");
+    assert_eq!(out.code, Some(1));
+    assert!(out.stdout.contains("FAIL test_fails ("), "{}", out.stdout);
+    assert!(out.stdout.contains("     Runtime error: ./a_test.bodu:2:27: assert error: numbers: assert.eq failed: 1 != 2"), "{}", out.stdout);
+    assert!(out.stdout.contains("ok   test_passes ("), "{}", out.stdout);
+    assert!(out.stdout.contains("1 passed, 1 failed, 0 filtered out"), "{}", out.stdout);
+}
+
+#[test]
+fn filter_skips_the_other_tests() {
+    let dir = common::dir("test-filter", &[("a_test.bodu", r#"
+        fn test_one() {}
+        fn test_two() { throw "not run"; }
+    "#)]);
+    let out = common::bodu_in(&dir, &["test", "--filter", "one"], "");
+    assert!(out.ok(), "{}{}", out.stdout, out.stderr);
+    assert!(out.stdout.contains("1 passed, 0 failed, 1 filtered out"), "{}", out.stdout);
+}
+
+#[test]
+fn a_file_that_throws_fails() {
+    let dir = common::dir("test-throws", &[("a_test.bodu", "throw \"boom\";\nfn test_a() {}\n")]);
+    let out = common::bodu_in(&dir, &["test"], "");
+    assert_eq!(out.code, Some(1));
+    assert!(out.stdout.contains("FAIL ./a_test.bodu couldn't run"), "{}", out.stdout);
+    assert!(out.stdout.contains("0 passed, 1 failed"), "{}", out.stdout);
+}
+
+// assert.eq says where arrays and objects differ, one line each
+#[test]
+fn assert_eq_prints_the_differences() {
+    let dir = common::dir("test-assert-eq", &[("a_test.bodu", r#"
+        fn test_a() {
+            let x = object.new();
+            x.a = 1;
+            x.b = array.new(1, 2, 3);
+            x.c = "x";
+            let y = object.new();
+            y.a = 1;
+            y.b = array.new(1, 5);
+            y.c = "y";
+            y.d = null;
+            assert.eq(x, y);
+        }
+    "#)]);
+    let out = common::bodu_in(&dir, &["test"], "");
+    assert_eq!(out.code, Some(1));
+    let expected = [
+        "assert error: assert.eq failed, the differences are:",
+        "         .b[1]: 2 != 5",
+        "         .b[2]: 3 != nothing",
+        "         .c: \"x\" != \"y\"",
+        "         .d: nothing != null",
+    ];
+    assert!(out.stdout.contains(&expected.join("\n")), "{}", out.stdout);
+}
+
+#[test]
+fn assert_functions_fail() {
+    let dir = common::dir("test-assert", &[("a_test.bodu", r#"
+        fn test_ne() { assert.ne(array.new(1), array.new(1)); }
+        fn test_throws() { assert.throws(fn() {}); }
+        fn test_throws_kind() { assert.throws(fn() { throw "boom"; }, "type"); }
+        fn test_approx() { assert.approx(1, 1.5, 0.1); }
+        fn test_approx_ok() { assert.approx(0.1 + 0.2, 0.3); }
+    "#)]);
+    let out = common::bodu_in(&dir, &["test"], "");
+    assert_eq!(out.code, Some(1));
+    for expected in [
+        "assert error: assert.ne failed: both are [1]",
+        "assert error: assert.throws failed: the function didn't throw",
+        "assert error: assert.throws failed: expected a type error, the function threw \"boom\"",
+        "assert error: assert.approx failed: 1 and 1.5 are 0.5 apart, more than 0.1",
+    ] {
+        assert!(out.stdout.contains(expected), "{}\n{}", expected, out.stdout);
+    }
+    assert!(out.stdout.contains("ok   test_approx_ok ("), "{}", out.stdout);
+    assert!(out.stdout.contains("1 passed, 4 failed"), "{}", out.stdout);
+}